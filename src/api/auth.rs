@@ -0,0 +1,27 @@
+use axum::{
+    async_trait,
+    extract::{FromRequest, RequestParts},
+};
+
+use crate::{api::Error, models::api_key::ApiKey};
+
+/// The request's api key, rejecting requests made without an admin key.
+pub struct AdminKey(pub ApiKey);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AdminKey {
+    type Rejection = Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let api_key = req
+            .extensions()
+            .and_then(|extensions| extensions.get::<ApiKey>())
+            .cloned();
+
+        match api_key {
+            Some(api_key) if api_key.admin => Ok(Self(api_key)),
+            Some(_) => Err(Error::Forbidden),
+            None => Err(Error::Unauthorized),
+        }
+    }
+}
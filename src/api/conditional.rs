@@ -0,0 +1,186 @@
+use axum::{
+    body::{self, BoxBody},
+    http::{header, HeaderMap, HeaderValue, Response, StatusCode},
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use rosu_v2::prelude::RankStatus;
+
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Validators for a stored document, used to answer conditional requests.
+pub struct Validators {
+    etag: String,
+    last_modified: DateTime<Utc>,
+    cache_control: &'static str,
+}
+
+impl Validators {
+    pub fn new(id: u32, updated_at: DateTime<Utc>, status: RankStatus) -> Self {
+        // ranked and approved maps rarely change, so caches may hold on to them for longer, but
+        // loved maps can still be updated by their mapper
+        let cache_control = match status {
+            RankStatus::Ranked | RankStatus::Approved => "public, max-age=3600",
+            _ => "public, max-age=60",
+        };
+
+        Self {
+            etag: format!("\"{}-{}\"", id, updated_at.timestamp_millis()),
+            last_modified: updated_at,
+            cache_control,
+        }
+    }
+
+    fn is_not_modified(&self, request_headers: &HeaderMap) -> bool {
+        // if-none-match takes precedence over if-modified-since when both are sent
+        if let Some(if_none_match) = request_headers.get(header::IF_NONE_MATCH) {
+            return if_none_match.to_str().is_ok_and(|if_none_match| {
+                if_none_match.trim() == "*"
+                    || if_none_match
+                        .split(',')
+                        .map(|etag| etag.trim().trim_start_matches("W/"))
+                        .any(|etag| etag == self.etag)
+            });
+        }
+
+        request_headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|since| since.to_str().ok())
+            .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+            .is_some_and(|since| self.last_modified.timestamp() <= since.timestamp())
+    }
+
+    fn insert_headers(&self, response_headers: &mut HeaderMap) {
+        let last_modified = self.last_modified.format(HTTP_DATE_FORMAT).to_string();
+
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            response_headers.insert(header::ETAG, etag);
+        }
+        if let Ok(last_modified) = HeaderValue::from_str(&last_modified) {
+            response_headers.insert(header::LAST_MODIFIED, last_modified);
+        }
+        response_headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(self.cache_control),
+        );
+    }
+
+    /// Respond with `304 Not Modified` if the client's copy is still current, otherwise
+    /// with the given body.
+    pub fn respond<T, F>(self, request_headers: &HeaderMap, make_body: F) -> Response<BoxBody>
+    where
+        T: IntoResponse,
+        F: FnOnce() -> T,
+    {
+        let mut response = match self.is_not_modified(request_headers) {
+            true => StatusCode::NOT_MODIFIED.into_response().map(body::boxed),
+            false => make_body().into_response().map(body::boxed),
+        };
+
+        self.insert_headers(response.headers_mut());
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn validators() -> Validators {
+        let updated_at = Utc.with_ymd_and_hms(2022, 10, 1, 12, 0, 0).unwrap();
+        Validators::new(1, updated_at, RankStatus::Ranked)
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn if_none_match() {
+        let validators = validators();
+        let etag = "\"1-1664625600000\"";
+
+        for if_none_match in [
+            etag,
+            "\"1-1\", \"1-1664625600000\"",
+            "*",
+            "W/\"1-1664625600000\"",
+        ] {
+            let request = headers(&[(header::IF_NONE_MATCH, if_none_match)]);
+            assert!(validators.is_not_modified(&request), "{}", if_none_match);
+        }
+
+        let request = headers(&[(header::IF_NONE_MATCH, "\"1-1\", \"2-1664625600000\"")]);
+        assert!(!validators.is_not_modified(&request));
+    }
+
+    #[test]
+    fn if_none_match_takes_precedence() {
+        let validators = validators();
+
+        let request = headers(&[
+            (header::IF_NONE_MATCH, "\"1-1\""),
+            (header::IF_MODIFIED_SINCE, "Sat, 01 Oct 2022 12:00:00 GMT"),
+        ]);
+        assert!(!validators.is_not_modified(&request));
+
+        let request = headers(&[
+            (header::IF_NONE_MATCH, "\"1-1664625600000\""),
+            (header::IF_MODIFIED_SINCE, "Sat, 01 Oct 2022 11:00:00 GMT"),
+        ]);
+        assert!(validators.is_not_modified(&request));
+    }
+
+    #[test]
+    fn if_modified_since() {
+        let validators = validators();
+
+        let equal = headers(&[(header::IF_MODIFIED_SINCE, "Sat, 01 Oct 2022 12:00:00 GMT")]);
+        assert!(validators.is_not_modified(&equal));
+
+        let later = headers(&[(header::IF_MODIFIED_SINCE, "Sun, 02 Oct 2022 12:00:00 GMT")]);
+        assert!(validators.is_not_modified(&later));
+
+        let older = headers(&[(header::IF_MODIFIED_SINCE, "Sat, 01 Oct 2022 11:59:59 GMT")]);
+        assert!(!validators.is_not_modified(&older));
+
+        let unparseable = headers(&[(header::IF_MODIFIED_SINCE, "yesterday")]);
+        assert!(!validators.is_not_modified(&unparseable));
+
+        assert!(!validators.is_not_modified(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn sends_validators_with_every_response() {
+        let modified = validators().respond(&HeaderMap::new(), || "body");
+        let request = headers(&[(header::IF_NONE_MATCH, "\"1-1664625600000\"")]);
+        let not_modified = validators().respond(&request, || "body");
+
+        assert_eq!(modified.status(), StatusCode::OK);
+        assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+
+        for response in [modified, not_modified] {
+            let headers = response.headers();
+            assert_eq!(headers[header::ETAG], "\"1-1664625600000\"");
+            assert_eq!(
+                headers[header::LAST_MODIFIED],
+                "Sat, 01 Oct 2022 12:00:00 GMT"
+            );
+            assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=3600");
+        }
+    }
+
+    #[test]
+    fn caches_loved_maps_briefly() {
+        let updated_at = Utc.with_ymd_and_hms(2022, 10, 1, 12, 0, 0).unwrap();
+        let validators = Validators::new(1, updated_at, RankStatus::Loved);
+
+        assert_eq!(validators.cache_control, "public, max-age=60");
+    }
+}
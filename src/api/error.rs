@@ -1,5 +1,5 @@
 use axum::body::{Bytes, Full, HttpBody};
-use axum::http::{Response, StatusCode};
+use axum::http::{header, HeaderValue, Response, StatusCode};
 use axum::response::IntoResponse;
 use axum::Json;
 use std::borrow::Cow;
@@ -7,10 +7,26 @@ use std::collections::HashMap;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    /// Return `401 Unauthorized`
+    #[error("a valid api key is required")]
+    Unauthorized,
+
+    /// Return `403 Forbidden`
+    #[error("this api key is not allowed to access this resource")]
+    Forbidden,
+
     /// Return `404 Not Found`
     #[error("not found")]
     NotFound,
 
+    /// Return `429 Too Many Requests`, with the seconds until a request will be accepted again
+    #[error("too many requests")]
+    TooManyRequests { retry_after: u64 },
+
+    /// Return `451 Unavailable For Legal Reasons`, with osu!'s explanation if given
+    #[error("this beatmapset is unavailable for download")]
+    UnavailableForLegalReasons { message: Option<String> },
+
     /// Return `422 Unprocessable Entity`
     #[error("error in the request body")]
     UnprocessableEntity {
@@ -42,7 +58,11 @@ impl Error {
 
     fn status_code(&self) -> StatusCode {
         match self {
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
             Self::NotFound => StatusCode::NOT_FOUND,
+            Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::UnavailableForLegalReasons { .. } => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
             Self::UnprocessableEntity { .. } => StatusCode::UNPROCESSABLE_ENTITY,
             Self::Anyhow(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -64,6 +84,21 @@ impl IntoResponse for Error {
                 return (StatusCode::UNPROCESSABLE_ENTITY, Json(Errors { errors })).into_response();
             }
 
+            Self::UnavailableForLegalReasons {
+                message: Some(ref message),
+            } => {
+                return (self.status_code(), message.clone()).into_response();
+            }
+
+            Self::TooManyRequests { retry_after } => {
+                let mut response = (self.status_code(), self.to_string()).into_response();
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+
+                return response;
+            }
+
             Self::Anyhow(ref e) => {
                 log::error!("Generic error: {:?}", e);
             }
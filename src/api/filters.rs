@@ -0,0 +1,589 @@
+use std::borrow::Cow;
+
+use crate::{
+    api::{error, Result},
+    models::{
+        mode::Mode, ranked_status::RankedStatus, search_filters::SearchFilters,
+        search_sort::SearchSort,
+    },
+    usecases,
+};
+use rosu_v2::prelude::{GameMode, GameMods};
+
+/// Parse a comma separated list of values, where each may expand to several, rejecting any
+/// unknown ones.
+fn parse_list<T>(
+    field: &'static str,
+    value: Option<&str>,
+    default: Vec<T>,
+    parse: fn(&str) -> Option<Vec<T>>,
+    names: &[&str],
+) -> Result<Vec<T>> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(default),
+    };
+
+    let mut parsed = Vec::new();
+    let mut errors: Vec<(&'static str, Cow<'static, str>)> = Vec::new();
+
+    for item in value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        match parse(item) {
+            Some(items) => parsed.extend(items),
+            None => errors.push((
+                field,
+                format!(
+                    "unknown {} `{}`, valid values are {} or their numeric codes",
+                    field,
+                    item,
+                    names.join(", ")
+                )
+                .into(),
+            )),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(error::Error::unprocessable_entity(errors));
+    }
+
+    if parsed.is_empty() {
+        return Ok(default);
+    }
+
+    Ok(parsed)
+}
+
+/// Parse the `status` search parameter, defaulting to ranked.
+pub fn parse_statuses(value: Option<&str>) -> Result<Vec<RankedStatus>> {
+    let names = [&RankedStatus::NAMES[..], &RankedStatus::GROUP_NAMES[..]].concat();
+
+    parse_list(
+        "status",
+        value,
+        vec![RankedStatus::Ranked],
+        |value| match RankedStatus::parse_group(value) {
+            Some(group) => Some(group.to_vec()),
+            None => RankedStatus::parse(value).map(|status| vec![status]),
+        },
+        &names,
+    )
+}
+
+/// Parse a flag given as 1/0 or true/false, defaulting to false.
+pub fn parse_flag(field: &'static str, value: Option<&str>) -> Result<bool> {
+    Ok(parse_optional_flag(field, value)?.unwrap_or(false))
+}
+
+/// Parse a flag given as 1/0 or true/false, leaving it unset if it's missing or empty.
+pub fn parse_optional_flag(field: &'static str, value: Option<&str>) -> Result<Option<bool>> {
+    match value.map(|value| value.trim().to_lowercase()).as_deref() {
+        None | Some("") => Ok(None),
+        Some("0") | Some("false") => Ok(Some(false)),
+        Some("1") | Some("true") => Ok(Some(true)),
+        Some(value) => Err(error::Error::unprocessable_entity([(
+            field,
+            format!(
+                "`{}` is not a valid flag, expected 1, 0, true or false",
+                value
+            ),
+        )])),
+    }
+}
+
+/// Parse the `mode` search parameter, defaulting to all modes.
+pub fn parse_modes(value: Option<&str>) -> Result<Vec<Mode>> {
+    parse_list(
+        "mode",
+        value,
+        vec![Mode::All],
+        |value| Mode::parse(value).map(|mode| vec![mode]),
+        &Mode::NAMES,
+    )
+}
+
+/// Parse the `mods` parameter, either as acronyms like `HDDT` or as a bitwise number.
+pub fn parse_mods(value: Option<&str>) -> Result<GameMods> {
+    let value = match value.map(str::trim) {
+        Some(value) if !value.is_empty() => value,
+        _ => return Ok(GameMods::NoMod),
+    };
+
+    let mods = match value.parse::<u32>() {
+        Ok(bits) => GameMods::from_bits(bits),
+        Err(_) => value.parse::<GameMods>().ok(),
+    };
+
+    let mods = mods.ok_or_else(|| {
+        error::Error::unprocessable_entity([(
+            "mods",
+            format!(
+                "`{}` are not valid mods, expected acronyms like HDDT or their bitwise value",
+                value
+            ),
+        )])
+    })?;
+
+    // a bare nightcore bit, without the double time one it implies, speeds the map up too
+    let contradictory = (mods.contains(GameMods::Easy) && mods.contains(GameMods::HardRock))
+        || (mods.intersects(GameMods::NightCore) && mods.contains(GameMods::HalfTime));
+    if contradictory {
+        return Err(error::Error::unprocessable_entity([(
+            "mods",
+            format!(
+                "`{}` combines mods which can't be played together, like EZHR or DTHT",
+                value
+            ),
+        )]));
+    }
+
+    Ok(mods)
+}
+
+/// Parse a `mode` parameter naming a single mode, e.g. to convert a beatmap to.
+pub fn parse_game_mode(value: Option<&str>) -> Result<Option<GameMode>> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    match Mode::parse(value).and_then(Mode::game_mode) {
+        Some(mode) => Ok(Some(mode)),
+        None => Err(error::Error::unprocessable_entity([(
+            "mode",
+            format!(
+                "unknown mode `{}`, valid values are {} or their numeric codes",
+                value,
+                Mode::NAMES[1..].join(", ")
+            ),
+        )])),
+    }
+}
+
+/// Parse the `sort` search parameter.
+pub fn parse_sort(value: Option<&str>, default: SearchSort) -> Result<SearchSort> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(default),
+    };
+
+    SearchSort::parse(value).ok_or_else(|| {
+        error::Error::unprocessable_entity([(
+            "sort",
+            format!(
+                "unknown sort `{}`, valid values are {}, optionally followed by _asc or _desc",
+                value,
+                SearchSort::NAMES.join(", ")
+            ),
+        )])
+    })
+}
+
+/// Parse osu!direct's ranked filter, used in place of `status` by osu!direct clients.
+pub fn parse_direct_statuses(direct_status: i8) -> Result<Vec<RankedStatus>> {
+    usecases::beatmapsets::direct_statuses(direct_status).ok_or_else(|| {
+        error::Error::unprocessable_entity([(
+            "direct_status",
+            format!("unknown osu!direct status `{}`", direct_status),
+        )])
+    })
+}
+
+/// osu!'s genre ids by name.
+const GENRES: [(&str, u8); 13] = [
+    ("unspecified", 1),
+    ("video_game", 2),
+    ("anime", 3),
+    ("rock", 4),
+    ("pop", 5),
+    ("other", 6),
+    ("novelty", 7),
+    ("hip_hop", 9),
+    ("electronic", 10),
+    ("metal", 11),
+    ("classical", 12),
+    ("folk", 13),
+    ("jazz", 14),
+];
+
+/// osu!'s language ids by name.
+const LANGUAGES: [(&str, u8); 14] = [
+    ("other", 1),
+    ("english", 2),
+    ("japanese", 3),
+    ("chinese", 4),
+    ("instrumental", 5),
+    ("korean", 6),
+    ("french", 7),
+    ("german", 8),
+    ("swedish", 9),
+    ("spanish", 10),
+    ("italian", 11),
+    ("russian", 12),
+    ("polish", 13),
+    ("unspecified", 14),
+];
+
+fn parse_id(value: &str, ids: &[(&str, u8)]) -> Option<u8> {
+    let value = value.to_lowercase().replace([' ', '-'], "_");
+
+    ids.iter()
+        .find(|(name, id)| *name == value || id.to_string() == value)
+        .map(|(_, id)| *id)
+}
+
+/// Parse the `genre` search parameter, a comma separated list of genres.
+pub fn parse_genres(value: Option<&str>) -> Result<Vec<u8>> {
+    let names: Vec<&str> = GENRES.iter().map(|(name, _)| *name).collect();
+
+    parse_list(
+        "genre",
+        value,
+        vec![],
+        |value| parse_id(value, &GENRES).map(|genre| vec![genre]),
+        &names,
+    )
+}
+
+/// Parse the `language` search parameter, a comma separated list of languages.
+pub fn parse_languages(value: Option<&str>) -> Result<Vec<u8>> {
+    let names: Vec<&str> = LANGUAGES.iter().map(|(name, _)| *name).collect();
+
+    parse_list(
+        "language",
+        value,
+        vec![],
+        |value| parse_id(value, &LANGUAGES).map(|language| vec![language]),
+        &names,
+    )
+}
+
+/// Highest key count a mania difficulty can have (co-op maps go up to 18).
+const MAX_MANIA_KEYS: u8 = 18;
+
+fn parse_key_count(value: &str) -> Result<u8> {
+    match value.trim().trim_end_matches(['k', 'K']).parse::<u8>() {
+        Ok(keys) if (1..=MAX_MANIA_KEYS).contains(&keys) => Ok(keys),
+        _ => Err(error::Error::unprocessable_entity([(
+            "keys",
+            format!(
+                "`{}` is not a valid key count, expected 1 to {}",
+                value, MAX_MANIA_KEYS
+            ),
+        )])),
+    }
+}
+
+/// Parse the `keys` search parameter, a comma separated list of mania key counts.
+pub fn parse_keys(value: Option<&str>) -> Result<Vec<u8>> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(parse_key_count)
+        .collect()
+}
+
+/// Take `keys=N` terms out of a search query, returning the remaining query and the key
+/// counts found.
+pub fn extract_query_keys(query: Option<String>) -> Result<(Option<String>, Vec<u8>)> {
+    let query = match query {
+        Some(query) => query,
+        None => return Ok((None, vec![])),
+    };
+
+    let mut keys = Vec::new();
+    let mut terms = Vec::new();
+
+    for term in query.split_whitespace() {
+        let key_count = term
+            .strip_prefix("keys=")
+            .or_else(|| term.strip_prefix("key="));
+
+        match key_count {
+            Some(key_count) => keys.push(parse_key_count(key_count)?),
+            None => terms.push(term),
+        }
+    }
+
+    let query = match terms.is_empty() {
+        true => None,
+        false => Some(terms.join(" ")),
+    };
+
+    Ok((query, keys))
+}
+
+/// Reject star and length ranges whose lower bound is above their upper bound.
+pub fn validate_ranges(filters: &SearchFilters) -> Result<()> {
+    let mut errors = Vec::new();
+
+    if let (Some(min_stars), Some(max_stars)) = (filters.min_stars, filters.max_stars) {
+        if min_stars > max_stars {
+            errors.push(("min_stars", "min_stars must not be above max_stars"));
+        }
+    }
+
+    if let (Some(min_length), Some(max_length)) = (filters.min_length, filters.max_length) {
+        if min_length > max_length {
+            errors.push(("min_length", "min_length must not be above max_length"));
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(error::Error::unprocessable_entity(errors)),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct RandomParams {
+    /// Comma separated statuses, by name or numeric code (defaults to ranked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Comma separated modes, by name or numeric code (defaults to all)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Comma separated mania key counts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_stars: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_stars: Option<f32>,
+    /// Minimum total length in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u32>,
+    /// Maximum total length in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u32>,
+    /// Whether to include NSFW beatmapsets, as 1/0 or true/false (defaults to false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nsfw: Option<String>,
+    /// Seed to get the same pick again, as long as the matching maps don't change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    /// Whether maps with more plays are more likely to be picked, as 1/0 or true/false (defaults
+    /// to false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weighted: Option<String>,
+}
+
+impl RandomParams {
+    pub fn search_filters(&self) -> Result<SearchFilters> {
+        let search_filters = SearchFilters {
+            statuses: parse_statuses(self.status.as_deref())?,
+            modes: parse_modes(self.mode.as_deref())?,
+            keys: parse_keys(self.keys.as_deref())?,
+            include_nsfw: parse_flag("nsfw", self.nsfw.as_deref())?,
+            min_stars: self.min_stars,
+            max_stars: self.max_stars,
+            min_length: self.min_length,
+            max_length: self.max_length,
+            ..Default::default()
+        };
+        validate_ranges(&search_filters)?;
+
+        Ok(search_filters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fields a result was rejected for, panicking if it wasn't a 422.
+    fn rejected_fields<T: std::fmt::Debug>(result: Result<T>) -> Vec<String> {
+        match result {
+            Err(error::Error::UnprocessableEntity { errors }) => {
+                let mut fields: Vec<String> = errors.keys().map(|key| key.to_string()).collect();
+                fields.sort();
+                fields
+            }
+            other => panic!("expected an unprocessable entity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_statuses_and_groups() {
+        assert_eq!(parse_statuses(None).unwrap(), vec![RankedStatus::Ranked]);
+        assert_eq!(
+            parse_statuses(Some("")).unwrap(),
+            vec![RankedStatus::Ranked]
+        );
+        assert_eq!(
+            parse_statuses(Some("loved, -2")).unwrap(),
+            vec![RankedStatus::Loved, RankedStatus::Graveyard]
+        );
+        assert_eq!(
+            parse_statuses(Some("has_leaderboard")).unwrap(),
+            RankedStatus::HAS_LEADERBOARD.to_vec()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_statuses() {
+        assert_eq!(
+            rejected_fields(parse_statuses(Some("ranked,nope"))),
+            ["status"]
+        );
+    }
+
+    #[test]
+    fn parses_flags() {
+        assert!(!parse_flag("novideo", None).unwrap());
+        assert!(!parse_flag("novideo", Some("")).unwrap());
+        assert!(!parse_flag("novideo", Some("0")).unwrap());
+        assert!(!parse_flag("novideo", Some("False")).unwrap());
+        assert!(parse_flag("novideo", Some("1")).unwrap());
+        assert!(parse_flag("novideo", Some(" TRUE ")).unwrap());
+        assert_eq!(
+            rejected_fields(parse_flag("novideo", Some("yes"))),
+            ["novideo"]
+        );
+        assert_eq!(
+            rejected_fields(parse_flag("novideo", Some("2"))),
+            ["novideo"]
+        );
+    }
+
+    #[test]
+    fn parses_optional_flags() {
+        assert_eq!(parse_optional_flag("video", None).unwrap(), None);
+        assert_eq!(parse_optional_flag("video", Some(" ")).unwrap(), None);
+        assert_eq!(
+            parse_optional_flag("video", Some("0")).unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            parse_optional_flag("video", Some("true")).unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            rejected_fields(parse_optional_flag("video", Some("maybe"))),
+            ["video"]
+        );
+    }
+
+    #[test]
+    fn parses_modes() {
+        assert_eq!(parse_modes(None).unwrap(), vec![Mode::All]);
+        assert_eq!(
+            parse_modes(Some("osu,3")).unwrap(),
+            vec![Mode::Standard, Mode::Mania]
+        );
+        assert_eq!(rejected_fields(parse_modes(Some("piano"))), ["mode"]);
+    }
+
+    #[test]
+    fn parses_a_single_game_mode() {
+        assert_eq!(parse_game_mode(None).unwrap(), None);
+        assert_eq!(
+            parse_game_mode(Some("fruits")).unwrap(),
+            Some(GameMode::Catch)
+        );
+        assert_eq!(rejected_fields(parse_game_mode(Some("all"))), ["mode"]);
+    }
+
+    #[test]
+    fn parses_mods_as_acronyms_or_bits() {
+        assert_eq!(parse_mods(None).unwrap(), GameMods::NoMod);
+        assert_eq!(parse_mods(Some(" ")).unwrap(), GameMods::NoMod);
+        assert_eq!(
+            parse_mods(Some("HDDT")).unwrap(),
+            GameMods::Hidden | GameMods::DoubleTime
+        );
+        assert_eq!(
+            parse_mods(Some("72")).unwrap(),
+            GameMods::Hidden | GameMods::DoubleTime
+        );
+        assert_eq!(rejected_fields(parse_mods(Some("XYZ"))), ["mods"]);
+        assert_eq!(rejected_fields(parse_mods(Some("EZHR"))), ["mods"]);
+        assert_eq!(rejected_fields(parse_mods(Some("HDDTHT"))), ["mods"]);
+        assert_eq!(rejected_fields(parse_mods(Some("NCHT"))), ["mods"]);
+        assert_eq!(rejected_fields(parse_mods(Some("768"))), ["mods"]);
+    }
+
+    #[test]
+    fn parses_sorts() {
+        assert_eq!(
+            parse_sort(None, SearchSort::default()).unwrap(),
+            SearchSort::default()
+        );
+        assert!(
+            !parse_sort(Some("title_asc"), SearchSort::default())
+                .unwrap()
+                .descending
+        );
+        assert_eq!(
+            rejected_fields(parse_sort(Some("length"), SearchSort::default())),
+            ["sort"]
+        );
+    }
+
+    #[test]
+    fn parses_direct_statuses() {
+        assert_eq!(parse_direct_statuses(8).unwrap(), vec![RankedStatus::Loved]);
+        assert_eq!(rejected_fields(parse_direct_statuses(1)), ["direct_status"]);
+    }
+
+    #[test]
+    fn parses_genres_and_languages_by_name_or_id() {
+        assert_eq!(parse_genres(Some("Hip Hop, 10")).unwrap(), vec![9, 10]);
+        assert_eq!(parse_languages(Some("japanese")).unwrap(), vec![3]);
+        assert_eq!(parse_genres(None).unwrap(), Vec::<u8>::new());
+        assert_eq!(rejected_fields(parse_genres(Some("8"))), ["genre"]);
+    }
+
+    #[test]
+    fn parses_key_counts() {
+        assert_eq!(parse_keys(Some("4k, 7")).unwrap(), vec![4, 7]);
+        assert_eq!(parse_keys(None).unwrap(), Vec::<u8>::new());
+        assert_eq!(rejected_fields(parse_keys(Some("0"))), ["keys"]);
+        assert_eq!(rejected_fields(parse_keys(Some("19k"))), ["keys"]);
+    }
+
+    #[test]
+    fn extracts_keys_from_queries() {
+        assert_eq!(
+            extract_query_keys(Some("camellia keys=7 ghost".to_string())).unwrap(),
+            (Some("camellia ghost".to_string()), vec![7])
+        );
+        assert_eq!(
+            extract_query_keys(Some("key=4K".to_string())).unwrap(),
+            (None, vec![4])
+        );
+        assert_eq!(extract_query_keys(None).unwrap(), (None, vec![]));
+        assert_eq!(
+            rejected_fields(extract_query_keys(Some("keys=many".to_string()))),
+            ["keys"]
+        );
+    }
+
+    #[test]
+    fn rejects_inverted_ranges() {
+        let filters = SearchFilters {
+            min_stars: Some(6.0),
+            max_stars: Some(5.0),
+            min_length: Some(300),
+            max_length: Some(60),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            rejected_fields(validate_ranges(&filters)),
+            ["min_length", "min_stars"]
+        );
+
+        let filters = SearchFilters {
+            min_stars: Some(5.0),
+            max_stars: Some(5.0),
+            ..Default::default()
+        };
+        assert!(validate_ranges(&filters).is_ok());
+    }
+}
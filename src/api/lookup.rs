@@ -0,0 +1,50 @@
+use crate::api::{error, Result};
+
+/// Upper bound on how many ids can be looked up in a single request.
+pub const MAX_LOOKUP_IDS: usize = 100;
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct LookupBody {
+    pub ids: Vec<u32>,
+}
+
+/// Parse ids given as `ids[]=1&ids[]=2` (or `ids=1&ids=2`) from a query string.
+pub fn ids_from_query(query: Option<&str>) -> Result<Vec<u32>> {
+    let mut ids = Vec::new();
+
+    for (key, value) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        if key != "ids[]" && key != "ids" {
+            continue;
+        }
+
+        match value.parse() {
+            Ok(id) => ids.push(id),
+            Err(_) => {
+                return Err(error::Error::unprocessable_entity([(
+                    "ids",
+                    format!("{} is not a valid id", value),
+                )]))
+            }
+        }
+    }
+
+    Ok(ids)
+}
+
+pub fn validate_ids(ids: &[u32]) -> Result<()> {
+    if ids.is_empty() {
+        return Err(error::Error::unprocessable_entity([(
+            "ids",
+            "at least one id is required",
+        )]));
+    }
+
+    if ids.len() > MAX_LOOKUP_IDS {
+        return Err(error::Error::unprocessable_entity([(
+            "ids",
+            format!("at most {} ids can be looked up at once", MAX_LOOKUP_IDS),
+        )]));
+    }
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
-use crate::Context;
+use crate::{helpers::elastic, Context};
 use anyhow::Context as AnyhowContext;
 use axum::{
     body::{Bytes, Full},
@@ -11,7 +11,13 @@ use axum::{
 use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 
+pub mod auth;
+pub mod conditional;
 pub mod error;
+pub mod filters;
+pub mod lookup;
+pub mod pagination;
+pub mod rate_limit;
 pub mod routes;
 
 use crate::models;
@@ -19,6 +25,7 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::Config;
 
 pub use error::Error;
+use rate_limit::{RateLimitLayer, TrustedProxy};
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 async fn serve_swagger_ui(Path(tail): Path<String>) -> Response<Full<Bytes>> {
@@ -47,18 +54,51 @@ fn api_router() -> Router {
             routes::v1::beatmapsets::get_beatmapset,
             routes::v1::beatmapsets::search_beatmapsets,
             routes::v2::beatmaps::get_beatmap,
+            routes::v2::beatmaps::lookup_beatmaps,
+            routes::v2::beatmaps::lookup_beatmaps_by_query,
+            routes::v2::beatmaps::random_beatmap,
+            routes::v2::beatmaps::get_beatmap_attributes,
+            routes::v2::beatmaps::calculate_pp,
             routes::v2::beatmapsets::get_beatmapset,
             routes::v2::beatmapsets::search_beatmapsets,
-            routes::downloads::get_beatmapset
+            routes::v2::beatmapsets::lookup_beatmapsets,
+            routes::v2::beatmapsets::lookup_beatmapsets_by_query,
+            routes::v2::beatmapsets::random_beatmapset,
+            routes::v2::suggest::suggest,
+            routes::v2::users::get_user_beatmapsets,
+            routes::downloads::get_beatmapset,
+            routes::images::get_thumbnail,
+            routes::images::get_background,
+            routes::previews::get_preview,
+            routes::admin::refresh_beatmap,
+            routes::admin::delete_beatmap,
+            routes::admin::refresh_beatmapset,
+            routes::admin::delete_beatmapset,
+            routes::admin::refresh_archive,
+            routes::admin::set_beatmapset_hidden,
+            routes::admin::enqueue_crawl,
+            routes::admin::get_archive_stats,
+            routes::admin::create_api_key
         ),
         components(schemas(
             models::cheesegull::beatmap::CheesegullBeatmap,
-            models::cheesegull::beatmapset::CheesegullBeatmapset
+            models::cheesegull::beatmapset::CheesegullBeatmapset,
+            models::crawl_request::CrawlKind,
+            models::image::ImageSize,
+            models::image::ImageFormat,
+            models::stored_archive::ArchiveStoreStats,
+            lookup::LookupBody,
+            routes::v2::beatmaps::PerformanceBody,
+            routes::admin::HiddenBody,
+            routes::admin::CrawlBody,
+            routes::admin::ApiKeyBody,
+            routes::admin::CreatedApiKey
         )),
         tags(
             (name = "v1", description = "Cheesegull endpoints"),
             (name = "v2", description = "osu!api v2 endpoints"),
-            (name = "general", description = "General endpoints")
+            (name = "general", description = "General endpoints"),
+            (name = "admin", description = "Admin endpoints, requiring an admin api key")
         )
     )]
     struct ApiDoc;
@@ -81,21 +121,54 @@ fn api_router() -> Router {
         .merge(routes::v1::beatmapsets::router())
         .merge(routes::v2::beatmaps::router())
         .merge(routes::v2::beatmapsets::router())
+        .merge(routes::v2::suggest::router())
+        .merge(routes::v2::users::router())
         .merge(routes::downloads::router())
+        .merge(routes::images::router())
+        .merge(routes::previews::router())
+        .merge(routes::admin::router())
 }
 
 pub async fn serve(context: Context) -> anyhow::Result<()> {
-    let server_port = context.config.api_port.clone();
+    let server_port = context.config.api_port;
+
+    elastic::create_index_if_not_exists(&context.database, &context.config.elastic_api_keys_index)
+        .await?;
+    elastic::create_index_if_not_exists(
+        &context.database,
+        &context.config.elastic_beatmapsets_index,
+    )
+    .await?;
+    elastic::create_index_if_not_exists(
+        &context.database,
+        &context.config.elastic_difficulty_attributes_index,
+    )
+    .await?;
+    elastic::create_index_if_not_exists(&context.database, &context.config.elastic_archives_index)
+        .await?;
+
+    let trusted_proxies = context
+        .config
+        .trusted_proxies
+        .iter()
+        // an empty TRUSTED_PROXIES trusts no proxies
+        .filter(|proxy| !proxy.trim().is_empty())
+        .map(|proxy| {
+            TrustedProxy::parse(proxy)
+                .ok_or_else(|| anyhow::anyhow!("invalid trusted proxy: {}", proxy))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
     let app = api_router().layer(
         ServiceBuilder::new()
             .layer(TraceLayer::new_for_http())
-            .layer(AddExtensionLayer::new(context)),
+            .layer(AddExtensionLayer::new(context))
+            .layer(RateLimitLayer::new(trusted_proxies)),
     );
 
     log::info!("serving api on {}", server_port);
     axum::Server::bind(&format!("127.0.0.1:{}", server_port).parse()?)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr, _>())
         .await
         .context("failed to start api")
 }
@@ -0,0 +1,103 @@
+use crate::{
+    api::{error, Result},
+    models::search_cursor::SearchCursor,
+    repositories::beatmapsets::Pagination,
+};
+
+/// Upper bound on how many results can be requested in a single page.
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Results past this depth can only be reached with a cursor, matching elasticsearch's
+/// default `index.max_result_window`.
+pub const MAX_RESULT_WINDOW: u64 = 10_000;
+
+pub fn validate_page(amount: u64, offset: u64) -> Result<()> {
+    if amount == 0 || amount > MAX_PAGE_SIZE {
+        return Err(error::Error::unprocessable_entity([(
+            "amount",
+            format!("amount must be between 1 and {}", MAX_PAGE_SIZE),
+        )]));
+    }
+
+    if offset + amount > MAX_RESULT_WINDOW {
+        return Err(error::Error::unprocessable_entity([(
+            "offset",
+            format!(
+                "only the first {} results can be reached with an offset, use a cursor instead",
+                MAX_RESULT_WINDOW
+            ),
+        )]));
+    }
+
+    Ok(())
+}
+
+/// Page with the cursor if one was given, starting a new one if it's empty, and otherwise
+/// with the offset. Cursors hold a point-in-time open, so they're only used when asked for.
+pub fn search_pagination(offset: Option<u64>, cursor: Option<&str>) -> Result<Pagination> {
+    Ok(match cursor.map(str::trim) {
+        Some("") => Pagination::Cursor(None),
+        Some(cursor) => Pagination::Cursor(Some(decode_cursor(cursor)?)),
+        None => Pagination::Offset(offset.unwrap_or(0)),
+    })
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<SearchCursor> {
+    SearchCursor::decode(cursor)
+        .ok_or_else(|| error::Error::unprocessable_entity([("cursor", "invalid cursor")]))
+}
+
+pub fn expired_cursor() -> error::Error {
+    error::Error::unprocessable_entity([("cursor", "cursor has expired")])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_with_offsets_unless_a_cursor_is_given() {
+        assert!(matches!(
+            search_pagination(None, None).unwrap(),
+            Pagination::Offset(0)
+        ));
+        assert!(matches!(
+            search_pagination(Some(50), None).unwrap(),
+            Pagination::Offset(50)
+        ));
+    }
+
+    #[test]
+    fn starts_a_cursor_when_it_is_empty() {
+        assert!(matches!(
+            search_pagination(None, Some("")).unwrap(),
+            Pagination::Cursor(None)
+        ));
+    }
+
+    #[test]
+    fn continues_a_given_cursor() {
+        let cursor = SearchCursor {
+            pit_id: "pit".to_string(),
+            search_after: vec![serde_json::json!(1)],
+        };
+
+        match search_pagination(Some(50), Some(&cursor.encode())).unwrap() {
+            Pagination::Cursor(Some(decoded)) => assert_eq!(decoded.pit_id, "pit"),
+            _ => panic!("expected the given cursor"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_cursors() {
+        assert!(search_pagination(None, Some("nope!")).is_err());
+    }
+
+    #[test]
+    fn limits_pages_to_the_result_window() {
+        assert!(validate_page(100, 9_900).is_ok());
+        assert!(validate_page(100, 9_901).is_err());
+        assert!(validate_page(0, 0).is_err());
+        assert!(validate_page(MAX_PAGE_SIZE + 1, 0).is_err());
+    }
+}
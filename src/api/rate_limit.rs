@@ -0,0 +1,439 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    task::{Context as TaskContext, Poll},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{self, BoxBody, Bytes, HttpBody},
+    extract::ConnectInfo,
+    http::{HeaderMap, HeaderValue, Request, Response},
+    response::IntoResponse,
+    BoxError,
+};
+use futures::future::BoxFuture;
+use moka::sync::Cache;
+use tower::{Layer, Service};
+
+use crate::{api::Error, models::api_key::ApiKey, repositories, Context};
+
+const API_KEY_CACHE_TTL: Duration = Duration::from_secs(60);
+const MAX_CACHED_API_KEYS: u64 = 10_000;
+const MAX_TRACKED_BUCKETS: u64 = 100_000;
+
+/// Every bucket is full again after a minute, so forgetting idle ones changes nothing.
+const BUCKET_IDLE_TTL: Duration = Duration::from_secs(60);
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+struct RateLimitDecision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    reset_after: u64,
+    retry_after: u64,
+}
+
+/// A proxy, or range of proxies, trusted to say which address it forwarded a request for.
+#[derive(Clone, Copy, Debug)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix_len: u32,
+}
+
+impl TrustedProxy {
+    /// Parse an address like `10.0.0.1`, or a range like `10.0.0.0/8`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (address, prefix_len) = match value.trim().split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len.parse::<u32>().ok()?)),
+            None => (value.trim(), None),
+        };
+
+        let network: IpAddr = address.parse().ok()?;
+        let max_prefix_len = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        let prefix_len = prefix_len.unwrap_or(max_prefix_len);
+        (prefix_len <= max_prefix_len).then_some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+struct RateLimitState {
+    trusted_proxies: Vec<TrustedProxy>,
+    buckets: Cache<String, Arc<Mutex<TokenBucket>>>,
+    api_keys: Cache<String, Option<ApiKey>>,
+}
+
+impl RateLimitState {
+    fn check(&self, bucket_key: String, requests_per_minute: u32) -> RateLimitDecision {
+        let capacity = requests_per_minute as f64;
+        let refill_per_second = capacity / 60.0;
+        let now = Instant::now();
+
+        let bucket = self.buckets.get_with(bucket_key, || {
+            Arc::new(Mutex::new(TokenBucket {
+                tokens: capacity,
+                last_refill: now,
+            }))
+        });
+        let mut bucket = bucket.lock().unwrap();
+
+        let elapsed = now
+            .saturating_duration_since(bucket.last_refill)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_second).min(capacity);
+        bucket.last_refill = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        RateLimitDecision {
+            allowed,
+            limit: requests_per_minute,
+            remaining: bucket.tokens.floor() as u32,
+            reset_after: ((capacity - bucket.tokens) / refill_per_second).ceil() as u64,
+            retry_after: ((1.0 - bucket.tokens).max(0.0) / refill_per_second).ceil() as u64,
+        }
+    }
+
+    async fn fetch_api_key(&self, ctx: &Context, key: &str) -> anyhow::Result<Option<ApiKey>> {
+        let api_key = repositories::api_keys::fetch(ctx, key).await?;
+        self.api_keys.insert(key.to_string(), api_key.clone());
+
+        Ok(api_key)
+    }
+}
+
+/// Token bucket rate limiting per api key, or per client ip for requests without one.
+///
+/// Must be layered inside the `Context` extension layer.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    state: Arc<RateLimitState>,
+}
+
+impl RateLimitLayer {
+    pub fn new(trusted_proxies: Vec<TrustedProxy>) -> Self {
+        let state = RateLimitState {
+            trusted_proxies,
+            buckets: Cache::builder()
+                .max_capacity(MAX_TRACKED_BUCKETS)
+                .time_to_idle(BUCKET_IDLE_TTL)
+                .build(),
+            api_keys: Cache::builder()
+                .max_capacity(MAX_CACHED_API_KEYS)
+                .time_to_live(API_KEY_CACHE_TTL)
+                .build(),
+        };
+
+        Self {
+            state: Arc::new(state),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    state: Arc<RateLimitState>,
+}
+
+/// The client's address. `X-Forwarded-For` is only believed for requests from a trusted proxy,
+/// taking the right-most address in it that isn't also a trusted proxy, since anything further
+/// left was written by the client.
+fn client_ip<B>(request: &Request<B>, trusted_proxies: &[TrustedProxy]) -> Option<IpAddr> {
+    let peer_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())?;
+
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+    if !is_trusted(peer_ip) {
+        return Some(peer_ip);
+    }
+
+    let hops = request
+        .headers()
+        .get_all("x-forwarded-for")
+        .iter()
+        .rev()
+        .flat_map(|header| header.to_str().unwrap_or_default().rsplit(','));
+
+    let mut client_ip = peer_ip;
+    for hop in hops {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client_ip = ip;
+                if !is_trusted(ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    Some(client_ip)
+}
+
+fn insert_rate_limit_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    headers.insert("x-ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert(
+        "x-ratelimit-remaining",
+        HeaderValue::from(decision.remaining),
+    );
+    headers.insert("x-ratelimit-reset", HeaderValue::from(decision.reset_after));
+}
+
+fn too_many_requests(decision: &RateLimitDecision) -> Response<BoxBody> {
+    let mut response = Error::TooManyRequests {
+        retry_after: decision.retry_after,
+    }
+    .into_response();
+    insert_rate_limit_headers(response.headers_mut(), decision);
+
+    response.map(body::boxed)
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RateLimit<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<BoxError>,
+{
+    type Response = Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        // the clone may not be ready, so keep the service that was polled
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+
+        Box::pin(async move {
+            let ctx = request
+                .extensions()
+                .get::<Context>()
+                .cloned()
+                .expect("rate limit layer requires the context extension");
+
+            let ip_bucket_key = format!(
+                "ip:{}",
+                client_ip(&request, &state.trusted_proxies)
+                    .map(|ip| ip.to_string())
+                    .unwrap_or_default()
+            );
+            let ip_requests_per_minute = ctx.config.rate_limit_per_minute;
+
+            let api_key = match request.headers().get("x-api-key") {
+                Some(key) => {
+                    let key = match key.to_str() {
+                        Ok(key) => key,
+                        Err(_) => return Ok(Error::Unauthorized.into_response().map(body::boxed)),
+                    };
+
+                    let api_key = match state.api_keys.get(key) {
+                        Some(api_key) => api_key,
+                        None => {
+                            // looking up unknown keys costs a request to elasticsearch, so
+                            // those are limited by ip first
+                            if ip_requests_per_minute > 0 {
+                                let decision =
+                                    state.check(ip_bucket_key.clone(), ip_requests_per_minute);
+                                if !decision.allowed {
+                                    return Ok(too_many_requests(&decision));
+                                }
+                            }
+
+                            match state.fetch_api_key(&ctx, key).await {
+                                Ok(api_key) => api_key,
+                                Err(e) => {
+                                    return Ok(Error::from(e).into_response().map(body::boxed))
+                                }
+                            }
+                        }
+                    };
+
+                    match api_key {
+                        Some(api_key) => Some(api_key),
+                        None => return Ok(Error::Unauthorized.into_response().map(body::boxed)),
+                    }
+                }
+                None => None,
+            };
+
+            let (bucket_key, requests_per_minute) = match &api_key {
+                Some(api_key) => (format!("key:{}", api_key.key), api_key.requests_per_minute),
+                None => (ip_bucket_key, ip_requests_per_minute),
+            };
+
+            if let Some(api_key) = api_key {
+                request.extensions_mut().insert(api_key);
+            }
+
+            if requests_per_minute == 0 {
+                return Ok(inner.call(request).await?.map(body::boxed));
+            }
+
+            let decision = state.check(bucket_key, requests_per_minute);
+            if !decision.allowed {
+                return Ok(too_many_requests(&decision));
+            }
+
+            let mut response = inner.call(request).await?;
+            insert_rate_limit_headers(response.headers_mut(), &decision);
+
+            Ok(response.map(body::boxed))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded_request(peer: &str, forwarded_for: &[&str]) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        for value in forwarded_for {
+            request
+                .headers_mut()
+                .append("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+        }
+
+        request
+    }
+
+    fn proxies(values: &[&str]) -> Vec<TrustedProxy> {
+        values
+            .iter()
+            .map(|value| TrustedProxy::parse(value).unwrap())
+            .collect()
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_trusted_proxies() {
+        assert!(TrustedProxy::parse("10.0.0.1").is_some());
+        assert!(TrustedProxy::parse("10.0.0.0/8").is_some());
+        assert!(TrustedProxy::parse("fd00::/8").is_some());
+        assert!(TrustedProxy::parse("10.0.0.0/33").is_none());
+        assert!(TrustedProxy::parse("not an address").is_none());
+    }
+
+    #[test]
+    fn trusted_proxy_ranges_contain_their_addresses() {
+        let range = TrustedProxy::parse("10.1.0.0/16").unwrap();
+        assert!(range.contains("10.1.200.3".parse().unwrap()));
+        assert!(!range.contains("10.2.0.1".parse().unwrap()));
+        assert!(!range.contains("::1".parse().unwrap()));
+
+        let everything = TrustedProxy::parse("0.0.0.0/0").unwrap();
+        assert!(everything.contains("203.0.113.9".parse().unwrap()));
+
+        let single = TrustedProxy::parse("fd00::1").unwrap();
+        assert!(single.contains("fd00::1".parse().unwrap()));
+        assert!(!single.contains("fd00::2".parse().unwrap()));
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peers() {
+        let request = forwarded_request("203.0.113.9:1234", &["198.51.100.1"]);
+
+        assert_eq!(client_ip(&request, &[]), ip("203.0.113.9"));
+        assert_eq!(
+            client_ip(&request, &proxies(&["10.0.0.0/8"])),
+            ip("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn takes_the_right_most_untrusted_hop() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+
+        // the left-most address was made up by the client
+        let request = forwarded_request("10.0.0.1:1234", &["1.1.1.1, 198.51.100.7, 10.0.0.2"]);
+        assert_eq!(client_ip(&request, &trusted), ip("198.51.100.7"));
+
+        let request = forwarded_request("10.0.0.1:1234", &["1.1.1.1", "198.51.100.7"]);
+        assert_eq!(client_ip(&request, &trusted), ip("198.51.100.7"));
+    }
+
+    #[test]
+    fn stops_at_unparseable_hops() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+
+        let request = forwarded_request("10.0.0.1:1234", &["garbage, 10.0.0.2"]);
+        assert_eq!(client_ip(&request, &trusted), ip("10.0.0.2"));
+
+        let request = forwarded_request("10.0.0.1:1234", &[]);
+        assert_eq!(client_ip(&request, &trusted), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn limits_buckets_to_their_capacity() {
+        let layer = RateLimitLayer::new(vec![]);
+
+        for _ in 0..3 {
+            assert!(layer.state.check("ip:a".to_string(), 3).allowed);
+        }
+
+        let decision = layer.state.check("ip:a".to_string(), 3);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!(decision.retry_after > 0);
+
+        assert!(layer.state.check("ip:b".to_string(), 3).allowed);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::{
+    api::{auth::AdminKey, error, Result},
+    models::{
+        crawl_request::{CrawlKind, CrawlRequest},
+        stored_archive::ArchiveStoreStats,
+    },
+    repositories, usecases, Context,
+};
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use rosu_v2::prelude::{Beatmap as OsuBeatmap, Beatmapset as OsuBeatmapset};
+
+const MAX_CRAWL_RANGE: u32 = 10_000;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/admin/beatmaps/:beatmap_id", delete(delete_beatmap))
+        .route("/admin/beatmaps/:beatmap_id/refresh", post(refresh_beatmap))
+        .route(
+            "/admin/beatmapsets/:beatmapset_id",
+            delete(delete_beatmapset),
+        )
+        .route(
+            "/admin/beatmapsets/:beatmapset_id/refresh",
+            post(refresh_beatmapset),
+        )
+        .route(
+            "/admin/beatmapsets/:beatmapset_id/archive/refresh",
+            post(refresh_archive),
+        )
+        .route(
+            "/admin/beatmapsets/:beatmapset_id/hidden",
+            put(set_beatmapset_hidden),
+        )
+        .route("/admin/crawl", post(enqueue_crawl))
+        .route("/admin/archives/stats", get(get_archive_stats))
+        .route("/admin/api-keys", post(create_api_key))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/beatmaps/{beatmap_id}/refresh",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Refreshed beatmap from osu!"),
+        (status = 404, description = "Beatmap not found on osu!")
+    ),
+    params(
+        ("beatmap_id" = u32, Path, description = "Beatmap id")
+    )
+)]
+async fn refresh_beatmap(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Path(beatmap_id): Path<u32>,
+) -> Result<Json<OsuBeatmap>> {
+    match usecases::beatmaps::refresh(&ctx, beatmap_id).await? {
+        Some(beatmap) => Ok(Json(beatmap.data)),
+        None => Err(error::Error::NotFound),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/beatmaps/{beatmap_id}",
+    tag = "admin",
+    responses(
+        (status = 204, description = "Deleted beatmap from the index")
+    ),
+    params(
+        ("beatmap_id" = u32, Path, description = "Beatmap id")
+    )
+)]
+async fn delete_beatmap(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Path(beatmap_id): Path<u32>,
+) -> Result<StatusCode> {
+    usecases::beatmaps::delete(&ctx, beatmap_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/beatmapsets/{beatmapset_id}/refresh",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Refreshed beatmapset from osu!"),
+        (status = 404, description = "Beatmapset not found on osu!")
+    ),
+    params(
+        ("beatmapset_id" = u32, Path, description = "Beatmapset id")
+    )
+)]
+async fn refresh_beatmapset(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Path(beatmapset_id): Path<u32>,
+) -> Result<Json<OsuBeatmapset>> {
+    match usecases::beatmapsets::refresh(&ctx, beatmapset_id).await? {
+        Some(beatmapset) => Ok(Json(beatmapset.data)),
+        None => Err(error::Error::NotFound),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/beatmapsets/{beatmapset_id}",
+    tag = "admin",
+    responses(
+        (status = 204, description = "Deleted beatmapset and its stored archives")
+    ),
+    params(
+        ("beatmapset_id" = u32, Path, description = "Beatmapset id")
+    )
+)]
+async fn delete_beatmapset(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Path(beatmapset_id): Path<u32>,
+) -> Result<StatusCode> {
+    usecases::beatmapsets::delete(&ctx, beatmapset_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/beatmapsets/{beatmapset_id}/archive/refresh",
+    tag = "admin",
+    responses(
+        (status = 204, description = "Downloaded a fresh archive from osu!"),
+        (status = 404, description = "Beatmapset archive not found on osu!"),
+        (status = 422, description = "Archive storage is not configured")
+    ),
+    params(
+        ("beatmapset_id" = u32, Path, description = "Beatmapset id")
+    )
+)]
+async fn refresh_archive(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Path(beatmapset_id): Path<u32>,
+) -> Result<StatusCode> {
+    if ctx.config.archive_path.is_none() {
+        return Err(error::Error::unprocessable_entity([(
+            "archive_path",
+            "archive storage is not configured",
+        )]));
+    }
+
+    match usecases::archives::refresh(&ctx, beatmapset_id).await? {
+        Some(_) => Ok(StatusCode::NO_CONTENT),
+        None => Err(error::Error::NotFound),
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct HiddenBody {
+    pub hidden: bool,
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/beatmapsets/{beatmapset_id}/hidden",
+    tag = "admin",
+    request_body = HiddenBody,
+    responses(
+        (status = 204, description = "Updated whether the beatmapset is hidden"),
+        (status = 404, description = "Beatmapset not indexed")
+    ),
+    params(
+        ("beatmapset_id" = u32, Path, description = "Beatmapset id")
+    )
+)]
+async fn set_beatmapset_hidden(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Path(beatmapset_id): Path<u32>,
+    Json(body): Json<HiddenBody>,
+) -> Result<StatusCode> {
+    match usecases::beatmapsets::set_hidden(&ctx, beatmapset_id, body.hidden).await? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(error::Error::NotFound),
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct CrawlBody {
+    pub kind: CrawlKind,
+    pub start_id: u32,
+    pub end_id: u32,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/crawl",
+    tag = "admin",
+    request_body = CrawlBody,
+    responses(
+        (status = 202, description = "Queued id range for the crawler"),
+        (status = 422, description = "Invalid id range")
+    )
+)]
+async fn enqueue_crawl(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Json(body): Json<CrawlBody>,
+) -> Result<StatusCode> {
+    if body.end_id < body.start_id {
+        return Err(error::Error::unprocessable_entity([(
+            "end_id",
+            "end_id must not be less than start_id",
+        )]));
+    }
+
+    if body.end_id - body.start_id >= MAX_CRAWL_RANGE {
+        return Err(error::Error::unprocessable_entity([(
+            "end_id",
+            format!("at most {} ids can be queued at once", MAX_CRAWL_RANGE),
+        )]));
+    }
+
+    let crawl_request = CrawlRequest {
+        kind: body.kind,
+        start_id: body.start_id,
+        end_id: body.end_id,
+        created_at: chrono::Utc::now(),
+    };
+
+    repositories::crawl_queue::push(&ctx, crawl_request).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/archives/stats",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Archive store size, and activity of this api process since it started", body = ArchiveStoreStats),
+        (status = 422, description = "Archive storage is not configured")
+    )
+)]
+async fn get_archive_stats(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+) -> Result<Json<ArchiveStoreStats>> {
+    if ctx.config.archive_path.is_none() {
+        return Err(error::Error::unprocessable_entity([(
+            "archive_path",
+            "archive storage is not configured",
+        )]));
+    }
+
+    Ok(Json(usecases::archives::store_stats(&ctx).await?))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct ApiKeyBody {
+    /// Who the key is for
+    pub name: String,
+    /// Requests per minute allowed with the key, 0 for unlimited (defaults to 0)
+    pub requests_per_minute: Option<u32>,
+    /// Whether the key can use admin endpoints (defaults to false)
+    pub admin: Option<bool>,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct CreatedApiKey {
+    /// The key to send in the `x-api-key` header
+    pub key: String,
+    pub name: String,
+    pub requests_per_minute: u32,
+    pub admin: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys",
+    tag = "admin",
+    request_body = ApiKeyBody,
+    responses(
+        (status = 201, description = "Created an api key", body = CreatedApiKey),
+        (status = 422, description = "Invalid key name")
+    )
+)]
+async fn create_api_key(
+    ctx: Extension<Context>,
+    _admin: AdminKey,
+    Json(body): Json<ApiKeyBody>,
+) -> Result<(StatusCode, Json<CreatedApiKey>)> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(error::Error::unprocessable_entity([(
+            "name",
+            "name must not be empty",
+        )]));
+    }
+
+    let api_key = usecases::api_keys::create(
+        &ctx,
+        name.to_string(),
+        body.requests_per_minute.unwrap_or(0),
+        body.admin.unwrap_or(false),
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKey {
+            key: api_key.key,
+            name: api_key.name,
+            requests_per_minute: api_key.requests_per_minute,
+            admin: api_key.admin,
+        }),
+    ))
+}
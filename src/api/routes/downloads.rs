@@ -1,42 +1,96 @@
-use axum::extract::{Extension, Path};
-use axum::response::{IntoResponse, Redirect};
+use axum::body::{self, BoxBody};
+use axum::extract::{Extension, Path, Query};
+use axum::http::Response;
+use axum::response::{Headers, IntoResponse, Redirect};
 use axum::{routing::get, Router};
 
-use crate::api::{error, Result};
+use crate::api::{error, filters, Result};
 use crate::{repositories, usecases, Context};
 
 pub fn router() -> Router {
     Router::new().route("/d/:beatmapset_id", get(get_beatmapset))
 }
 
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct DownloadParams {
+    /// Set to 1 or true to download the archive without its video
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub novideo: Option<String>,
+}
+
 #[utoipa::path(
     get,
     path = "/d/{beatmapset_id}",
     tag = "general",
     responses(
         (status = 200, description = "Found beatmapset successfully", body = Vec<u8>),
-        (status = 404, description = "Beatmapset not found")
+        (status = 404, description = "Beatmapset not found"),
+        (status = 451, description = "Beatmapset download disabled by osu!")
     ),
     params(
-        ("beatmapset_id" = u32, Path, description = "Beatmapset id")
+        ("beatmapset_id" = u32, Path, description = "Beatmapset id"),
+        DownloadParams
     )
 )]
 async fn get_beatmapset(
     ctx: Extension<Context>,
     Path(beatmapset_id): Path<u32>,
-) -> Result<impl IntoResponse> {
-    let beatmapset_option = usecases::beatmapsets::fetch(&ctx, beatmapset_id).await?;
-    if beatmapset_option.is_none() {
-        return Err(error::Error::NotFound);
+    Query(params): Query<DownloadParams>,
+) -> Result<Response<BoxBody>> {
+    let beatmapset = match usecases::beatmapsets::fetch(&ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset,
+        None => return Err(error::Error::NotFound),
+    };
+
+    let availability = &beatmapset.data.availability;
+    if availability.download_disabled {
+        return Err(error::Error::UnavailableForLegalReasons {
+            message: availability.more_information.clone(),
+        });
+    }
+
+    // sets without a video only have one variant, so don't store it twice
+    let no_video =
+        filters::parse_flag("novideo", params.novideo.as_deref())? && beatmapset.data.video;
+
+    if ctx.config.archive_path.is_some() {
+        let archive = usecases::archives::fetch(&ctx, beatmapset_id, no_video).await?;
+
+        return match archive {
+            Some(archive) => {
+                if let Err(e) = usecases::archives::record_download(&ctx, beatmapset_id).await {
+                    log::warn!(
+                        "failed to record download of beatmapset {}: {}",
+                        beatmapset_id,
+                        e
+                    );
+                }
+
+                let headers = Headers(vec![
+                    (
+                        "content-type",
+                        "application/x-osu-beatmap-archive".to_string(),
+                    ),
+                    (
+                        "content-disposition",
+                        format!("attachment; filename=\"{}.osz\"", beatmapset_id),
+                    ),
+                ]);
+
+                Ok((headers, archive).into_response())
+            }
+            None => Err(error::Error::NotFound),
+        };
     }
 
-    let osz_url = repositories::osu::beatmapsets::download(&ctx, beatmapset_id).await?;
+    let osz_url = repositories::osu::beatmapsets::download(&ctx, beatmapset_id, no_video).await?;
     match osz_url {
-        Some(url) => {
-            Ok(Redirect::temporary(url.parse().map_err(|_| {
-                anyhow::anyhow!("Failed to parse url: {}", url)
-            })?))
-        }
+        Some(url) => Ok(Redirect::temporary(
+            url.parse()
+                .map_err(|_| anyhow::anyhow!("Failed to parse url: {}", url))?,
+        )
+        .into_response()
+        .map(body::boxed)),
         None => Err(error::Error::NotFound),
     }
 }
@@ -0,0 +1,100 @@
+use axum::body::BoxBody;
+use axum::extract::{Extension, Path, Query};
+use axum::http::Response;
+use axum::response::{Headers, IntoResponse};
+use axum::{routing::get, Router};
+
+use crate::api::{error, Result};
+use crate::models::image::{ImageFormat, ImageSize};
+use crate::{usecases, Context};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/thumb/:beatmapset_id", get(get_thumbnail))
+        .route("/bg/:beatmap_id", get(get_background))
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct ImageParams {
+    /// list (150x150), card (400x140) or full (up to 1920x1080)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<ImageSize>,
+    /// jpeg or webp (defaults to jpeg)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<ImageFormat>,
+}
+
+fn image_response(image: axum::body::Bytes, format: ImageFormat) -> Response<BoxBody> {
+    let headers = Headers(vec![
+        ("content-type", format.content_type()),
+        ("cache-control", "public, max-age=86400"),
+    ]);
+
+    (headers, image).into_response()
+}
+
+/// Images are rendered from stored archives, so without storage there are none to serve.
+fn require_archive_storage(ctx: &Context) -> Result<()> {
+    match ctx.config.archive_path {
+        Some(_) => Ok(()),
+        None => Err(error::Error::NotFound),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/thumb/{beatmapset_id}",
+    tag = "general",
+    responses(
+        (status = 200, description = "Rendered the beatmapset's background", body = Vec<u8>),
+        (status = 404, description = "Beatmapset or background not found, or archive storage is not configured"),
+    ),
+    params(
+        ("beatmapset_id" = u32, Path, description = "Beatmapset id"),
+        ImageParams
+    )
+)]
+async fn get_thumbnail(
+    ctx: Extension<Context>,
+    Path(beatmapset_id): Path<u32>,
+    Query(params): Query<ImageParams>,
+) -> Result<Response<BoxBody>> {
+    require_archive_storage(&ctx)?;
+
+    let size = params.size.unwrap_or(ImageSize::List);
+    let format = params.format.unwrap_or(ImageFormat::Jpeg);
+
+    match usecases::images::thumbnail(&ctx, beatmapset_id, size, format).await? {
+        Some(thumbnail) => Ok(image_response(thumbnail, format)),
+        None => Err(error::Error::NotFound),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/bg/{beatmap_id}",
+    tag = "general",
+    responses(
+        (status = 200, description = "Rendered the beatmap's background", body = Vec<u8>),
+        (status = 404, description = "Beatmap or background not found, or archive storage is not configured"),
+    ),
+    params(
+        ("beatmap_id" = u32, Path, description = "Beatmap id"),
+        ImageParams
+    )
+)]
+async fn get_background(
+    ctx: Extension<Context>,
+    Path(beatmap_id): Path<u32>,
+    Query(params): Query<ImageParams>,
+) -> Result<Response<BoxBody>> {
+    require_archive_storage(&ctx)?;
+
+    let size = params.size.unwrap_or(ImageSize::Full);
+    let format = params.format.unwrap_or(ImageFormat::Jpeg);
+
+    match usecases::images::background(&ctx, beatmap_id, size, format).await? {
+        Some(background) => Ok(image_response(background, format)),
+        None => Err(error::Error::NotFound),
+    }
+}
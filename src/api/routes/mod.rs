@@ -1,3 +1,6 @@
+pub mod admin;
 pub mod downloads;
+pub mod images;
+pub mod previews;
 pub mod v1;
 pub mod v2;
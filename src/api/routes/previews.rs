@@ -0,0 +1,50 @@
+use axum::body::BoxBody;
+use axum::extract::{Extension, Path};
+use axum::http::Response;
+use axum::response::{Headers, IntoResponse};
+use axum::{routing::get, Router};
+
+use crate::api::{error, Result};
+use crate::{usecases, Context};
+
+pub fn router() -> Router {
+    Router::new().route("/preview/:file_name", get(get_preview))
+}
+
+#[utoipa::path(
+    get,
+    path = "/preview/{beatmapset_id}.mp3",
+    tag = "general",
+    responses(
+        (status = 200, description = "Ten seconds of the beatmapset's song from its preview time, as a mono MP3", body = Vec<u8>),
+        (status = 404, description = "Beatmapset or decodable audio not found, or archive storage is not configured")
+    ),
+    params(
+        ("beatmapset_id" = u32, Path, description = "Beatmapset id")
+    )
+)]
+async fn get_preview(
+    ctx: Extension<Context>,
+    Path(file_name): Path<String>,
+) -> Result<Response<BoxBody>> {
+    if ctx.config.archive_path.is_none() {
+        return Err(error::Error::NotFound);
+    }
+
+    let beatmapset_id = file_name
+        .strip_suffix(".mp3")
+        .and_then(|beatmapset_id| beatmapset_id.parse::<u32>().ok())
+        .ok_or(error::Error::NotFound)?;
+
+    match usecases::previews::fetch(&ctx, beatmapset_id).await? {
+        Some(preview) => {
+            let headers = Headers(vec![
+                ("content-type", "audio/mpeg"),
+                ("cache-control", "public, max-age=86400"),
+            ]);
+
+            Ok((headers, preview).into_response())
+        }
+        None => Err(error::Error::NotFound),
+    }
+}
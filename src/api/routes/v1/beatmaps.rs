@@ -1,10 +1,12 @@
 use crate::{
-    api::{error, Result},
+    api::{conditional::Validators, error, Result},
     models::cheesegull::beatmap::CheesegullBeatmap,
     usecases, Context,
 };
 use axum::{
+    body::BoxBody,
     extract::{Extension, Path},
+    http::{HeaderMap, Response},
     routing::get,
     Json, Router,
 };
@@ -19,6 +21,7 @@ pub fn router() -> Router {
     tag = "v1",
     responses(
         (status = 200, description = "Found beatmap successfully", body = CheesegullBeatmap),
+        (status = 304, description = "Beatmap not modified"),
         (status = 404, description = "Beatmap not found")
     ),
     params(
@@ -28,11 +31,16 @@ pub fn router() -> Router {
 async fn get_beatmap(
     ctx: Extension<Context>,
     Path(beatmap_id): Path<u32>,
-) -> Result<Json<Option<CheesegullBeatmap>>> {
+    headers: HeaderMap,
+) -> Result<Response<BoxBody>> {
     let beatmap = usecases::beatmaps::fetch(&ctx, beatmap_id).await?;
 
     match beatmap {
-        Some(beatmap) => Ok(Json(Some(beatmap.data.into()))),
+        Some(beatmap) => {
+            let validators = Validators::new(beatmap_id, beatmap.updated_at, beatmap.data.status);
+
+            Ok(validators.respond(&headers, || Json(CheesegullBeatmap::from(beatmap.data))))
+        }
         None => Err(error::Error::NotFound),
     }
 }
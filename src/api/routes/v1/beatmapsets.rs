@@ -1,17 +1,20 @@
 use crate::{
-    api::{error, Result},
+    api::{conditional::Validators, error, filters, pagination, Result},
     models::{
-        cheesegull::beatmapset::CheesegullBeatmapset, mode::Mode, ranked_status::RankedStatus,
+        cheesegull::beatmapset::CheesegullBeatmapset, search_filters::SearchFilters,
+        search_sort::SearchSort,
     },
-    repositories, usecases, Context,
+    repositories::{self, beatmapsets::Pagination},
+    usecases, Context,
 };
 use axum::{
+    body::BoxBody,
     extract::{Extension, Path, Query},
+    http::{HeaderMap, Response},
     response::IntoResponse,
     routing::get,
     Json, Router,
 };
-use num_traits::FromPrimitive;
 use rosu_v2::prelude::Beatmapset as OsuBeatmapset;
 
 pub fn router() -> Router {
@@ -26,6 +29,7 @@ pub fn router() -> Router {
     tag = "v1",
     responses(
         (status = 200, description = "Found beatmapset successfully", body = CheesegullBeatmapset),
+        (status = 304, description = "Beatmapset not modified"),
         (status = 404, description = "Beatmapset not found")
     ),
     params(
@@ -35,11 +39,19 @@ pub fn router() -> Router {
 async fn get_beatmapset(
     ctx: Extension<Context>,
     Path(beatmapset_id): Path<u32>,
-) -> Result<Json<Option<CheesegullBeatmapset>>> {
+    headers: HeaderMap,
+) -> Result<Response<BoxBody>> {
     let beatmapset = usecases::beatmapsets::fetch(&ctx, beatmapset_id).await?;
 
     match beatmapset {
-        Some(beatmapset) => Ok(Json(Some(beatmapset.data.into()))),
+        Some(beatmapset) => {
+            let validators =
+                Validators::new(beatmapset_id, beatmapset.updated_at, beatmapset.data.status);
+
+            Ok(validators.respond(&headers, || {
+                Json(CheesegullBeatmapset::from(beatmapset.data))
+            }))
+        }
         None => Err(error::Error::NotFound),
     }
 }
@@ -52,12 +64,49 @@ pub struct SearchParams {
     pub amount: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub offset: Option<u64>,
+    /// Comma separated statuses, by name or numeric code (defaults to ranked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Comma separated modes, by name or numeric code (defaults to all)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Comma separated mania key counts, also accepted as `keys=N` in the query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<String>,
+    /// Whether converted difficulties count when filtering by mode, keys, star rating or length,
+    /// as 1/0 or true/false (defaults to false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converts: Option<String>,
+    /// Comma separated genres, by name or id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    /// Comma separated languages, by name or id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Whether to include NSFW beatmapsets, as 1/0 or true/false (defaults to false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nsfw: Option<String>,
+    /// Only beatmapsets with (1 or true) or without (0 or false) a video
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video: Option<String>,
+    /// Only beatmapsets with (1 or true) or without (0 or false) a storyboard
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub status: Option<i8>,
+    pub storyboard: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub mode: Option<i8>,
+    pub min_stars: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_stars: Option<f32>,
+    /// Minimum total length in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u32>,
+    /// Maximum total length in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub osu_direct: Option<bool>,
+    /// osu!direct's ranked filter (`r`), used instead of `status`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direct_status: Option<i8>,
 }
 
 #[utoipa::path(
@@ -67,29 +116,59 @@ pub struct SearchParams {
     params(SearchParams),
     responses(
         (status = 200, description = "Beatmapsets found", body = [CheesegullBeatmapset]),
+        (status = 422, description = "Invalid filters, page size or offset")
     ),
 )]
 async fn search_beatmapsets(
     ctx: Extension<Context>,
     Query(params): Query<SearchParams>,
 ) -> Result<impl IntoResponse> {
-    let beatmapsets = repositories::beatmapsets::search(
+    let amount = params.amount.unwrap_or(pagination::MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+    pagination::validate_page(amount, offset)?;
+
+    let statuses = match params.direct_status {
+        Some(direct_status) => filters::parse_direct_statuses(direct_status)?,
+        None => filters::parse_statuses(params.status.as_deref())?,
+    };
+    let modes = filters::parse_modes(params.mode.as_deref())?;
+
+    let (query, mut keys) = filters::extract_query_keys(params.query)?;
+    keys.extend(filters::parse_keys(params.keys.as_deref())?);
+
+    let include_converts = filters::parse_flag("converts", params.converts.as_deref())?;
+    let search_filters = SearchFilters {
+        query,
+        statuses,
+        modes,
+        keys,
+        include_converts,
+        genres: filters::parse_genres(params.genre.as_deref())?,
+        languages: filters::parse_languages(params.language.as_deref())?,
+        include_nsfw: filters::parse_flag("nsfw", params.nsfw.as_deref())?,
+        video: filters::parse_optional_flag("video", params.video.as_deref())?,
+        storyboard: filters::parse_optional_flag("storyboard", params.storyboard.as_deref())?,
+        min_stars: params.min_stars,
+        max_stars: params.max_stars,
+        min_length: params.min_length,
+        max_length: params.max_length,
+        mapper_id: None,
+    };
+    filters::validate_ranges(&search_filters)?;
+
+    let search_page = repositories::beatmapsets::search(
         &ctx,
-        params.query,
-        params.amount.unwrap_or(100),
-        params.offset.unwrap_or(0),
-        params
-            .status
-            .map(|s| FromPrimitive::from_i8(s).unwrap_or(RankedStatus::Ranked))
-            .unwrap_or(RankedStatus::Ranked),
-        params
-            .mode
-            .map(|s| FromPrimitive::from_i8(s).unwrap_or(Mode::All))
-            .unwrap_or(Mode::All),
+        &search_filters,
+        SearchSort::default(),
+        amount,
+        Pagination::Offset(offset),
+        false,
     )
-    .await?;
+    .await?
+    .ok_or_else(pagination::expired_cursor)?;
 
-    let osu_beatmapsets: Vec<OsuBeatmapset> = beatmapsets
+    let osu_beatmapsets: Vec<OsuBeatmapset> = search_page
+        .beatmapsets
         .into_iter()
         .map(|beatmapset| beatmapset.data)
         .collect();
@@ -101,7 +180,10 @@ async fn search_beatmapsets(
         false => Json(
             osu_beatmapsets
                 .into_iter()
-                .map(|osu_beatmapset| osu_beatmapset.into())
+                .map(|osu_beatmapset| match include_converts {
+                    true => CheesegullBeatmapset::with_converts(osu_beatmapset),
+                    false => osu_beatmapset.into(),
+                })
                 .collect::<Vec<CheesegullBeatmapset>>(),
         )
         .into_response(),
@@ -1,16 +1,39 @@
 use crate::{
-    api::{error, Result},
+    api::{
+        conditional::Validators,
+        error,
+        filters::{self, RandomParams},
+        lookup::{self, LookupBody},
+        Result,
+    },
+    helpers::performance::{self, Score},
+    models::beatmap::Beatmap,
     usecases, Context,
 };
 use axum::{
-    extract::{Extension, Path},
-    routing::get,
+    body::BoxBody,
+    extract::{Extension, Path, Query, RawQuery},
+    http::{HeaderMap, Response},
+    routing::{get, post},
     Json, Router,
 };
-use rosu_v2::prelude::Beatmap as OsuBeatmap;
+use rosu_v2::prelude::{
+    Beatmap as OsuBeatmap, BeatmapDifficultyAttributes, GameMode, GameMods, ScoreStatistics,
+};
 
 pub fn router() -> Router {
-    Router::new().route("/api/v2/beatmaps/:beatmap_id", get(get_beatmap))
+    Router::new()
+        .route("/api/v2/beatmaps/:beatmap_id", get(get_beatmap))
+        .route(
+            "/api/v2/beatmaps/lookup",
+            get(lookup_beatmaps_by_query).post(lookup_beatmaps),
+        )
+        .route("/api/v2/beatmaps/random", get(random_beatmap))
+        .route(
+            "/api/v2/beatmaps/:beatmap_id/attributes",
+            get(get_beatmap_attributes),
+        )
+        .route("/api/v2/beatmaps/:beatmap_id/pp", post(calculate_pp))
 }
 
 #[utoipa::path(
@@ -19,6 +42,7 @@ pub fn router() -> Router {
     tag = "v2",
     responses(
         (status = 200, description = "Found beatmap successfully"),
+        (status = 304, description = "Beatmap not modified"),
         (status = 404, description = "Beatmap not found")
     ),
     params(
@@ -28,11 +52,381 @@ pub fn router() -> Router {
 async fn get_beatmap(
     ctx: Extension<Context>,
     Path(beatmap_id): Path<u32>,
-) -> Result<Json<Option<OsuBeatmap>>> {
+    headers: HeaderMap,
+) -> Result<Response<BoxBody>> {
     let beatmap = usecases::beatmaps::fetch(&ctx, beatmap_id).await?;
 
     match beatmap {
-        Some(beatmap) => Ok(Json(Some(beatmap.data))),
+        Some(beatmap) => {
+            let validators = Validators::new(beatmap_id, beatmap.updated_at, beatmap.data.status);
+
+            Ok(validators.respond(&headers, || Json(beatmap.data)))
+        }
         None => Err(error::Error::NotFound),
     }
 }
+
+#[derive(serde::Serialize)]
+pub struct BeatmapLookup {
+    pub id: u32,
+    pub found: bool,
+    pub beatmap: Option<OsuBeatmap>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/beatmaps/lookup",
+    tag = "v2",
+    request_body = LookupBody,
+    responses(
+        (status = 200, description = "Looked up beatmaps, in request order"),
+        (status = 422, description = "Invalid ids")
+    )
+)]
+async fn lookup_beatmaps(
+    ctx: Extension<Context>,
+    Json(body): Json<LookupBody>,
+) -> Result<Json<Vec<BeatmapLookup>>> {
+    lookup(&ctx, body.ids).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/beatmaps/lookup",
+    tag = "v2",
+    responses(
+        (status = 200, description = "Looked up beatmaps, in request order"),
+        (status = 422, description = "Invalid ids")
+    ),
+    params(
+        ("ids[]" = Vec<u32>, Query, description = "Beatmap ids")
+    )
+)]
+async fn lookup_beatmaps_by_query(
+    ctx: Extension<Context>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<BeatmapLookup>>> {
+    let beatmap_ids = lookup::ids_from_query(query.as_deref())?;
+
+    lookup(&ctx, beatmap_ids).await
+}
+
+async fn lookup(ctx: &Context, beatmap_ids: Vec<u32>) -> Result<Json<Vec<BeatmapLookup>>> {
+    lookup::validate_ids(&beatmap_ids)?;
+
+    let beatmaps = usecases::beatmaps::fetch_many(ctx, &beatmap_ids).await?;
+
+    let lookups = beatmap_ids
+        .into_iter()
+        .map(|beatmap_id| {
+            let beatmap = beatmaps
+                .get(&beatmap_id)
+                .map(|beatmap| beatmap.data.clone());
+
+            BeatmapLookup {
+                id: beatmap_id,
+                found: beatmap.is_some(),
+                beatmap,
+            }
+        })
+        .collect();
+
+    Ok(Json(lookups))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/beatmaps/random",
+    tag = "v2",
+    params(RandomParams),
+    responses(
+        (status = 200, description = "Picked a random beatmap"),
+        (status = 404, description = "No beatmaps match the filters"),
+        (status = 422, description = "Invalid filters")
+    ),
+)]
+async fn random_beatmap(
+    ctx: Extension<Context>,
+    Query(params): Query<RandomParams>,
+) -> Result<Json<OsuBeatmap>> {
+    let search_filters = params.search_filters()?;
+    let weighted = filters::parse_flag("weighted", params.weighted.as_deref())?;
+
+    match usecases::beatmaps::random(&ctx, &search_filters, params.seed, weighted, false).await? {
+        Some((beatmap, _)) => Ok(Json(beatmap.data)),
+        None => Err(error::Error::NotFound),
+    }
+}
+
+/// Resolve the mode to calculate a beatmap in. Only osu!standard beatmaps can be converted, and
+/// only to taiko and catch.
+fn conversion_mode(beatmap: &OsuBeatmap, mode: Option<GameMode>) -> Result<GameMode> {
+    match mode {
+        None => Ok(beatmap.mode),
+        Some(mode) if mode == beatmap.mode => Ok(mode),
+        Some(GameMode::Mania) => Err(error::Error::unprocessable_entity([(
+            "mode",
+            "beatmaps can't be converted to mania",
+        )])),
+        Some(mode) if beatmap.mode == GameMode::Osu => Ok(mode),
+        Some(_) => Err(error::Error::unprocessable_entity([(
+            "mode",
+            "only osu!standard beatmaps can be converted to other modes",
+        )])),
+    }
+}
+
+/// Fetch a beatmap along with its difficulty attributes in the requested mode.
+async fn beatmap_attributes(
+    ctx: &Context,
+    beatmap_id: u32,
+    mode: Option<GameMode>,
+    mods: GameMods,
+) -> Result<(Beatmap, GameMode, BeatmapDifficultyAttributes)> {
+    let beatmap = match usecases::beatmaps::fetch(ctx, beatmap_id).await? {
+        Some(beatmap) => beatmap,
+        None => return Err(error::Error::NotFound),
+    };
+    let mode = conversion_mode(&beatmap.data, mode)?;
+
+    match usecases::difficulty_attributes::fetch(ctx, &beatmap, mode, mods).await? {
+        Some(attributes) => Ok((beatmap, mode, attributes)),
+        None => Err(error::Error::NotFound),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct AttributesParams {
+    /// Mods as acronyms like HDDT or their bitwise value (defaults to no mod)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mods: Option<String>,
+    /// Mode to convert an osu!standard beatmap to, taiko or catch (defaults to the beatmap's mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct BeatmapAttributes {
+    pub beatmap_id: u32,
+    pub mode: GameMode,
+    pub mods: String,
+    #[serde(flatten)]
+    pub attributes: BeatmapDifficultyAttributes,
+    /// Performance points of a full combo with perfect accuracy
+    pub max_pp: f32,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/beatmaps/{beatmap_id}/attributes",
+    tag = "v2",
+    responses(
+        (status = 200, description = "Found difficulty attributes for the mods"),
+        (status = 404, description = "Beatmap not found"),
+        (status = 422, description = "Invalid mods or mode")
+    ),
+    params(
+        ("beatmap_id" = u32, Path, description = "Beatmap id"),
+        AttributesParams
+    )
+)]
+async fn get_beatmap_attributes(
+    ctx: Extension<Context>,
+    Path(beatmap_id): Path<u32>,
+    Query(params): Query<AttributesParams>,
+) -> Result<Json<BeatmapAttributes>> {
+    let mods = filters::parse_mods(params.mods.as_deref())?;
+    let mode = filters::parse_game_mode(params.mode.as_deref())?;
+
+    let (beatmap, mode, attributes) = beatmap_attributes(&ctx, beatmap_id, mode, mods).await?;
+    let max_pp = performance::max_pp(&beatmap.data, &attributes, mods);
+
+    Ok(Json(BeatmapAttributes {
+        beatmap_id,
+        mode,
+        mods: mods.to_string(),
+        attributes,
+        max_pp,
+    }))
+}
+
+/// Accuracies the performance table of a beatmap is calculated for.
+const TABLE_ACCURACIES: [f64; 4] = [95.0, 98.0, 99.0, 100.0];
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct PerformanceBody {
+    /// Mods as acronyms like HDDT or their bitwise value (defaults to no mod)
+    pub mods: Option<String>,
+    /// Mode to convert an osu!standard beatmap to, taiko or catch (defaults to the beatmap's mode)
+    pub mode: Option<String>,
+    /// Accuracy in percent, used when no hit counts are given (defaults to 100)
+    pub accuracy: Option<f64>,
+    pub count_geki: Option<u32>,
+    /// Defaults to the objects not covered by the other hit counts
+    pub count_300: Option<u32>,
+    pub count_katu: Option<u32>,
+    pub count_100: Option<u32>,
+    pub count_50: Option<u32>,
+    pub misses: Option<u32>,
+    /// Defaults to the beatmap's max combo
+    pub combo: Option<u32>,
+}
+
+impl PerformanceBody {
+    fn has_hit_counts(&self) -> bool {
+        [
+            self.count_geki,
+            self.count_300,
+            self.count_katu,
+            self.count_100,
+            self.count_50,
+        ]
+        .iter()
+        .any(Option::is_some)
+    }
+
+    /// Build the hit counts of the score, either from the given counts or spread out to reach
+    /// the given accuracy.
+    fn statistics(&self, mode: GameMode, total_hits: u32) -> Result<ScoreStatistics> {
+        let misses = self.misses.unwrap_or(0);
+
+        if !self.has_hit_counts() {
+            let accuracy = self.accuracy.unwrap_or(100.0);
+            if !(0.0..=100.0).contains(&accuracy) {
+                return Err(error::Error::unprocessable_entity([(
+                    "accuracy",
+                    "accuracy must be between 0 and 100",
+                )]));
+            }
+
+            return Ok(performance::statistics_for_accuracy(
+                mode, total_hits, accuracy, misses,
+            ));
+        }
+
+        let mut statistics = ScoreStatistics {
+            count_geki: self.count_geki.unwrap_or(0),
+            count_300: self.count_300.unwrap_or(0),
+            count_katu: self.count_katu.unwrap_or(0),
+            count_100: self.count_100.unwrap_or(0),
+            count_50: self.count_50.unwrap_or(0),
+            count_miss: misses,
+        };
+
+        // tiny droplets don't add to the combo, so they aren't part of catch's object count
+        let counted = match mode {
+            GameMode::Catch => statistics.count_300 + statistics.count_100 + statistics.count_miss,
+            _ => statistics.total_hits(mode),
+        };
+        if counted > total_hits {
+            return Err(error::Error::unprocessable_entity([(
+                "count_300",
+                format!(
+                    "hit counts add up to {} but the beatmap only has {} objects",
+                    counted, total_hits
+                ),
+            )]));
+        }
+
+        if self.count_300.is_none() {
+            statistics.count_300 = total_hits - counted;
+        }
+
+        Ok(statistics)
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct AccuracyPerformance {
+    pub accuracy: f64,
+    pub pp: f32,
+}
+
+#[derive(serde::Serialize)]
+pub struct BeatmapPerformance {
+    pub beatmap_id: u32,
+    pub mode: GameMode,
+    pub mods: String,
+    pub pp: f32,
+    pub accuracy: f32,
+    pub combo: u32,
+    pub statistics: ScoreStatistics,
+    pub star_rating: f32,
+    pub max_combo: u32,
+    /// Performance points of full combos at common accuracies
+    pub accuracy_table: Vec<AccuracyPerformance>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/beatmaps/{beatmap_id}/pp",
+    tag = "v2",
+    request_body = PerformanceBody,
+    responses(
+        (status = 200, description = "Calculated performance points of the score"),
+        (status = 404, description = "Beatmap not found"),
+        (status = 422, description = "Invalid score")
+    ),
+    params(
+        ("beatmap_id" = u32, Path, description = "Beatmap id")
+    )
+)]
+async fn calculate_pp(
+    ctx: Extension<Context>,
+    Path(beatmap_id): Path<u32>,
+    Json(body): Json<PerformanceBody>,
+) -> Result<Json<BeatmapPerformance>> {
+    let mods = filters::parse_mods(body.mods.as_deref())?;
+    let mode = filters::parse_game_mode(body.mode.as_deref())?;
+
+    let (beatmap, mode, attributes) = beatmap_attributes(&ctx, beatmap_id, mode, mods).await?;
+    let total_hits = performance::total_hits(&beatmap.data, &attributes);
+
+    let statistics = body.statistics(mode, total_hits)?;
+    let combo = body.combo.unwrap_or(attributes.max_combo);
+    if combo > attributes.max_combo {
+        return Err(error::Error::unprocessable_entity([(
+            "combo",
+            format!(
+                "combo must not be above the max combo of {}",
+                attributes.max_combo
+            ),
+        )]));
+    }
+
+    let score = Score {
+        mods,
+        statistics,
+        combo,
+    };
+    let pp = performance::calculate(&beatmap.data, &attributes, &score);
+
+    let accuracy_table = TABLE_ACCURACIES
+        .iter()
+        .map(|&accuracy| {
+            let full_combo = Score {
+                mods,
+                statistics: performance::statistics_for_accuracy(mode, total_hits, accuracy, 0),
+                combo: attributes.max_combo,
+            };
+
+            AccuracyPerformance {
+                accuracy,
+                pp: performance::calculate(&beatmap.data, &attributes, &full_combo),
+            }
+        })
+        .collect();
+
+    Ok(Json(BeatmapPerformance {
+        beatmap_id,
+        mode,
+        mods: mods.to_string(),
+        pp,
+        accuracy: score.statistics.accuracy(mode),
+        combo,
+        statistics: score.statistics,
+        star_rating: attributes.stars,
+        max_combo: attributes.max_combo,
+        accuracy_table,
+    }))
+}
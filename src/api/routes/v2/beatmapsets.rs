@@ -1,21 +1,34 @@
 use crate::{
-    api::{error, Result},
-    models::{mode::Mode, ranked_status::RankedStatus},
-    repositories, usecases, Context,
+    api::{
+        conditional::Validators,
+        error,
+        filters::{self, RandomParams},
+        lookup::{self, LookupBody},
+        pagination, Result,
+    },
+    models::{search_facets::SearchFacets, search_filters::SearchFilters, search_sort::SearchSort},
+    repositories::{self, beatmapsets::Pagination},
+    usecases, Context,
 };
 use axum::{
-    extract::{Extension, Path, Query},
+    body::BoxBody,
+    extract::{Extension, Path, Query, RawQuery},
+    http::{HeaderMap, Response},
     response::IntoResponse,
     routing::get,
     Json, Router,
 };
-use num_traits::FromPrimitive;
 use rosu_v2::prelude::Beatmapset as OsuBeatmapset;
 
 pub fn router() -> Router {
     Router::new()
         .route("/api/v2/beatmapsets/:beatmapset_id", get(get_beatmapset))
         .route("/api/v2/beatmapsets/search", get(search_beatmapsets))
+        .route("/api/v2/beatmapsets/random", get(random_beatmapset))
+        .route(
+            "/api/v2/beatmapsets/lookup",
+            get(lookup_beatmapsets_by_query).post(lookup_beatmapsets),
+        )
 }
 
 #[utoipa::path(
@@ -24,6 +37,7 @@ pub fn router() -> Router {
     tag = "v2",
     responses(
         (status = 200, description = "Found beatmapset successfully"),
+        (status = 304, description = "Beatmapset not modified"),
         (status = 404, description = "Beatmapset not found")
     ),
     params(
@@ -33,15 +47,89 @@ pub fn router() -> Router {
 async fn get_beatmapset(
     ctx: Extension<Context>,
     Path(beatmapset_id): Path<u32>,
-) -> Result<Json<Option<OsuBeatmapset>>> {
+    headers: HeaderMap,
+) -> Result<Response<BoxBody>> {
     let beatmapset = usecases::beatmapsets::fetch(&ctx, beatmapset_id).await?;
 
     match beatmapset {
-        Some(beatmapset) => Ok(Json(Some(beatmapset.data))),
+        Some(beatmapset) => {
+            let validators =
+                Validators::new(beatmapset_id, beatmapset.updated_at, beatmapset.data.status);
+
+            Ok(validators.respond(&headers, || Json(beatmapset.data)))
+        }
         None => Err(error::Error::NotFound),
     }
 }
 
+#[derive(serde::Serialize)]
+pub struct BeatmapsetLookup {
+    pub id: u32,
+    pub found: bool,
+    pub beatmapset: Option<OsuBeatmapset>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/beatmapsets/lookup",
+    tag = "v2",
+    request_body = LookupBody,
+    responses(
+        (status = 200, description = "Looked up beatmapsets, in request order"),
+        (status = 422, description = "Invalid ids")
+    )
+)]
+async fn lookup_beatmapsets(
+    ctx: Extension<Context>,
+    Json(body): Json<LookupBody>,
+) -> Result<Json<Vec<BeatmapsetLookup>>> {
+    lookup(&ctx, body.ids).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/beatmapsets/lookup",
+    tag = "v2",
+    responses(
+        (status = 200, description = "Looked up beatmapsets, in request order"),
+        (status = 422, description = "Invalid ids")
+    ),
+    params(
+        ("ids[]" = Vec<u32>, Query, description = "Beatmapset ids")
+    )
+)]
+async fn lookup_beatmapsets_by_query(
+    ctx: Extension<Context>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<BeatmapsetLookup>>> {
+    let beatmapset_ids = lookup::ids_from_query(query.as_deref())?;
+
+    lookup(&ctx, beatmapset_ids).await
+}
+
+async fn lookup(ctx: &Context, beatmapset_ids: Vec<u32>) -> Result<Json<Vec<BeatmapsetLookup>>> {
+    lookup::validate_ids(&beatmapset_ids)?;
+
+    let beatmapsets = usecases::beatmapsets::fetch_many(ctx, &beatmapset_ids).await?;
+
+    let lookups = beatmapset_ids
+        .into_iter()
+        .map(|beatmapset_id| {
+            let beatmapset = beatmapsets
+                .get(&beatmapset_id)
+                .map(|beatmapset| beatmapset.data.clone());
+
+            BeatmapsetLookup {
+                id: beatmapset_id,
+                found: beatmapset.is_some(),
+                beatmapset,
+            }
+        })
+        .collect();
+
+    Ok(Json(lookups))
+}
+
 #[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
 pub struct SearchParams {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -50,12 +138,76 @@ pub struct SearchParams {
     pub amount: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub offset: Option<u64>,
+    /// Comma separated statuses, by name or numeric code (defaults to ranked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Comma separated modes, by name or numeric code (defaults to all)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Comma separated mania key counts, also accepted as `keys=N` in the query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<String>,
+    /// Whether converted difficulties count when filtering by mode, keys, star rating or length,
+    /// as 1/0 or true/false (defaults to false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converts: Option<String>,
+    /// Comma separated genres, by name or id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    /// Comma separated languages, by name or id
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub status: Option<i8>,
+    pub language: Option<String>,
+    /// Whether to include NSFW beatmapsets, as 1/0 or true/false (defaults to false)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub mode: Option<i8>,
+    pub nsfw: Option<String>,
+    /// Only beatmapsets with (1 or true) or without (0 or false) a video
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video: Option<String>,
+    /// Only beatmapsets with (1 or true) or without (0 or false) a storyboard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storyboard: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_stars: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_stars: Option<f32>,
+    /// Minimum total length in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u32>,
+    /// Maximum total length in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub osu_direct: Option<bool>,
+    /// osu!direct's ranked filter (`r`), used instead of `status`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direct_status: Option<i8>,
+    /// Cursor returned with the previous page, or empty to start paging with cursors. Implies
+    /// `paged`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Whether to return an object with the beatmapsets, total and cursor rather than only the
+    /// beatmapsets, as 1/0 or true/false (defaults to false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paged: Option<String>,
+    /// Sort order, e.g. `plays_desc` or `title_asc` (defaults to relevance)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Whether to return result counts per status, mode, genre, language and star rating with
+    /// the first page, as 1/0 or true/false. Implies `paged`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SearchResponse {
+    pub beatmapsets: Vec<OsuBeatmapset>,
+    pub total: u64,
+    /// Pass as `cursor` to get the next page, only given when paging with cursors and absent on
+    /// the last page
+    pub cursor: Option<String>,
+    /// Only given with the first page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<SearchFacets>,
 }
 
 #[utoipa::path(
@@ -64,38 +216,112 @@ pub struct SearchParams {
     tag = "v2",
     params(SearchParams),
     responses(
-        (status = 200, description = "Beatmapsets found"),
+        (status = 200, description = "Beatmapsets found, as an array unless paged"),
+        (status = 422, description = "Invalid filters, page size, offset or cursor")
     ),
 )]
 async fn search_beatmapsets(
     ctx: Extension<Context>,
     Query(params): Query<SearchParams>,
 ) -> Result<impl IntoResponse> {
-    let beatmapsets = repositories::beatmapsets::search(
+    let amount = params.amount.unwrap_or(pagination::MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+    pagination::validate_page(amount, offset)?;
+
+    let statuses = match params.direct_status {
+        Some(direct_status) => filters::parse_direct_statuses(direct_status)?,
+        None => filters::parse_statuses(params.status.as_deref())?,
+    };
+    let modes = filters::parse_modes(params.mode.as_deref())?;
+
+    let (query, mut keys) = filters::extract_query_keys(params.query)?;
+    keys.extend(filters::parse_keys(params.keys.as_deref())?);
+
+    let include_converts = filters::parse_flag("converts", params.converts.as_deref())?;
+    let search_filters = SearchFilters {
+        query,
+        statuses,
+        modes,
+        keys,
+        include_converts,
+        genres: filters::parse_genres(params.genre.as_deref())?,
+        languages: filters::parse_languages(params.language.as_deref())?,
+        include_nsfw: filters::parse_flag("nsfw", params.nsfw.as_deref())?,
+        video: filters::parse_optional_flag("video", params.video.as_deref())?,
+        storyboard: filters::parse_optional_flag("storyboard", params.storyboard.as_deref())?,
+        min_stars: params.min_stars,
+        max_stars: params.max_stars,
+        min_length: params.min_length,
+        max_length: params.max_length,
+        mapper_id: None,
+    };
+    filters::validate_ranges(&search_filters)?;
+
+    let direct_response = params.osu_direct.unwrap_or(false);
+
+    // osu!direct clients page through results themselves
+    let search_pagination = match direct_response {
+        true => Pagination::Offset(offset),
+        false => pagination::search_pagination(params.offset, params.cursor.as_deref())?,
+    };
+    let sort = filters::parse_sort(params.sort.as_deref(), SearchSort::default())?;
+
+    let with_facets = filters::parse_flag("facets", params.facets.as_deref())? && !direct_response;
+    let paged = filters::parse_flag("paged", params.paged.as_deref())?
+        || params.cursor.is_some()
+        || with_facets;
+
+    let search_page = repositories::beatmapsets::search(
         &ctx,
-        params.query,
-        params.amount.unwrap_or(100),
-        params.offset.unwrap_or(0),
-        params
-            .status
-            .map(|s| FromPrimitive::from_i8(s).unwrap_or(RankedStatus::Ranked))
-            .unwrap_or(RankedStatus::Ranked),
-        params
-            .mode
-            .map(|s| FromPrimitive::from_i8(s).unwrap_or(Mode::All))
-            .unwrap_or(Mode::All),
+        &search_filters,
+        sort,
+        amount,
+        search_pagination,
+        with_facets,
     )
-    .await?;
+    .await?
+    .ok_or_else(pagination::expired_cursor)?;
 
-    let osu_beatmapsets: Vec<OsuBeatmapset> = beatmapsets
+    let osu_beatmapsets: Vec<OsuBeatmapset> = search_page
+        .beatmapsets
         .into_iter()
         .map(|beatmapset| beatmapset.data)
         .collect();
 
-    let direct_response = params.osu_direct.unwrap_or(false);
-
-    Ok(match direct_response {
-        true => usecases::beatmapsets::format_to_direct(osu_beatmapsets).into_response(),
-        false => Json(osu_beatmapsets).into_response(),
+    Ok(match (direct_response, paged) {
+        (true, _) => usecases::beatmapsets::format_to_direct(osu_beatmapsets).into_response(),
+        (false, true) => Json(SearchResponse {
+            beatmapsets: osu_beatmapsets,
+            total: search_page.total,
+            cursor: search_page.cursor.map(|cursor| cursor.encode()),
+            facets: search_page.facets,
+        })
+        .into_response(),
+        (false, false) => Json(osu_beatmapsets).into_response(),
     })
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/beatmapsets/random",
+    tag = "v2",
+    params(RandomParams),
+    responses(
+        (status = 200, description = "Picked a random beatmapset"),
+        (status = 404, description = "No beatmapsets match the filters"),
+        (status = 422, description = "Invalid filters")
+    ),
+)]
+async fn random_beatmapset(
+    ctx: Extension<Context>,
+    Query(params): Query<RandomParams>,
+) -> Result<Json<OsuBeatmapset>> {
+    let search_filters = params.search_filters()?;
+    let weighted = filters::parse_flag("weighted", params.weighted.as_deref())?;
+
+    // filtering difficulties one by one, since they aren't nested in beatmapset documents
+    match usecases::beatmaps::random(&ctx, &search_filters, params.seed, weighted, true).await? {
+        Some((_, beatmapset)) => Ok(Json(beatmapset.data)),
+        None => Err(error::Error::NotFound),
+    }
+}
@@ -1,2 +1,4 @@
 pub mod beatmaps;
 pub mod beatmapsets;
+pub mod suggest;
+pub mod users;
@@ -0,0 +1,62 @@
+use crate::{
+    api::{error, Result},
+    models::suggestions::Suggestions,
+    repositories, Context,
+};
+use axum::{
+    extract::{Extension, Query},
+    routing::get,
+    Json, Router,
+};
+
+/// Upper bound on how many suggestions of each kind can be requested.
+const MAX_SUGGESTIONS: u64 = 10;
+
+pub fn router() -> Router {
+    Router::new().route("/api/v2/suggest", get(suggest))
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct SuggestParams {
+    /// Text typed so far
+    pub q: String,
+    /// How many suggestions of each kind to return (defaults to 5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/suggest",
+    tag = "v2",
+    params(SuggestParams),
+    responses(
+        (status = 200, description = "Artist, title and mapper completions"),
+        (status = 422, description = "Invalid amount")
+    ),
+)]
+async fn suggest(
+    ctx: Extension<Context>,
+    Query(params): Query<SuggestParams>,
+) -> Result<Json<Suggestions>> {
+    let amount = params.amount.unwrap_or(5);
+    if amount == 0 || amount > MAX_SUGGESTIONS {
+        return Err(error::Error::unprocessable_entity([(
+            "amount",
+            format!("amount must be between 1 and {}", MAX_SUGGESTIONS),
+        )]));
+    }
+
+    let prefix = params.q.trim();
+    if prefix.is_empty() {
+        return Ok(Json(Suggestions {
+            artists: vec![],
+            titles: vec![],
+            creators: vec![],
+        }));
+    }
+
+    let suggestions = repositories::beatmapsets::suggest(&ctx, prefix, amount).await?;
+
+    Ok(Json(suggestions))
+}
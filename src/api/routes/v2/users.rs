@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Extension, Path, Query},
+    routing::get,
+    Json, Router,
+};
+use num_traits::FromPrimitive;
+use rosu_v2::prelude::Beatmapset as OsuBeatmapset;
+
+use crate::{
+    api::{filters, pagination, Result},
+    models::{
+        mode::Mode,
+        ranked_status::RankedStatus,
+        search_filters::SearchFilters,
+        search_sort::{SearchSort, SortField},
+    },
+    repositories, Context,
+};
+
+pub fn router() -> Router {
+    Router::new().route(
+        "/api/v2/users/:user_id/beatmapsets",
+        get(get_user_beatmapsets),
+    )
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct UserBeatmapsetsParams {
+    /// Comma separated statuses, by name or numeric code (defaults to all)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Comma separated modes, by name or numeric code (defaults to all)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Cursor returned with the previous page, or empty to start paging with cursors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Sort order, e.g. `plays_desc` or `title_asc` (defaults to updated_desc)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct UserBeatmapsetsResponse {
+    /// The user's own beatmapsets on this page, by status. Pages are sorted as a whole, so a
+    /// status can continue on the next page; filter by `status` to page through one at a time
+    pub beatmapsets: BTreeMap<&'static str, Vec<OsuBeatmapset>>,
+    /// Beatmapsets on this page by other mappers, with only the user's guest difficulties
+    pub guest_beatmapsets: Vec<OsuBeatmapset>,
+    pub total: u64,
+    /// Pass as `cursor` to get the next page, only given when paging with cursors and absent on
+    /// the last page
+    pub cursor: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/users/{user_id}/beatmapsets",
+    tag = "v2",
+    params(
+        ("user_id" = u32, Path, description = "User id"),
+        UserBeatmapsetsParams
+    ),
+    responses(
+        (status = 200, description = "The user's beatmapsets and guest difficulties, grouped by status within the page"),
+        (status = 422, description = "Invalid filters, page size, offset or cursor")
+    ),
+)]
+async fn get_user_beatmapsets(
+    ctx: Extension<Context>,
+    Path(user_id): Path<u32>,
+    Query(params): Query<UserBeatmapsetsParams>,
+) -> Result<Json<UserBeatmapsetsResponse>> {
+    let amount = params.amount.unwrap_or(pagination::MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+    pagination::validate_page(amount, offset)?;
+
+    // matching by id rather than name keeps sets from before the mapper was renamed
+    let search_filters = SearchFilters {
+        statuses: match params.status {
+            Some(status) => filters::parse_statuses(Some(&status))?,
+            None => vec![RankedStatus::All],
+        },
+        modes: filters::parse_modes(params.mode.as_deref())?,
+        include_nsfw: true,
+        mapper_id: Some(user_id),
+        ..Default::default()
+    };
+
+    let sort = filters::parse_sort(
+        params.sort.as_deref(),
+        SearchSort {
+            field: SortField::Updated,
+            descending: true,
+        },
+    )?;
+
+    let search_pagination = pagination::search_pagination(params.offset, params.cursor.as_deref())?;
+
+    let search_page = repositories::beatmapsets::search(
+        &ctx,
+        &search_filters,
+        sort,
+        amount,
+        search_pagination,
+        false,
+    )
+    .await?
+    .ok_or_else(pagination::expired_cursor)?;
+
+    let mut beatmapsets: BTreeMap<&'static str, Vec<OsuBeatmapset>> = BTreeMap::new();
+    let mut guest_beatmapsets = Vec::new();
+
+    let any_mode = search_filters.modes.is_empty() || search_filters.modes.contains(&Mode::All);
+
+    for beatmapset in search_page.beatmapsets {
+        if beatmapset.data.creator_id != user_id {
+            let mut beatmapset = beatmapset.data;
+            if let Some(beatmaps) = &mut beatmapset.maps {
+                beatmaps.retain(|beatmap| {
+                    beatmap.creator_id == user_id
+                        && (any_mode
+                            || search_filters
+                                .modes
+                                .iter()
+                                .any(|mode| *mode as i8 == beatmap.mode as i8))
+                });
+            }
+
+            // the set may only match the mode filter through other mappers' difficulties
+            if beatmapset
+                .maps
+                .as_ref()
+                .is_some_and(|beatmaps| !beatmaps.is_empty())
+            {
+                guest_beatmapsets.push(beatmapset);
+            }
+            continue;
+        }
+
+        let status: RankedStatus = match FromPrimitive::from_i8(beatmapset.data.status as i8) {
+            Some(status) => status,
+            None => {
+                log::warn!(
+                    "skipping beatmapset {} with unknown status {}",
+                    beatmapset.data.mapset_id,
+                    beatmapset.data.status as i8
+                );
+                continue;
+            }
+        };
+
+        beatmapsets
+            .entry(status.name())
+            .or_default()
+            .push(beatmapset.data);
+    }
+
+    Ok(Json(UserBeatmapsetsResponse {
+        beatmapsets,
+        guest_beatmapsets,
+        total: search_page.total,
+        cursor: search_page.cursor.map(|cursor| cursor.encode()),
+    }))
+}
@@ -24,6 +24,18 @@ pub struct Config {
     #[clap(long, env)]
     pub elastic_beatmapsets_index: String,
 
+    #[clap(long, env, default_value = "api_keys")]
+    pub elastic_api_keys_index: String,
+
+    #[clap(long, env, default_value = "crawl_queue")]
+    pub elastic_crawl_queue_index: String,
+
+    #[clap(long, env, default_value = "difficulty_attributes")]
+    pub elastic_difficulty_attributes_index: String,
+
+    #[clap(long, env, default_value = "archives")]
+    pub elastic_archives_index: String,
+
     #[clap(long, env)]
     pub osu_api_client_id: u64,
 
@@ -50,4 +62,59 @@ pub struct Config {
 
     #[clap(long, env)]
     pub max_timeout: u64,
+
+    #[clap(long, env)]
+    pub archive_path: Option<String>,
+
+    /// Megabytes of disk stored archives may take up, unlimited if unset
+    #[clap(long, env)]
+    pub archive_budget: Option<u64>,
+
+    /// Whether ranked, approved and loved archives may be evicted to stay within the budget
+    #[clap(long, env, parse(try_from_str), default_value = "false")]
+    pub evict_ranked_archives: bool,
+
+    /// Seconds to remember ids that osu! reported as not found
+    #[clap(long, env, default_value = "60")]
+    pub not_found_cache_ttl: u64,
+
+    /// Maximum documents of each kind kept in memory in front of elasticsearch, 0 to disable
+    #[clap(long, env, default_value = "0")]
+    pub hot_cache_capacity: u64,
+
+    /// Seconds to keep ranked, approved and loved documents in memory. Each component has its own
+    /// cache, so changes the updater makes only reach the api once this expires
+    #[clap(long, env, default_value = "3600")]
+    pub hot_cache_ranked_ttl: u64,
+
+    /// Seconds to keep documents of any other status in memory
+    #[clap(long, env, default_value = "60")]
+    pub hot_cache_unranked_ttl: u64,
+
+    /// Requests per minute allowed per ip for requests without an api key, 0 to disable. Behind a
+    /// reverse proxy, set `trusted_proxies` too, or every client shares the proxy's limit
+    #[clap(long, env, default_value = "0")]
+    pub rate_limit_per_minute: u32,
+
+    /// Comma separated addresses or ranges, like 10.0.0.0/8, of reverse proxies trusted to set
+    /// X-Forwarded-For
+    #[clap(long, env, use_value_delimiter = true)]
+    pub trusted_proxies: Vec<String>,
+
+    /// Documents per second the migrate component reindexes, to keep search responsive
+    #[clap(long, env, default_value = "500")]
+    pub reindex_requests_per_second: u32,
+
+    /// Archives the prefetcher downloads per minute
+    #[clap(long, env, default_value = "10")]
+    pub prefetch_per_minute: u32,
+
+    /// Order the prefetcher downloads archives in, plays or favourites, optionally followed by
+    /// _asc or _desc
+    #[clap(long, env, default_value = "plays")]
+    pub prefetch_order: String,
+
+    /// Stale archives the verifier downloads again per minute
+    #[clap(long, env, default_value = "10")]
+    pub verify_downloads_per_minute: u32,
 }
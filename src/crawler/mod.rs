@@ -1,7 +1,11 @@
 use crate::{
     helpers::elastic,
-    models::{beatmap::Beatmap, beatmapset::Beatmapset},
-    repositories, Context,
+    models::{
+        beatmap::Beatmap,
+        beatmapset::Beatmapset,
+        crawl_request::{CrawlKind, CrawlRequest},
+    },
+    repositories, usecases, Context,
 };
 use elasticsearch::SearchParts;
 use elasticsearch_dsl::{Aggregation, Query, Search};
@@ -44,14 +48,14 @@ async fn crawl_beatmaps(ctx: &Context) -> anyhow::Result<()> {
 
         current_id += 50;
 
-        let mut osu_beatmaps =
-            match repositories::osu::beatmaps::bulk_fetch(&ctx, beatmap_ids).await {
-                Ok(beatmaps) => beatmaps,
-                Err(_) => {
-                    log::error!("error while fetching beatmaps from id {}", current_id);
-                    vec![]
-                }
-            };
+        let mut osu_beatmaps = match repositories::osu::beatmaps::bulk_fetch(ctx, beatmap_ids).await
+        {
+            Ok(beatmaps) => beatmaps,
+            Err(_) => {
+                log::error!("error while fetching beatmaps from id {}", current_id);
+                vec![]
+            }
+        };
 
         let beatmaps_found = osu_beatmaps.len();
 
@@ -69,11 +73,13 @@ async fn crawl_beatmaps(ctx: &Context) -> anyhow::Result<()> {
                     updated_at: now,
                     last_checked: now,
                     crawled: true,
+                    hidden: false,
+                    parsed: None,
                 })
                 .collect();
 
             last_successful_id = beatmaps.iter().map(|b| b.data.map_id).max().unwrap() as u64;
-            repositories::beatmaps::bulk_create(&ctx, beatmaps).await?;
+            repositories::beatmaps::bulk_create(ctx, beatmaps).await?;
         } else {
             if backoff_time < ctx.config.max_backoff {
                 backoff_time = backoff_time.powf(2_f64).min(ctx.config.max_backoff);
@@ -125,7 +131,7 @@ async fn crawl_beatmapsets(ctx: &Context) -> anyhow::Result<()> {
     let mut backoff_time: f64 = ctx.config.backoff_start;
 
     loop {
-        let beatmapset = match repositories::osu::beatmapsets::fetch(&ctx, current_id).await {
+        let beatmapset = match repositories::osu::beatmapsets::fetch(ctx, current_id).await {
             Ok(beatmapset) => beatmapset,
             Err(_) => {
                 log::error!("error while fetching beatmapset {}", current_id);
@@ -144,9 +150,10 @@ async fn crawl_beatmapsets(ctx: &Context) -> anyhow::Result<()> {
                 updated_at: current_time,
                 last_checked: current_time,
                 crawled: true,
+                hidden: false,
             };
 
-            repositories::beatmapsets::create(&ctx, beatmapset).await?;
+            repositories::beatmapsets::create(ctx, beatmapset).await?;
             last_successful_id = current_id - 1;
             log::info!("indexed beatmapset {}", current_id - 1);
         } else {
@@ -170,16 +177,61 @@ async fn crawl_beatmapsets(ctx: &Context) -> anyhow::Result<()> {
     }
 }
 
+async fn recrawl(ctx: &Context, crawl_request: &CrawlRequest) {
+    for id in crawl_request.start_id..=crawl_request.end_id {
+        let result = match crawl_request.kind {
+            CrawlKind::Beatmaps => usecases::beatmaps::refresh(ctx, id).await.map(|_| ()),
+            CrawlKind::Beatmapsets => usecases::beatmapsets::refresh(ctx, id).await.map(|_| ()),
+        };
+
+        if let Err(e) = result {
+            log::error!(
+                "error while recrawling {:?} id {}: {}",
+                crawl_request.kind,
+                id,
+                e
+            );
+        }
+    }
+}
+
+async fn process_crawl_queue(ctx: &Context) -> anyhow::Result<()> {
+    elastic::create_index_if_not_exists(&ctx.database, &ctx.config.elastic_crawl_queue_index)
+        .await?;
+
+    loop {
+        let (id, crawl_request) = match repositories::crawl_queue::next(ctx).await? {
+            Some(queued) => queued,
+            None => {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            }
+        };
+
+        log::info!(
+            "recrawling {:?} from id {} to {}",
+            crawl_request.kind,
+            crawl_request.start_id,
+            crawl_request.end_id
+        );
+
+        recrawl(ctx, &crawl_request).await;
+        repositories::crawl_queue::remove(ctx, &id).await?;
+    }
+}
+
 pub async fn serve(context: Context) -> anyhow::Result<()> {
     let context_arc = Arc::new(context);
     let beatmaps_context = context_arc.clone();
     let beatmapsets_context = context_arc.clone();
+    let crawl_queue_context = context_arc.clone();
 
     let res = tokio::try_join!(
         tokio::spawn(async move { crawl_beatmaps(beatmaps_context.as_ref()).await }),
-        tokio::spawn(async move { crawl_beatmapsets(beatmapsets_context.as_ref()).await })
+        tokio::spawn(async move { crawl_beatmapsets(beatmapsets_context.as_ref()).await }),
+        tokio::spawn(async move { process_crawl_queue(crawl_queue_context.as_ref()).await })
     );
-    if !res.is_ok() {
+    if res.is_err() {
         anyhow::bail!("crawler failed: {:?}", res);
     }
 
@@ -0,0 +1,164 @@
+use std::io::{Cursor, Read};
+
+use md5::{Digest, Md5};
+use zip::{read::ZipFile, ZipArchive};
+
+/// Largest file read from an archive, well above any beatmap's audio or background, so that
+/// an entry claiming or inflating to more can't exhaust memory.
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Read every file in a beatmapset archive whose name ends with the extension, as
+/// `(file name, contents)` pairs.
+pub fn read_files_with_extension(
+    archive: &[u8],
+    extension: &str,
+) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let mut zip = ZipArchive::new(Cursor::new(archive))?;
+    let mut files = Vec::new();
+
+    for index in 0..zip.len() {
+        let mut file = zip.by_index(index)?;
+        if !file.is_file() || !file.name().to_lowercase().ends_with(extension) {
+            continue;
+        }
+
+        let contents = read_entry(&mut file, MAX_FILE_SIZE)?;
+        files.push((file.name().to_string(), contents));
+    }
+
+    Ok(files)
+}
+
+/// Read an entry of an archive, failing if it is larger than `max_size`, which is
+/// `MAX_FILE_SIZE` outside of tests. The size in the archive may not be the real one, so
+/// reading stops once past the limit either way.
+fn read_entry(file: &mut ZipFile, max_size: u64) -> anyhow::Result<Vec<u8>> {
+    if file.size() > max_size {
+        anyhow::bail!("{} is larger than {} bytes", file.name(), max_size);
+    }
+
+    let mut contents = Vec::with_capacity(file.size() as usize);
+    file.by_ref()
+        .take(max_size + 1)
+        .read_to_end(&mut contents)?;
+    if contents.len() as u64 > max_size {
+        anyhow::bail!("{} is larger than {} bytes", file.name(), max_size);
+    }
+
+    Ok(contents)
+}
+
+/// List the names of the files in a beatmapset archive.
+pub fn file_names(archive: &[u8]) -> anyhow::Result<Vec<String>> {
+    let zip = ZipArchive::new(Cursor::new(archive))?;
+
+    Ok(zip.file_names().map(|name| name.to_string()).collect())
+}
+
+/// Read a file from a beatmapset archive by the name a `.osu` file refers to it with, which
+/// may differ in case and path separators from the name in the archive.
+pub fn read_file(archive: &[u8], name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let normalize = |name: &str| {
+        name.replace('\\', "/")
+            .trim_start_matches("./")
+            .to_lowercase()
+    };
+    let name = normalize(name);
+
+    let mut zip = ZipArchive::new(Cursor::new(archive))?;
+
+    for index in 0..zip.len() {
+        let mut file = zip.by_index(index)?;
+        if !file.is_file() || normalize(file.name()) != name {
+            continue;
+        }
+
+        return Ok(Some(read_entry(&mut file, MAX_FILE_SIZE)?));
+    }
+
+    Ok(None)
+}
+
+/// Hash every `.osu` file in a beatmapset archive, as the lowercase hex md5 digests osu!
+/// reports as beatmap checksums.
+pub fn osu_checksums(archive: &[u8]) -> anyhow::Result<Vec<String>> {
+    Ok(read_files_with_extension(archive, ".osu")?
+        .iter()
+        .map(|(_, contents)| format!("{:x}", Md5::digest(contents)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in files {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn osu_checksums_hash_only_osu_files() {
+        let archive = zip(&[
+            ("Artist - Title (Mapper) [Easy].osu", b"easy"),
+            ("Artist - Title (Mapper) [Hard].OSU", b"hard"),
+            ("audio.mp3", b"audio"),
+        ]);
+
+        assert_eq!(
+            osu_checksums(&archive).unwrap(),
+            vec![
+                format!("{:x}", Md5::digest(b"easy")),
+                format!("{:x}", Md5::digest(b"hard")),
+            ]
+        );
+    }
+
+    #[test]
+    fn osu_checksums_fail_on_something_other_than_an_archive() {
+        assert!(osu_checksums(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn read_file_ignores_case_and_separators() {
+        let archive = zip(&[
+            ("SB/Background.JPG", b"background"),
+            ("audio.mp3", b"audio"),
+        ]);
+
+        for name in [
+            "sb/background.jpg",
+            "SB\\background.jpg",
+            "./sb/BACKGROUND.jpg",
+        ] {
+            assert_eq!(
+                read_file(&archive, name).unwrap().as_deref(),
+                Some(&b"background"[..]),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(read_file(&archive, "background.jpg").unwrap(), None);
+    }
+
+    #[test]
+    fn read_entry_caps_the_size() {
+        let archive = zip(&[("audio.mp3", &[0; 17])]);
+        let mut zip = ZipArchive::new(Cursor::new(&archive[..])).unwrap();
+
+        assert_eq!(
+            read_entry(&mut zip.by_index(0).unwrap(), 17).unwrap().len(),
+            17
+        );
+        assert!(read_entry(&mut zip.by_index(0).unwrap(), 16).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{DateTime, Utc};
+
+/// Counts of archive store activity since the process started. Every process counts its own,
+/// so requests served by another api process or archives evicted by the prefetcher aren't
+/// included.
+pub struct ArchiveStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    evicted_bytes: AtomicU64,
+    since: DateTime<Utc>,
+}
+
+pub struct ArchiveCounts {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub evicted_bytes: u64,
+    pub since: DateTime<Utc>,
+}
+
+impl Default for ArchiveStats {
+    fn default() -> Self {
+        ArchiveStats {
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            evicted_bytes: AtomicU64::new(0),
+            since: Utc::now(),
+        }
+    }
+}
+
+impl ArchiveStats {
+    /// An archive was served from the store.
+    pub fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An archive had to be downloaded from osu!.
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn evicted(&self, size: u64) {
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+        self.evicted_bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub fn counts(&self) -> ArchiveCounts {
+        ArchiveCounts {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            evicted_bytes: self.evicted_bytes.load(Ordering::Relaxed),
+            since: self.since,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::Cursor;
+
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, errors::Error, formats::FormatOptions,
+    io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+/// Mono audio, with samples between -1 and 1.
+pub struct Audio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Audio {
+    /// Length of the audio in microseconds.
+    pub fn duration(&self) -> u64 {
+        self.samples.len() as u64 * 1_000_000 / self.sample_rate as u64
+    }
+
+    /// The samples of a section of the audio, given in microseconds.
+    pub fn section(&self, start: u64, length: u64) -> &[f32] {
+        let index = |time: u64| {
+            ((time * self.sample_rate as u64 / 1_000_000) as usize).min(self.samples.len())
+        };
+
+        &self.samples[index(start)..index(start + length)]
+    }
+}
+
+/// Decode a song to mono, stopping once `limit` microseconds of it are decoded. The extension of
+/// its filename helps tell its format.
+///
+/// Returns `None` if its format isn't supported or no audio could be decoded.
+pub fn decode(data: Vec<u8>, extension: Option<&str>, limit: Option<u64>) -> Option<Audio> {
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+
+    let source = MediaSourceStream::new(Box::new(Cursor::new(data)), Default::default());
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?
+        .format;
+
+    let track = format.default_track()?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .ok()?;
+
+    let mut samples = Vec::new();
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+
+    // reading stops at the end of the stream or at the first part that can't be read
+    while let Ok(packet) = format.next_packet() {
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // a damaged packet, or one referring to data before the start of a cut stream, is
+            // silence so the rest stays in time
+            Err(Error::DecodeError(_)) => {
+                samples.resize(samples.len() + packet.dur() as usize, 0.0);
+                continue;
+            }
+            Err(_) => break,
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        let rate = *sample_rate.get_or_insert(spec.rate);
+
+        if buffer
+            .as_ref()
+            .is_none_or(|buffer| buffer.capacity() < decoded.capacity() * channels)
+        {
+            buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+        let buffer = buffer.as_mut().unwrap();
+        buffer.copy_interleaved_ref(decoded);
+
+        samples.extend(
+            buffer
+                .samples()
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+
+        if limit.is_some_and(|limit| samples.len() as u64 * 1_000_000 >= limit * rate as u64) {
+            break;
+        }
+    }
+
+    match sample_rate {
+        Some(sample_rate) if !samples.is_empty() => Some(Audio {
+            samples,
+            sample_rate,
+        }),
+        _ => None,
+    }
+}
+
+/// Resample audio to another sample rate, interpolating linearly between samples.
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let length = samples.len() as u64 * to as u64 / from as u64;
+    let ratio = from as f64 / to as f64;
+
+    (0..length)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+
+            let current = samples[index];
+            let next = samples.get(index + 1).copied().unwrap_or(current);
+
+            current + (next - current) * fraction
+        })
+        .collect()
+}
+
+/// Where a section of audio starts in microseconds: at the given start, or otherwise 40% into the
+/// audio as osu! does for previews, but never so late that the section would run past the end.
+pub fn section_start(duration: u64, start_ms: Option<u32>, length_ms: u32) -> u64 {
+    let start = match start_ms {
+        Some(start_ms) => start_ms as u64 * 1000,
+        None => duration * 2 / 5,
+    };
+
+    start.min(duration.saturating_sub(length_ms as u64 * 1000))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16 bit stereo WAV file of frames of left and right samples.
+    fn wav(sample_rate: u32, frames: &[(i16, i16)]) -> Vec<u8> {
+        let length = frames.len() as u32 * 4;
+
+        let mut wav = b"RIFF".to_vec();
+        wav.extend((36 + length).to_le_bytes());
+        wav.extend(b"WAVEfmt ");
+        wav.extend(16u32.to_le_bytes());
+        // pcm, 2 channels, the sample rate, bytes per second and per frame, and bits per sample
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend(sample_rate.to_le_bytes());
+        wav.extend((sample_rate * 4).to_le_bytes());
+        wav.extend(4u16.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(length.to_le_bytes());
+        for (left, right) in frames {
+            wav.extend(left.to_le_bytes());
+            wav.extend(right.to_le_bytes());
+        }
+
+        wav
+    }
+
+    #[test]
+    fn decodes_to_mono() {
+        let frames: Vec<_> = (0..44100).map(|_| (16384, -8192)).collect();
+        let audio = decode(wav(44100, &frames), Some("wav"), None).unwrap();
+
+        assert_eq!(audio.sample_rate, 44100);
+        assert_eq!(audio.samples.len(), 44100);
+        assert_eq!(audio.duration(), 1_000_000);
+        assert!(audio.samples.iter().all(|sample| *sample == 0.125));
+
+        assert!(decode(b"not audio".to_vec(), Some("ogg"), None).is_none());
+    }
+
+    #[test]
+    fn stops_decoding_at_the_limit() {
+        let frames: Vec<_> = (0..44100).map(|_| (0, 0)).collect();
+        let audio = decode(wav(44100, &frames), Some("wav"), Some(100_000)).unwrap();
+
+        assert!(audio.duration() >= 100_000 && audio.duration() < 1_000_000);
+        assert_eq!(audio.section(50_000, 50_000).len(), 2205);
+        assert_eq!(
+            audio.section(50_000, 1_000_000).len(),
+            audio.samples.len() - 2205
+        );
+    }
+
+    #[test]
+    fn resamples_linearly() {
+        let samples = [0.0, 1.0, 0.0, -1.0];
+
+        assert_eq!(
+            resample(&samples, 4, 8),
+            [0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -1.0]
+        );
+        assert_eq!(resample(&samples, 4, 2), [0.0, 0.0]);
+        assert_eq!(resample(&samples, 4, 4), samples);
+    }
+
+    #[test]
+    fn starts_sections_within_the_audio() {
+        assert_eq!(section_start(100_000_000, Some(30_000), 10_000), 30_000_000);
+        assert_eq!(section_start(100_000_000, None, 10_000), 40_000_000);
+        assert_eq!(section_start(100_000_000, Some(95_000), 10_000), 90_000_000);
+        assert_eq!(section_start(5_000_000, None, 10_000), 0);
+    }
+}
@@ -0,0 +1,422 @@
+use std::ops::{Add, Mul, Sub};
+
+use rosu_v2::prelude::GameMode;
+
+use super::slider::{PathType, SliderPath};
+
+const CIRCLE: u32 = 1;
+const SLIDER: u32 = 2;
+const SPINNER: u32 = 8;
+const HOLD: u32 = 128;
+
+/// A point on the playfield, in osu!pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pos {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Pos {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn length(self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn distance(self, other: Self) -> f64 {
+        (self - other).length()
+    }
+
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        match length > 0.0 {
+            true => self * (1.0 / length),
+            false => self,
+        }
+    }
+}
+
+impl Add for Pos {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Pos {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Pos {
+    type Output = Self;
+
+    fn mul(self, factor: f64) -> Self {
+        Self::new(self.x * factor, self.y * factor)
+    }
+}
+
+/// Hitsounds of an object or slider node which make taiko notes kats.
+pub const WHISTLE: u8 = 2;
+pub const CLAP: u8 = 8;
+
+#[derive(Clone, Debug)]
+pub enum HitObjectKind {
+    Circle,
+    Slider {
+        path: SliderPath,
+        /// How many times the path is travelled, one more than the repeats
+        spans: usize,
+        /// Hitsounds of the head, each repeat and the tail
+        node_sounds: Vec<u8>,
+    },
+    Spinner {
+        end_time: f64,
+    },
+    Hold {
+        end_time: f64,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct HitObject {
+    pub pos: Pos,
+    pub start_time: f64,
+    pub sound: u8,
+    pub kind: HitObjectKind,
+}
+
+/// A point from which on the beat length, or the slider velocity, changes.
+#[derive(Clone, Copy, Debug)]
+pub struct ControlPoint {
+    pub time: f64,
+    pub value: f64,
+}
+
+/// The parts of a `.osu` file difficulty calculation needs.
+#[derive(Clone, Debug)]
+pub struct Beatmap {
+    pub format_version: u32,
+    pub mode: GameMode,
+    pub stack_leniency: f64,
+    pub hp: f64,
+    pub cs: f64,
+    pub od: f64,
+    pub ar: f64,
+    pub slider_multiplier: f64,
+    pub slider_tick_rate: f64,
+    /// Beat lengths of the uninherited timing points
+    pub timing_points: Vec<ControlPoint>,
+    /// Slider velocity multipliers, reset to 1 by every uninherited timing point
+    pub velocity_points: Vec<ControlPoint>,
+    pub hit_objects: Vec<HitObject>,
+}
+
+impl Beatmap {
+    /// The beat length at a time, before the first timing point that of the first one.
+    pub fn beat_length_at(&self, time: f64) -> f64 {
+        control_point_at(&self.timing_points, time).unwrap_or(1000.0)
+    }
+
+    /// The slider velocity multiplier at a time.
+    pub fn slider_velocity_at(&self, time: f64) -> f64 {
+        control_point_at(&self.velocity_points, time).unwrap_or(1.0)
+    }
+
+    /// When an object ends, its start for circles and during sliders the end of the last span.
+    pub fn end_time(&self, hit_object: &HitObject) -> f64 {
+        match &hit_object.kind {
+            HitObjectKind::Circle => hit_object.start_time,
+            HitObjectKind::Slider { path, spans, .. } => {
+                hit_object.start_time
+                    + *spans as f64 * path.distance() / self.slider_velocity(hit_object.start_time)
+            }
+            HitObjectKind::Spinner { end_time } | HitObjectKind::Hold { end_time } => *end_time,
+        }
+    }
+
+    /// Distance a slider starting at a time travels per millisecond.
+    pub fn slider_velocity(&self, time: f64) -> f64 {
+        self.scoring_distance(time) / self.beat_length_at(time)
+    }
+
+    /// Distance a slider starting at a time travels per beat.
+    pub fn scoring_distance(&self, time: f64) -> f64 {
+        100.0 * self.slider_multiplier * self.slider_velocity_at(time)
+    }
+}
+
+fn control_point_at(points: &[ControlPoint], time: f64) -> Option<f64> {
+    let index = points.partition_point(|point| point.time <= time);
+    points.get(index.saturating_sub(1)).map(|point| point.value)
+}
+
+/// Parse the timing and hit objects of a `.osu` file, skipping lines which can't be understood
+/// as osu! does.
+pub fn parse(content: &str) -> Beatmap {
+    let content = content.trim_start_matches('\u{feff}');
+    let format_version = content
+        .lines()
+        .next()
+        .and_then(|line| line.trim().strip_prefix("osu file format v"))
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(14);
+
+    let mut beatmap = Beatmap {
+        format_version,
+        mode: GameMode::Osu,
+        stack_leniency: 0.7,
+        hp: 5.0,
+        cs: 5.0,
+        od: 5.0,
+        ar: -1.0,
+        slider_multiplier: 1.4,
+        slider_tick_rate: 1.0,
+        timing_points: Vec::new(),
+        velocity_points: Vec::new(),
+        hit_objects: Vec::new(),
+    };
+    // uninherited points come first among points at the same time, so inherited ones win
+    let mut velocity_points: Vec<(ControlPoint, bool)> = Vec::new();
+    let mut section = "";
+
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.starts_with("//") {
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            section = &trimmed[1..trimmed.len() - 1];
+            continue;
+        }
+
+        match section {
+            "General" | "Difficulty" => {
+                let (key, value) = match line.split_once(':') {
+                    Some((key, value)) => (key.trim(), value.trim()),
+                    None => continue,
+                };
+                let number = match value.parse::<f64>() {
+                    Ok(number) if number.is_finite() => number,
+                    _ => continue,
+                };
+
+                match key {
+                    "Mode" => beatmap.mode = GameMode::from(number.clamp(0.0, 3.0) as u8),
+                    "StackLeniency" => beatmap.stack_leniency = number,
+                    "HPDrainRate" => beatmap.hp = number,
+                    "CircleSize" => beatmap.cs = number,
+                    "OverallDifficulty" => beatmap.od = number,
+                    "ApproachRate" => beatmap.ar = number,
+                    "SliderMultiplier" => beatmap.slider_multiplier = number.clamp(0.4, 3.6),
+                    "SliderTickRate" => beatmap.slider_tick_rate = number.clamp(0.5, 8.0),
+                    _ => {}
+                }
+            }
+            "TimingPoints" => {
+                if let Some((point, uninherited)) = parse_timing_point(line) {
+                    if uninherited {
+                        beatmap.timing_points.push(ControlPoint {
+                            time: point.time,
+                            value: point.value.clamp(6.0, 60_000.0),
+                        });
+                        velocity_points.push((
+                            ControlPoint {
+                                time: point.time,
+                                value: 1.0,
+                            },
+                            true,
+                        ));
+                    } else {
+                        velocity_points.push((
+                            ControlPoint {
+                                time: point.time,
+                                value: (100.0 / -point.value).clamp(0.1, 10.0),
+                            },
+                            false,
+                        ));
+                    }
+                }
+            }
+            "HitObjects" => {
+                if let Some(hit_object) = parse_hit_object(line) {
+                    beatmap.hit_objects.push(hit_object);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // files from before approach rate was split from overall difficulty only name the latter
+    if beatmap.ar < 0.0 {
+        beatmap.ar = beatmap.od;
+    }
+
+    beatmap
+        .timing_points
+        .sort_by(|a, b| a.time.total_cmp(&b.time));
+    velocity_points.sort_by(|(a, a_uninherited), (b, b_uninherited)| {
+        a.time
+            .total_cmp(&b.time)
+            .then(b_uninherited.cmp(a_uninherited))
+    });
+    beatmap.velocity_points = velocity_points
+        .into_iter()
+        .map(|(point, _)| point)
+        .collect();
+    beatmap
+        .hit_objects
+        .sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    beatmap
+}
+
+/// Parse a timing point as its time and beat length, and whether it's uninherited.
+fn parse_timing_point(line: &str) -> Option<(ControlPoint, bool)> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 2 {
+        return None;
+    }
+
+    let time = fields[0]
+        .parse::<f64>()
+        .ok()
+        .filter(|time| time.is_finite())?;
+    let beat_length = fields[1]
+        .parse::<f64>()
+        .ok()
+        .filter(|beat_length| beat_length.is_finite())?;
+
+    // files from before inherited points were marked only use negative beat lengths
+    let uninherited = match fields.get(6) {
+        Some(uninherited) => *uninherited == "1",
+        None => beat_length >= 0.0,
+    };
+    if !uninherited && beat_length >= 0.0 {
+        return None;
+    }
+
+    Some((
+        ControlPoint {
+            time,
+            value: beat_length,
+        },
+        uninherited,
+    ))
+}
+
+fn parse_hit_object(line: &str) -> Option<HitObject> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 4 {
+        return None;
+    }
+
+    let x = fields[0].parse::<f64>().ok()?;
+    let y = fields[1].parse::<f64>().ok()?;
+    let start_time = fields[2]
+        .parse::<f64>()
+        .ok()
+        .filter(|time| time.is_finite())?;
+    let object_type = fields[3].parse::<u32>().ok()?;
+    let sound = fields
+        .get(4)
+        .and_then(|sound| sound.parse::<u8>().ok())
+        .unwrap_or(0);
+    let pos = Pos::new(x as i32 as f64, y as i32 as f64);
+
+    let kind = if object_type & CIRCLE != 0 {
+        HitObjectKind::Circle
+    } else if object_type & SLIDER != 0 {
+        let (path_type, points) = parse_path(fields.get(5)?, pos)?;
+        let spans = fields
+            .get(6)
+            .and_then(|spans| spans.parse::<usize>().ok())
+            .filter(|spans| *spans <= 9000)?
+            .max(1);
+        let expected_distance = fields
+            .get(7)
+            .and_then(|length| length.parse::<f64>().ok())
+            .map(|length| length.max(0.0))
+            .filter(|length| *length > 0.0);
+
+        let mut node_sounds: Vec<u8> = fields
+            .get(8)
+            .map(|sounds| {
+                sounds
+                    .split('|')
+                    .map(|sound| sound.trim().parse().unwrap_or(0))
+                    .collect()
+            })
+            .unwrap_or_default();
+        node_sounds.resize(spans + 1, sound);
+
+        HitObjectKind::Slider {
+            path: SliderPath::new(path_type, points, expected_distance),
+            spans,
+            node_sounds,
+        }
+    } else if object_type & SPINNER != 0 {
+        let end_time = fields.get(5)?.parse::<f64>().ok()?;
+        HitObjectKind::Spinner {
+            end_time: end_time.max(start_time),
+        }
+    } else if object_type & HOLD != 0 {
+        let end_time = fields.get(5)?.split(':').next()?.parse::<f64>().ok()?;
+        HitObjectKind::Hold {
+            end_time: end_time.max(start_time),
+        }
+    } else {
+        return None;
+    };
+
+    Some(HitObject {
+        pos,
+        start_time,
+        sound,
+        kind,
+    })
+}
+
+/// Parse a slider's curve, like `B|100:200|300:400`, as its type and control points relative to
+/// the slider's position, starting with the position itself.
+fn parse_path(value: &str, pos: Pos) -> Option<(PathType, Vec<Pos>)> {
+    let mut parts = value.split('|');
+    let path_type = match parts.next()? {
+        "L" => PathType::Linear,
+        "P" => PathType::PerfectCurve,
+        "C" => PathType::Catmull,
+        _ => PathType::Bezier,
+    };
+
+    let mut points = vec![Pos::default()];
+    for part in parts {
+        let (x, y) = match part.split_once(':') {
+            Some((x, y)) => (x.trim().parse::<f64>(), y.trim().parse::<f64>()),
+            None => continue,
+        };
+        if let (Ok(x), Ok(y)) = (x, y) {
+            points.push(Pos::new(x as i32 as f64, y as i32 as f64) - pos);
+        }
+    }
+
+    match points.len() > 1 {
+        true => Some((path_type, points)),
+        false => None,
+    }
+}
@@ -0,0 +1,522 @@
+use rosu_v2::prelude::{BeatmapDifficultyAttributes, GameModeAttributes, GameMods};
+
+use super::{
+    adjust_difficulty,
+    beatmap::{Beatmap, HitObjectKind},
+    clock_rate, difficulty_range,
+    slider::{self, SliderEventKind},
+    strain::{self, StrainPeaks},
+};
+
+const PLAYFIELD_WIDTH: f64 = 512.0;
+const LEGACY_LAST_TICK_OFFSET: f64 = 36.0;
+const RNG_SEED: u32 = 1337;
+
+const CATCHER_BASE_SIZE: f64 = 106.75;
+const ALLOWED_CATCH_RANGE: f64 = 0.8;
+
+const STAR_SCALING_FACTOR: f64 = 0.153;
+const NORMALIZED_HITOBJECT_RADIUS: f64 = 41.0;
+
+/// A fruit or droplet, which the catcher has to be under to keep the combo.
+struct PalpableObject {
+    start_time: f64,
+    x: f64,
+    hyper_dash: bool,
+    distance_to_hyper_dash: f64,
+}
+
+/// What a beatmap object turns into in catch, where only fruits are moved by hard rock.
+enum CatchObject {
+    Fruit(usize),
+    JuiceStream {
+        /// Position of the stream's last control point
+        end_x: f64,
+        start_time: f64,
+        droplets: usize,
+    },
+    BananaShower {
+        bananas: usize,
+    },
+}
+
+/// The random number generator osu!stable offsets objects with.
+struct LegacyRandom {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+    bit_buffer: u32,
+    bit_index: u32,
+}
+
+impl LegacyRandom {
+    const INT_TO_REAL: f64 = 1.0 / (i32::MAX as f64 + 1.0);
+
+    fn new(seed: u32) -> Self {
+        Self {
+            x: seed,
+            y: 842_502_087,
+            z: 3_579_807_591,
+            w: 273_326_509,
+            bit_buffer: 0,
+            bit_index: 32,
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let t = self.x ^ (self.x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        self.w = self.w ^ (self.w >> 19) ^ t ^ (t >> 8);
+
+        self.w
+    }
+
+    fn next_i32(&mut self) -> i32 {
+        (self.next_u32() & 0x7fff_ffff) as i32
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        Self::INT_TO_REAL * self.next_i32() as f64
+    }
+
+    fn next_range(&mut self, lower: f64, upper: f64) -> f64 {
+        lower + self.next_f64() * (upper - lower)
+    }
+
+    fn next_bool(&mut self) -> bool {
+        if self.bit_index == 32 {
+            self.bit_buffer = self.next_u32();
+            self.bit_index = 1;
+
+            return self.bit_buffer & 1 == 1;
+        }
+
+        self.bit_index += 1;
+        self.bit_buffer >>= 1;
+
+        self.bit_buffer & 1 == 1
+    }
+}
+
+pub fn calculate(beatmap: &Beatmap, mods: GameMods) -> BeatmapDifficultyAttributes {
+    let clock_rate = clock_rate(mods);
+    let cs = adjust_difficulty(beatmap.cs, mods, 1.3);
+    let ar = adjust_difficulty(beatmap.ar, mods, 1.4);
+
+    let (mut palpable_objects, catch_objects) = objects(beatmap);
+    if mods.contains(GameMods::HardRock) {
+        apply_hard_rock_offsets(&mut palpable_objects, &catch_objects);
+    }
+
+    let catcher_width =
+        CATCHER_BASE_SIZE * (1.0 - 0.7 * (cs - 5.0) / 5.0).abs() * ALLOWED_CATCH_RANGE;
+    initialise_hyper_dash(
+        &mut palpable_objects,
+        catcher_width / 2.0 / ALLOWED_CATCH_RANGE,
+    );
+
+    // the catcher is harder to position precisely at high circle sizes
+    let half_catcher_width = catcher_width * 0.5 * (1.0 - (cs - 5.5).max(0.0) * 0.0625);
+
+    let mut movement = Movement::new(clock_rate, half_catcher_width);
+    for index in 1..palpable_objects.len() {
+        movement.process(&palpable_objects, index);
+    }
+
+    let preempt = difficulty_range(ar, 1800.0, 1200.0, 450.0) / clock_rate;
+    let approach_rate = match preempt > 1200.0 {
+        true => -(preempt - 1800.0) / 120.0,
+        false => -(preempt - 1200.0) / 150.0 + 5.0,
+    };
+
+    BeatmapDifficultyAttributes {
+        max_combo: palpable_objects.len() as u32,
+        stars: (strain::weighted_sum(movement.peaks.peaks(), 0.94).sqrt() * STAR_SCALING_FACTOR)
+            as f32,
+        attrs: GameModeAttributes::Catch {
+            ar: approach_rate as f32,
+        },
+    }
+}
+
+/// Convert the beatmap to catch, returning the fruits and droplets in order of time alongside
+/// the objects they came from.
+fn objects(beatmap: &Beatmap) -> (Vec<PalpableObject>, Vec<CatchObject>) {
+    let mut palpable_objects = Vec::new();
+    let mut catch_objects = Vec::new();
+    let palpable = |start_time: f64, x: f64| PalpableObject {
+        start_time,
+        x: x.clamp(0.0, PLAYFIELD_WIDTH),
+        hyper_dash: false,
+        distance_to_hyper_dash: 0.0,
+    };
+
+    for hit_object in &beatmap.hit_objects {
+        let start_time = hit_object.start_time;
+        let x = hit_object.pos.x;
+
+        match &hit_object.kind {
+            HitObjectKind::Slider { path, spans, .. } => {
+                let velocity = beatmap.slider_velocity(start_time);
+                let tick_distance = beatmap.scoring_distance(start_time) / beatmap.slider_tick_rate;
+                let events = slider::slider_events(
+                    start_time,
+                    path.distance() / velocity,
+                    velocity,
+                    tick_distance,
+                    path.distance(),
+                    *spans,
+                    LEGACY_LAST_TICK_OFFSET,
+                );
+
+                // tiny droplets fill the gaps between the other parts of the stream
+                let mut droplets = 0;
+                let mut last_time: Option<f64> = None;
+                for event in events {
+                    if let Some(last_time) = last_time {
+                        let since_last_tick = ((event.time as i32) - (last_time as i32)) as f64;
+                        if since_last_tick > 80.0 {
+                            let mut time_between_tiny = since_last_tick;
+                            while time_between_tiny > 100.0 {
+                                time_between_tiny /= 2.0;
+                            }
+
+                            let mut time = time_between_tiny;
+                            while time < since_last_tick {
+                                droplets += 1;
+                                time += time_between_tiny;
+                            }
+                        }
+                    }
+                    last_time = Some(event.time);
+
+                    let event_x = x + path.position_at(event.path_progress).x;
+                    match event.kind {
+                        SliderEventKind::Tick => {
+                            droplets += 1;
+                            palpable_objects.push(palpable(event.time, event_x));
+                        }
+                        SliderEventKind::Head | SliderEventKind::Repeat | SliderEventKind::Tail => {
+                            palpable_objects.push(palpable(event.time, event_x));
+                        }
+                        SliderEventKind::LegacyLastTick => {}
+                    }
+                }
+
+                let end_x = x + path.last_control_point().x;
+                catch_objects.push(CatchObject::JuiceStream {
+                    end_x,
+                    start_time,
+                    droplets,
+                });
+            }
+            HitObjectKind::Spinner { end_time } => {
+                let mut spacing = end_time - start_time;
+                while spacing > 100.0 {
+                    spacing /= 2.0;
+                }
+
+                let mut bananas = 0;
+                if spacing > 0.0 {
+                    let mut time = start_time;
+                    while time <= *end_time {
+                        bananas += 1;
+                        time += spacing;
+                    }
+                }
+
+                catch_objects.push(CatchObject::BananaShower { bananas });
+            }
+            HitObjectKind::Circle | HitObjectKind::Hold { .. } => {
+                catch_objects.push(CatchObject::Fruit(palpable_objects.len()));
+                palpable_objects.push(palpable(start_time, x));
+            }
+        }
+    }
+
+    // fruits are indexed before sorting, so keep the order the same for equal times
+    let mut order: Vec<usize> = (0..palpable_objects.len()).collect();
+    order.sort_by(|a, b| {
+        palpable_objects[*a]
+            .start_time
+            .total_cmp(&palpable_objects[*b].start_time)
+    });
+    let mut positions = vec![0; order.len()];
+    for (position, index) in order.iter().enumerate() {
+        positions[*index] = position;
+    }
+    for catch_object in &mut catch_objects {
+        if let CatchObject::Fruit(index) = catch_object {
+            *index = positions[*index];
+        }
+    }
+
+    let mut sorted: Vec<Option<PalpableObject>> = palpable_objects.into_iter().map(Some).collect();
+    let palpable_objects = order
+        .into_iter()
+        .map(|index| sorted[index].take().unwrap())
+        .collect();
+
+    (palpable_objects, catch_objects)
+}
+
+/// Move fruits away from the ones before them as hard rock does, using the same random
+/// numbers as osu!stable.
+fn apply_hard_rock_offsets(palpable_objects: &mut [PalpableObject], catch_objects: &[CatchObject]) {
+    let mut rng = LegacyRandom::new(RNG_SEED);
+    let mut last_position: Option<f64> = None;
+    let mut last_start_time = 0.0;
+
+    for catch_object in catch_objects {
+        match catch_object {
+            CatchObject::Fruit(index) => {
+                let fruit = &mut palpable_objects[*index];
+                let mut position = fruit.x;
+                let start_time = fruit.start_time;
+
+                let last = match last_position {
+                    Some(last) => last,
+                    None => {
+                        last_position = Some(position);
+                        last_start_time = start_time;
+                        continue;
+                    }
+                };
+
+                let position_diff = position - last;
+                // osu!stable calculated the time between fruits as whole milliseconds
+                let time_diff = (start_time - last_start_time) as i32;
+
+                if time_diff > 1000 {
+                    last_position = Some(position);
+                    last_start_time = start_time;
+                    continue;
+                }
+
+                if position_diff == 0.0 {
+                    apply_random_offset(&mut position, time_diff as f64 / 4.0, &mut rng);
+                    fruit.x = position;
+                    continue;
+                }
+
+                if position_diff.abs() < (time_diff / 3) as f64 {
+                    apply_offset(&mut position, position_diff);
+                }
+
+                fruit.x = position;
+                last_position = Some(position);
+                last_start_time = start_time;
+            }
+            CatchObject::BananaShower { bananas } => {
+                for _ in 0..*bananas {
+                    rng.next_f64();
+                    rng.next_u32();
+                    rng.next_u32();
+                    rng.next_u32();
+                }
+            }
+            CatchObject::JuiceStream {
+                end_x,
+                start_time,
+                droplets,
+            } => {
+                last_position = Some(*end_x);
+                last_start_time = *start_time;
+
+                for _ in 0..*droplets {
+                    rng.next_u32();
+                }
+            }
+        }
+    }
+}
+
+fn apply_random_offset(position: &mut f64, max_offset: f64, rng: &mut LegacyRandom) {
+    let right = rng.next_bool();
+    let offset = rng.next_range(0.0, max_offset.max(0.0)).min(20.0);
+
+    if right {
+        if *position + offset <= PLAYFIELD_WIDTH {
+            *position += offset;
+        } else {
+            *position -= offset;
+        }
+    } else if *position - offset >= 0.0 {
+        *position -= offset;
+    } else {
+        *position += offset;
+    }
+}
+
+fn apply_offset(position: &mut f64, amount: f64) {
+    if amount > 0.0 {
+        if *position + amount < PLAYFIELD_WIDTH {
+            *position += amount;
+        }
+    } else if *position + amount > 0.0 {
+        *position += amount;
+    }
+}
+
+/// Mark the objects which need a hyper dash to reach the next one, and how close the others
+/// come to needing one.
+fn initialise_hyper_dash(palpable_objects: &mut [PalpableObject], half_catcher_width: f64) {
+    let mut last_direction = 0;
+    let mut last_excess = half_catcher_width;
+
+    for index in 0..palpable_objects.len().saturating_sub(1) {
+        let (x, start_time) = (
+            palpable_objects[index].x,
+            palpable_objects[index].start_time,
+        );
+        let (next_x, next_start_time) = (
+            palpable_objects[index + 1].x,
+            palpable_objects[index + 1].start_time,
+        );
+
+        let direction = match next_x > x {
+            true => 1,
+            false => -1,
+        };
+        // a quarter of a frame of grace time, as osu!stable gives
+        let time_to_next = next_start_time - start_time - 1000.0 / 60.0 / 4.0;
+        let distance_to_next = (next_x - x).abs()
+            - match last_direction == direction {
+                true => last_excess,
+                false => half_catcher_width,
+            };
+        let distance_to_hyper = time_to_next - distance_to_next;
+
+        let current = &mut palpable_objects[index];
+        current.hyper_dash = false;
+        current.distance_to_hyper_dash = 0.0;
+
+        if distance_to_hyper < 0.0 {
+            current.hyper_dash = true;
+            last_excess = half_catcher_width;
+        } else {
+            current.distance_to_hyper_dash = distance_to_hyper;
+            last_excess = distance_to_hyper.clamp(0.0, half_catcher_width);
+        }
+
+        last_direction = direction;
+    }
+}
+
+/// How far and how often the catcher has to move.
+struct Movement {
+    clock_rate: f64,
+    scaling_factor: f64,
+    last_player_position: Option<f64>,
+    last_distance_moved: f64,
+    last_strain_time: f64,
+    strain: f64,
+    peaks: StrainPeaks,
+}
+
+impl Movement {
+    const SKILL_MULTIPLIER: f64 = 900.0;
+    const STRAIN_DECAY_BASE: f64 = 0.2;
+    const SECTION_LENGTH: f64 = 750.0;
+    const ABSOLUTE_PLAYER_POSITIONING_ERROR: f64 = 16.0;
+    const DIRECTION_CHANGE_BONUS: f64 = 21.0;
+
+    fn new(clock_rate: f64, half_catcher_width: f64) -> Self {
+        Self {
+            clock_rate,
+            scaling_factor: NORMALIZED_HITOBJECT_RADIUS / half_catcher_width,
+            last_player_position: None,
+            last_distance_moved: 0.0,
+            last_strain_time: 0.0,
+            strain: 0.0,
+            peaks: StrainPeaks::new(Self::SECTION_LENGTH),
+        }
+    }
+
+    fn process(&mut self, objects: &[PalpableObject], index: usize) {
+        let start_time = objects[index].start_time / self.clock_rate;
+        let delta_time =
+            (objects[index].start_time - objects[index - 1].start_time) / self.clock_rate;
+
+        while let Some(section_start) = self.peaks.next_section(start_time) {
+            let time = section_start - objects[index - 1].start_time / self.clock_rate;
+            self.peaks
+                .start_section(self.strain * strain::decay(Self::STRAIN_DECAY_BASE, time));
+        }
+
+        self.strain *= strain::decay(Self::STRAIN_DECAY_BASE, delta_time);
+        self.strain += self.strain_value_of(objects, index, delta_time) * Self::SKILL_MULTIPLIER;
+        self.peaks.update(self.strain);
+    }
+
+    fn strain_value_of(
+        &mut self,
+        objects: &[PalpableObject],
+        index: usize,
+        delta_time: f64,
+    ) -> f64 {
+        let current = &objects[index];
+        let last = &objects[index - 1];
+
+        let position = current.x * self.scaling_factor;
+        let last_position = last.x * self.scaling_factor;
+        // every strain interval is capped at the equivalent of 375 bpm streams
+        let strain_time = delta_time.max(40.0);
+
+        let last_player_position = *self.last_player_position.get_or_insert(last_position);
+        let leniency = NORMALIZED_HITOBJECT_RADIUS - Self::ABSOLUTE_PLAYER_POSITIONING_ERROR;
+        let mut player_position =
+            last_player_position.clamp(position - leniency, position + leniency);
+
+        let distance_moved = player_position - last_player_position;
+        let weighted_strain_time = strain_time + 13.0 + 3.0 / self.clock_rate;
+
+        let mut distance_addition = distance_moved.abs().powf(1.3) / 510.0;
+        let sqrt_strain = weighted_strain_time.sqrt();
+        let mut edge_dash_bonus = 0.0;
+
+        if distance_moved.abs() > 0.1 {
+            if self.last_distance_moved.abs() > 0.1
+                && distance_moved.signum() != self.last_distance_moved.signum()
+            {
+                let bonus_factor = distance_moved.abs().min(50.0) / 50.0;
+                let antiflow_factor = (self.last_distance_moved.abs().min(70.0) / 70.0).max(0.38);
+
+                distance_addition += Self::DIRECTION_CHANGE_BONUS
+                    / (self.last_strain_time + 16.0).sqrt()
+                    * bonus_factor
+                    * antiflow_factor
+                    * (1.0 - (weighted_strain_time / 1000.0).powi(3)).max(0.0);
+            }
+
+            // every movement counts for something, which adds up in streams
+            distance_addition += 12.5 * distance_moved.abs().min(NORMALIZED_HITOBJECT_RADIUS * 2.0)
+                / (NORMALIZED_HITOBJECT_RADIUS * 6.0)
+                / sqrt_strain;
+        }
+
+        // walking to the edge of the catcher instead of dashing is hard
+        if last.distance_to_hyper_dash <= 20.0 {
+            if !last.hyper_dash {
+                edge_dash_bonus += 5.7;
+            } else {
+                // a hyper dash lands exactly on the fruit
+                player_position = position;
+            }
+
+            distance_addition *= 1.0
+                + edge_dash_bonus
+                    * ((20.0 - last.distance_to_hyper_dash) / 20.0)
+                    * ((strain_time * self.clock_rate).min(265.0) / 265.0).powf(1.5);
+        }
+
+        self.last_player_position = Some(player_position);
+        self.last_distance_moved = distance_moved;
+        self.last_strain_time = strain_time;
+
+        distance_addition / weighted_strain_time
+    }
+}
@@ -0,0 +1,199 @@
+use rosu_v2::prelude::{BeatmapDifficultyAttributes, GameModeAttributes, GameMods};
+
+use super::{
+    beatmap::{Beatmap, HitObjectKind},
+    clock_rate,
+    strain::{self, StrainPeaks},
+};
+
+const SECTION_LENGTH: f64 = 400.0;
+const STAR_SCALING_FACTOR: f64 = 0.018;
+
+/// A note or hold, with the times scaled by the clock rate.
+struct DifficultyObject {
+    column: usize,
+    start_time: f64,
+    end_time: f64,
+    delta_time: f64,
+}
+
+pub fn calculate(beatmap: &Beatmap, mods: GameMods) -> BeatmapDifficultyAttributes {
+    let clock_rate = clock_rate(mods);
+    let columns = beatmap.cs.round_ties_even().max(1.0) as usize;
+
+    let mut notes: Vec<(usize, f64, f64)> = beatmap
+        .hit_objects
+        .iter()
+        .map(|hit_object| {
+            let column = (hit_object.pos.x / (512.0 / columns as f64)).floor() as i64;
+            let end_time = match hit_object.kind {
+                HitObjectKind::Hold { end_time } => end_time,
+                _ => hit_object.start_time,
+            };
+
+            (
+                column.clamp(0, columns as i64 - 1) as usize,
+                hit_object.start_time,
+                end_time,
+            )
+        })
+        .collect();
+    // osu! sorts by the rounded start times, keeping the file's order among equal ones
+    notes.sort_by_key(|(_, start_time, _)| start_time.round_ties_even() as i64);
+
+    let max_combo = beatmap
+        .hit_objects
+        .iter()
+        .map(|hit_object| match hit_object.kind {
+            HitObjectKind::Hold { .. } => 2,
+            _ => 1,
+        })
+        .sum();
+
+    let objects: Vec<DifficultyObject> = notes
+        .windows(2)
+        .map(|pair| {
+            let (_, last_start_time, _) = pair[0];
+            let (column, start_time, end_time) = pair[1];
+
+            DifficultyObject {
+                column,
+                start_time: start_time / clock_rate,
+                end_time: end_time / clock_rate,
+                delta_time: (start_time - last_start_time) / clock_rate,
+            }
+        })
+        .collect();
+
+    let mut strain = Strain::new(columns);
+    for index in 0..objects.len() {
+        strain.process(&objects, index);
+    }
+
+    BeatmapDifficultyAttributes {
+        max_combo,
+        stars: (strain::weighted_sum(strain.peaks.peaks(), 0.9) * STAR_SCALING_FACTOR) as f32,
+        attrs: GameModeAttributes::Mania {
+            great_hit_window: ((great_hit_window(beatmap.od, mods) * clock_rate).trunc()
+                / clock_rate)
+                .ceil() as f32,
+            score_multiplier: score_multiplier(mods) as f32,
+        },
+    }
+}
+
+/// The hit window of a 300 as osu! computes it in mania, before the clock rate.
+fn great_hit_window(od: f64, mods: GameMods) -> f64 {
+    let mut window = 34.0 + 3.0 * (10.0 - od).clamp(0.0, 10.0);
+
+    if mods.contains(GameMods::HardRock) {
+        window /= 1.4;
+    } else if mods.contains(GameMods::Easy) {
+        window *= 1.4;
+    }
+
+    if mods.intersects(GameMods::DoubleTime | GameMods::NightCore) {
+        window *= 1.5;
+    } else if mods.contains(GameMods::HalfTime) {
+        window *= 0.75;
+    }
+
+    window
+}
+
+fn score_multiplier(mods: GameMods) -> f64 {
+    [GameMods::NoFail, GameMods::Easy, GameMods::HalfTime]
+        .into_iter()
+        .filter(|mod_| mods.contains(*mod_))
+        .fold(1.0, |multiplier, _| multiplier * 0.5)
+}
+
+/// Strain of each column, and of all of them together.
+struct Strain {
+    start_times: Vec<f64>,
+    end_times: Vec<f64>,
+    individual_strains: Vec<f64>,
+    individual_strain: f64,
+    overall_strain: f64,
+    peaks: StrainPeaks,
+}
+
+impl Strain {
+    const INDIVIDUAL_DECAY_BASE: f64 = 0.125;
+    const OVERALL_DECAY_BASE: f64 = 0.30;
+    const RELEASE_THRESHOLD: f64 = 24.0;
+
+    fn new(columns: usize) -> Self {
+        Self {
+            start_times: vec![0.0; columns],
+            end_times: vec![0.0; columns],
+            individual_strains: vec![0.0; columns],
+            individual_strain: 0.0,
+            overall_strain: 1.0,
+            peaks: StrainPeaks::new(SECTION_LENGTH),
+        }
+    }
+
+    fn process(&mut self, objects: &[DifficultyObject], index: usize) {
+        let current = &objects[index];
+        while let Some(section_start) = self.peaks.next_section(current.start_time) {
+            let time = section_start - objects[index - 1].start_time;
+            self.peaks.start_section(
+                self.individual_strain * strain::decay(Self::INDIVIDUAL_DECAY_BASE, time)
+                    + self.overall_strain * strain::decay(Self::OVERALL_DECAY_BASE, time),
+            );
+        }
+
+        let strain = self.strain_value_of(current);
+        self.peaks.update(strain);
+    }
+
+    fn strain_value_of(&mut self, current: &DifficultyObject) -> f64 {
+        let (start_time, end_time, column) = (current.start_time, current.end_time, current.column);
+
+        let mut is_overlapping = false;
+        // the closest release to the end of the current note
+        let mut closest_end_time = (end_time - start_time).abs();
+        // holding another note makes everything harder
+        let mut hold_factor = 1.0;
+        // releasing a hold while something else is held is awkward
+        let mut hold_addition = 0.0;
+
+        for other_end_time in &self.end_times {
+            is_overlapping |=
+                *other_end_time - 1.0 > start_time && end_time - 1.0 > *other_end_time;
+
+            if *other_end_time - 1.0 > end_time {
+                hold_factor = 1.25;
+            }
+
+            closest_end_time = closest_end_time.min((end_time - other_end_time).abs());
+        }
+
+        // releasing several notes together is as easy as releasing one
+        if is_overlapping {
+            hold_addition =
+                1.0 / (1.0 + (0.5 * (Self::RELEASE_THRESHOLD - closest_end_time)).exp());
+        }
+
+        self.individual_strains[column] *= strain::decay(
+            Self::INDIVIDUAL_DECAY_BASE,
+            start_time - self.start_times[column],
+        );
+        self.individual_strains[column] += 2.0 * hold_factor;
+
+        // a chord is as hard as its hardest column
+        self.individual_strain = match current.delta_time <= 1.0 {
+            true => self.individual_strain.max(self.individual_strains[column]),
+            false => self.individual_strains[column],
+        };
+
+        self.overall_strain *= strain::decay(Self::OVERALL_DECAY_BASE, current.delta_time);
+        self.overall_strain += (1.0 + hold_addition) * hold_factor;
+
+        self.start_times[column] = start_time;
+        self.end_times[column] = end_time;
+
+        self.individual_strain + self.overall_strain
+    }
+}
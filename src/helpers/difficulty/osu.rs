@@ -0,0 +1,942 @@
+use std::f64::consts::PI;
+
+use rosu_v2::prelude::{BeatmapDifficultyAttributes, GameModeAttributes, GameMods};
+
+use super::{
+    adjust_difficulty,
+    beatmap::{Beatmap, HitObjectKind, Pos},
+    clock_rate, difficulty_range,
+    slider::{self, SliderEventKind, SliderPath},
+    strain::{self, StrainPeaks},
+};
+
+const OBJECT_RADIUS: f64 = 64.0;
+const STACK_DISTANCE: f64 = 3.0;
+const LEGACY_LAST_TICK_OFFSET: f64 = 36.0;
+
+const NORMALISED_RADIUS: f64 = 50.0;
+const MIN_DELTA_TIME: f64 = 25.0;
+const MAXIMUM_SLIDER_RADIUS: f64 = NORMALISED_RADIUS * 2.4;
+const ASSUMED_SLIDER_RADIUS: f64 = NORMALISED_RADIUS * 1.8;
+
+const SECTION_LENGTH: f64 = 400.0;
+const DIFFICULTY_MULTIPLIER: f64 = 0.0675;
+const PERFORMANCE_BASE_MULTIPLIER: f64 = 1.14;
+
+/// A judged point of a slider after its head.
+struct NestedObject {
+    time: f64,
+    /// Position relative to the slider's
+    pos: Pos,
+    repeat: bool,
+}
+
+struct Slider<'a> {
+    path: &'a SliderPath,
+    spans: usize,
+    span_duration: f64,
+    nested: Vec<NestedObject>,
+}
+
+struct OsuObject<'a> {
+    pos: Pos,
+    start_time: f64,
+    end_time: f64,
+    stack_height: i32,
+    slider: Option<Slider<'a>>,
+    spinner: bool,
+}
+
+impl OsuObject<'_> {
+    fn end_position(&self) -> Pos {
+        match &self.slider {
+            Some(slider) => self.pos + slider.path.end_position(slider.spans),
+            None => self.pos,
+        }
+    }
+}
+
+/// Where the cursor lazily follows a slider to, and how far it has to move for it.
+#[derive(Clone, Copy, Default)]
+struct SliderCursor {
+    end_position: Pos,
+    travel_distance: f64,
+    travel_time: f64,
+}
+
+/// An object as seen from the one before it, with the times scaled by the clock rate.
+#[derive(Default)]
+struct DifficultyObject {
+    start_time: f64,
+    delta_time: f64,
+    strain_time: f64,
+    jump_distance: f64,
+    movement_distance: f64,
+    movement_time: f64,
+    travel_distance: f64,
+    travel_time: f64,
+    angle: Option<f64>,
+    slider: bool,
+    spinner: bool,
+    radius: f64,
+    stacked_position: Pos,
+    end_position: Pos,
+}
+
+pub fn calculate(beatmap: &Beatmap, mods: GameMods) -> BeatmapDifficultyAttributes {
+    let clock_rate = clock_rate(mods);
+    let cs = adjust_difficulty(beatmap.cs, mods, 1.3);
+    let ar = adjust_difficulty(beatmap.ar, mods, 1.4);
+    let od = adjust_difficulty(beatmap.od, mods, 1.4);
+
+    let preempt = difficulty_range(ar, 1800.0, 1200.0, 450.0);
+    let great_window = difficulty_range(od, 80.0, 50.0, 20.0) / clock_rate;
+    let scale = (1.0 - 0.7 * (cs - 5.0) / 5.0) / 2.0;
+    let radius = OBJECT_RADIUS * scale;
+
+    let mut objects = objects(beatmap);
+    let stack_threshold = preempt * beatmap.stack_leniency;
+    match beatmap.format_version >= 6 {
+        true => apply_stacking(&mut objects, stack_threshold),
+        false => apply_stacking_old(&mut objects, stack_threshold),
+    }
+
+    let max_combo = objects
+        .iter()
+        .map(|object| match &object.slider {
+            Some(slider) => 1 + slider.nested.len() as u32,
+            None => 1,
+        })
+        .sum();
+
+    let difficulty_objects = difficulty_objects(&objects, scale, radius, clock_rate);
+
+    let mut aim = Aim::new(true);
+    let mut aim_no_sliders = Aim::new(false);
+    let mut speed = Speed::new(great_window);
+    let mut flashlight = Flashlight::new(mods.contains(GameMods::Hidden));
+    for index in 0..difficulty_objects.len() {
+        aim.process(&difficulty_objects, index);
+        aim_no_sliders.process(&difficulty_objects, index);
+        speed.process(&difficulty_objects, index);
+        if mods.contains(GameMods::Flashlight) {
+            flashlight.process(&difficulty_objects, index);
+        }
+    }
+
+    let aim_rating = aim.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+    let aim_rating_no_sliders = aim_no_sliders.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+    let speed_rating = speed.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+    let flashlight_rating = match mods.contains(GameMods::Flashlight) {
+        true => flashlight.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER,
+        false => 0.0,
+    };
+    let slider_factor = match aim_rating > 0.0 {
+        true => aim_rating_no_sliders / aim_rating,
+        false => 1.0,
+    };
+
+    let base_performance =
+        |rating: f64| (5.0 * (rating / DIFFICULTY_MULTIPLIER).max(1.0) - 4.0).powi(3) / 100_000.0;
+    let base_flashlight_performance = match mods.contains(GameMods::Flashlight) {
+        true => flashlight_rating.powi(2) * 25.0,
+        false => 0.0,
+    };
+    let total_performance = (base_performance(aim_rating).powf(1.1)
+        + base_performance(speed_rating).powf(1.1)
+        + base_flashlight_performance.powf(1.1))
+    .powf(1.0 / 1.1);
+    // every rating is at least 1 in the formulas, so without objects there is nothing to rate
+    let stars = match !objects.is_empty() && total_performance > 0.00001 {
+        true => {
+            PERFORMANCE_BASE_MULTIPLIER.cbrt()
+                * 0.027
+                * ((100_000.0 / 2.0_f64.powf(1.0 / 1.1) * total_performance).cbrt() + 4.0)
+        }
+        false => 0.0,
+    };
+
+    let preempt = preempt / clock_rate;
+    let approach_rate = match preempt > 1200.0 {
+        true => (1800.0 - preempt) / 120.0,
+        false => (1200.0 - preempt) / 150.0 + 5.0,
+    };
+
+    BeatmapDifficultyAttributes {
+        max_combo,
+        stars: stars as f32,
+        attrs: GameModeAttributes::Osu {
+            ar: approach_rate as f32,
+            od: ((80.0 - great_window) / 6.0) as f32,
+            aim_difficulty: aim_rating as f32,
+            flashlight_difficulty: flashlight_rating as f32,
+            slider_factor: slider_factor as f32,
+            speed_difficulty: speed_rating as f32,
+        },
+    }
+}
+
+fn objects(beatmap: &Beatmap) -> Vec<OsuObject<'_>> {
+    beatmap
+        .hit_objects
+        .iter()
+        .map(|hit_object| {
+            let start_time = hit_object.start_time;
+            let mut object = OsuObject {
+                pos: hit_object.pos,
+                start_time,
+                end_time: start_time,
+                stack_height: 0,
+                slider: None,
+                spinner: false,
+            };
+
+            match &hit_object.kind {
+                HitObjectKind::Slider { path, spans, .. } => {
+                    let velocity = beatmap.slider_velocity(start_time);
+                    // files from before version 8 space ticks the same at any slider velocity
+                    let tick_multiplier = match beatmap.format_version < 8 {
+                        true => 1.0 / beatmap.slider_velocity_at(start_time),
+                        false => 1.0,
+                    };
+                    let tick_distance = beatmap.scoring_distance(start_time)
+                        / beatmap.slider_tick_rate
+                        * tick_multiplier;
+                    let span_duration = path.distance() / velocity;
+
+                    let mut nested: Vec<NestedObject> = slider::slider_events(
+                        start_time,
+                        span_duration,
+                        velocity,
+                        tick_distance,
+                        path.distance(),
+                        *spans,
+                        LEGACY_LAST_TICK_OFFSET,
+                    )
+                    .into_iter()
+                    .filter(|event| {
+                        !matches!(event.kind, SliderEventKind::Head | SliderEventKind::Tail)
+                    })
+                    .map(|event| NestedObject {
+                        time: event.time,
+                        pos: match event.kind {
+                            SliderEventKind::LegacyLastTick => path.end_position(*spans),
+                            _ => path.position_at(event.path_progress),
+                        },
+                        repeat: event.kind == SliderEventKind::Repeat,
+                    })
+                    .collect();
+                    nested.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+                    object.end_time = start_time + *spans as f64 * span_duration;
+                    object.slider = Some(Slider {
+                        path,
+                        spans: *spans,
+                        span_duration,
+                        nested,
+                    });
+                }
+                HitObjectKind::Spinner { end_time } => {
+                    object.end_time = *end_time;
+                    object.spinner = true;
+                }
+                HitObjectKind::Circle | HitObjectKind::Hold { .. } => {}
+            }
+
+            object
+        })
+        .collect()
+}
+
+/// Stack objects placed on top of each other shortly after one another, as osu! does for
+/// beatmaps from file format version 6 on.
+fn apply_stacking(objects: &mut [OsuObject], stack_threshold: f64) {
+    for i in (1..objects.len()).rev() {
+        let mut object_i = i;
+        if objects[object_i].stack_height != 0 || objects[object_i].spinner {
+            continue;
+        }
+
+        if objects[object_i].slider.is_none() {
+            for n in (0..i).rev() {
+                if objects[n].spinner {
+                    continue;
+                }
+                if objects[object_i].start_time - objects[n].end_time > stack_threshold {
+                    break;
+                }
+
+                // objects stacked on the end of a slider move away from it instead
+                let end_position = objects[n].end_position();
+                if objects[n].slider.is_some()
+                    && end_position.distance(objects[object_i].pos) < STACK_DISTANCE
+                {
+                    let offset = objects[object_i].stack_height - objects[n].stack_height + 1;
+                    for object_j in &mut objects[n + 1..=i] {
+                        if end_position.distance(object_j.pos) < STACK_DISTANCE {
+                            object_j.stack_height -= offset;
+                        }
+                    }
+                    break;
+                }
+
+                if objects[n].pos.distance(objects[object_i].pos) < STACK_DISTANCE {
+                    objects[n].stack_height = objects[object_i].stack_height + 1;
+                    object_i = n;
+                }
+            }
+        } else {
+            for n in (0..i).rev() {
+                if objects[n].spinner {
+                    continue;
+                }
+                if objects[object_i].start_time - objects[n].start_time > stack_threshold {
+                    break;
+                }
+
+                if objects[n].end_position().distance(objects[object_i].pos) < STACK_DISTANCE {
+                    objects[n].stack_height = objects[object_i].stack_height + 1;
+                    object_i = n;
+                }
+            }
+        }
+    }
+}
+
+/// Stack objects as osu! did for beatmaps from before file format version 6.
+fn apply_stacking_old(objects: &mut [OsuObject], stack_threshold: f64) {
+    for i in 0..objects.len() {
+        if objects[i].stack_height != 0 && objects[i].slider.is_none() {
+            continue;
+        }
+
+        let mut start_time = objects[i].end_time;
+        let mut slider_stack = 0;
+        let slider_end = match &objects[i].slider {
+            Some(slider) => objects[i].pos + slider.path.position_at(1.0),
+            None => objects[i].pos,
+        };
+
+        for j in i + 1..objects.len() {
+            if objects[j].start_time - stack_threshold > start_time {
+                break;
+            }
+
+            if objects[j].pos.distance(objects[i].pos) < STACK_DISTANCE {
+                objects[i].stack_height += 1;
+                start_time = objects[j].end_time;
+            } else if objects[j].pos.distance(slider_end) < STACK_DISTANCE {
+                slider_stack += 1;
+                objects[j].stack_height -= slider_stack;
+                start_time = objects[j].end_time;
+            }
+        }
+    }
+}
+
+/// Follow a slider's judged points with the least cursor movement that still hits them.
+fn slider_cursor(
+    object: &OsuObject,
+    slider: &Slider,
+    stacked_position: Pos,
+    radius: f64,
+) -> SliderCursor {
+    let travel_time = slider
+        .nested
+        .last()
+        .map_or(0.0, |nested| nested.time - object.start_time);
+
+    let mut end_progress = match slider.span_duration > 0.0 {
+        true => travel_time / slider.span_duration,
+        false => 0.0,
+    };
+    end_progress = match end_progress % 2.0 >= 1.0 {
+        true => 1.0 - end_progress % 1.0,
+        false => end_progress % 1.0,
+    };
+
+    let mut end_position = stacked_position + slider.path.position_at(end_progress);
+    let mut cursor = stacked_position;
+    let scaling_factor = NORMALISED_RADIUS / radius;
+    let mut travel_distance = 0.0;
+
+    for (index, nested) in slider.nested.iter().enumerate() {
+        let last = index == slider.nested.len() - 1;
+        let mut movement = stacked_position + nested.pos - cursor;
+        let mut required_movement = ASSUMED_SLIDER_RADIUS;
+
+        if last {
+            // the tail is judged early, so the cursor only has to get as far as it is by then
+            let lazy_movement = end_position - cursor;
+            if lazy_movement.length() < movement.length() {
+                movement = lazy_movement;
+            }
+        } else if nested.repeat {
+            required_movement = NORMALISED_RADIUS;
+        }
+
+        let mut movement_length = scaling_factor * movement.length();
+        if movement_length > required_movement {
+            cursor = cursor + movement * ((movement_length - required_movement) / movement_length);
+            movement_length *= (movement_length - required_movement) / movement_length;
+            travel_distance += movement_length;
+        }
+
+        if last {
+            end_position = cursor;
+        }
+    }
+
+    // repeats are harder than their distance alone suggests
+    travel_distance *= (1.0 + (slider.spans - 1) as f64 / 2.5).powf(1.0 / 2.5);
+
+    SliderCursor {
+        end_position,
+        travel_distance,
+        travel_time,
+    }
+}
+
+fn difficulty_objects(
+    objects: &[OsuObject],
+    scale: f64,
+    radius: f64,
+    clock_rate: f64,
+) -> Vec<DifficultyObject> {
+    let stacked_positions: Vec<Pos> = objects
+        .iter()
+        .map(|object| {
+            let offset = object.stack_height as f64 * scale * -6.4;
+            object.pos + Pos::new(offset, offset)
+        })
+        .collect();
+    let cursors: Vec<Option<SliderCursor>> = objects
+        .iter()
+        .zip(&stacked_positions)
+        .map(|(object, stacked_position)| {
+            let slider = object.slider.as_ref()?;
+            Some(slider_cursor(object, slider, *stacked_position, radius))
+        })
+        .collect();
+    let cursor_position = |index: usize| match cursors[index] {
+        Some(cursor) => cursor.end_position,
+        None => stacked_positions[index],
+    };
+
+    let mut scaling_factor = NORMALISED_RADIUS / radius;
+    // small circles are harder to aim at than their size alone suggests
+    if radius < 30.0 {
+        scaling_factor *= 1.0 + (30.0 - radius).min(5.0) / 50.0;
+    }
+
+    (1..objects.len())
+        .map(|index| {
+            let current = &objects[index];
+            let last = &objects[index - 1];
+            let position = stacked_positions[index];
+
+            let delta_time = (current.start_time - last.start_time) / clock_rate;
+            let mut object = DifficultyObject {
+                start_time: current.start_time / clock_rate,
+                delta_time,
+                strain_time: delta_time.max(MIN_DELTA_TIME),
+                slider: current.slider.is_some(),
+                spinner: current.spinner,
+                radius,
+                stacked_position: position,
+                end_position: current.end_position(),
+                ..Default::default()
+            };
+
+            if let Some(cursor) = cursors[index] {
+                object.travel_distance = cursor.travel_distance;
+                object.travel_time = (cursor.travel_time / clock_rate).max(MIN_DELTA_TIME);
+            }
+
+            if current.spinner || last.spinner {
+                return object;
+            }
+
+            let last_cursor_position = cursor_position(index - 1);
+            object.jump_distance =
+                (position * scaling_factor - last_cursor_position * scaling_factor).length();
+            object.movement_time = object.strain_time;
+            object.movement_distance = object.jump_distance;
+
+            if let (Some(last_cursor), Some(last_slider)) = (cursors[index - 1], &last.slider) {
+                let last_travel_time = (last_cursor.travel_time / clock_rate).max(MIN_DELTA_TIME);
+                object.movement_time = (object.strain_time - last_travel_time).max(MIN_DELTA_TIME);
+
+                // the cursor may leave the slider early, from anywhere within its follow circle
+                let tail_position =
+                    stacked_positions[index - 1] + last_slider.path.end_position(last_slider.spans);
+                let tail_jump_distance = (tail_position - position).length() * scaling_factor;
+                object.movement_distance = (object.jump_distance
+                    - (MAXIMUM_SLIDER_RADIUS - ASSUMED_SLIDER_RADIUS))
+                    .min(tail_jump_distance - MAXIMUM_SLIDER_RADIUS)
+                    .max(0.0);
+            }
+
+            if index >= 2 && !objects[index - 2].spinner {
+                let last_last_cursor_position = cursor_position(index - 2);
+                let v1 = last_last_cursor_position - stacked_positions[index - 1];
+                let v2 = position - last_cursor_position;
+                let dot = v1.dot(v2);
+                let det = v1.x * v2.y - v1.y * v2.x;
+
+                object.angle = Some(det.atan2(dot).abs());
+            }
+
+            object
+        })
+        .collect()
+}
+
+/// Weaken the hardest sections, so a few difficult moments weigh less than a hard beatmap.
+fn reduced_difficulty_value(
+    peaks: Vec<f64>,
+    reduced_section_count: usize,
+    difficulty_multiplier: f64,
+) -> f64 {
+    let mut peaks = peaks;
+    peaks.sort_by(|a, b| b.total_cmp(a));
+
+    for (index, peak) in peaks.iter_mut().take(reduced_section_count).enumerate() {
+        let fraction = (index as f64 / reduced_section_count as f64).clamp(0.0, 1.0);
+        let scale = lerp(1.0, 10.0, fraction).log10();
+        *peak *= lerp(0.75, 1.0, scale);
+    }
+
+    strain::weighted_sum(peaks, 0.9) * difficulty_multiplier
+}
+
+fn lerp(start: f64, end: f64, amount: f64) -> f64 {
+    start + (end - start) * amount
+}
+
+fn velocity(distance: f64, time: f64) -> f64 {
+    match time > 0.0 {
+        true => distance / time,
+        false => 0.0,
+    }
+}
+
+struct Aim {
+    with_sliders: bool,
+    strain: f64,
+    peaks: StrainPeaks,
+}
+
+impl Aim {
+    const SKILL_MULTIPLIER: f64 = 23.55;
+    const STRAIN_DECAY_BASE: f64 = 0.15;
+
+    fn new(with_sliders: bool) -> Self {
+        Self {
+            with_sliders,
+            strain: 0.0,
+            peaks: StrainPeaks::new(SECTION_LENGTH),
+        }
+    }
+
+    fn process(&mut self, objects: &[DifficultyObject], index: usize) {
+        let current = &objects[index];
+        while let Some(section_start) = self.peaks.next_section(current.start_time) {
+            let time = section_start - objects[index - 1].start_time;
+            self.peaks
+                .start_section(self.strain * strain::decay(Self::STRAIN_DECAY_BASE, time));
+        }
+
+        self.strain *= strain::decay(Self::STRAIN_DECAY_BASE, current.delta_time);
+        self.strain += self.strain_value_of(objects, index) * Self::SKILL_MULTIPLIER;
+        self.peaks.update(self.strain);
+    }
+
+    fn strain_value_of(&self, objects: &[DifficultyObject], index: usize) -> f64 {
+        const WIDE_ANGLE_MULTIPLIER: f64 = 1.5;
+        const ACUTE_ANGLE_MULTIPLIER: f64 = 1.95;
+        const SLIDER_MULTIPLIER: f64 = 1.5;
+        const VELOCITY_CHANGE_MULTIPLIER: f64 = 0.75;
+
+        let current = &objects[index];
+        if current.spinner || index <= 1 || objects[index - 1].spinner {
+            return 0.0;
+        }
+
+        let last = &objects[index - 1];
+        let last_last = &objects[index - 2];
+
+        // sliders count as moving on from their end, or from where the cursor left them
+        let mut current_velocity = velocity(current.jump_distance, current.strain_time);
+        if last.slider && self.with_sliders {
+            let travel_velocity = velocity(last.travel_distance, last.travel_time);
+            let movement_velocity = velocity(current.movement_distance, current.movement_time);
+            current_velocity = current_velocity.max(movement_velocity + travel_velocity);
+        }
+
+        let mut previous_velocity = velocity(last.jump_distance, last.strain_time);
+        if last_last.slider && self.with_sliders {
+            let travel_velocity = velocity(last_last.travel_distance, last_last.travel_time);
+            let movement_velocity = velocity(last.movement_distance, last.movement_time);
+            previous_velocity = previous_velocity.max(movement_velocity + travel_velocity);
+        }
+
+        let mut wide_angle_bonus = 0.0;
+        let mut acute_angle_bonus = 0.0;
+        let mut slider_bonus = 0.0;
+        let mut velocity_change_bonus = 0.0;
+
+        let mut aim_strain = current_velocity;
+
+        // angles only matter for rhythms that stay the same
+        if current.strain_time.max(last.strain_time)
+            < 1.25 * current.strain_time.min(last.strain_time)
+        {
+            if let (Some(current_angle), Some(last_angle), Some(last_last_angle)) =
+                (current.angle, last.angle, last_last.angle)
+            {
+                let angle_bonus = current_velocity.min(previous_velocity);
+
+                wide_angle_bonus = wide_angle(current_angle);
+                acute_angle_bonus = acute_angle(current_angle);
+
+                if current.strain_time > 100.0 {
+                    acute_angle_bonus = 0.0;
+                } else {
+                    acute_angle_bonus *= acute_angle(last_angle)
+                        * angle_bonus.min(125.0 / current.strain_time)
+                        * (PI / 2.0 * ((100.0 - current.strain_time) / 25.0).min(1.0))
+                            .sin()
+                            .powi(2)
+                        * (PI / 2.0 * (current.jump_distance.clamp(50.0, 100.0) - 50.0) / 50.0)
+                            .sin()
+                            .powi(2);
+                }
+
+                // repeating the same angle is easier than changing it
+                wide_angle_bonus *=
+                    angle_bonus * (1.0 - wide_angle_bonus.min(wide_angle(last_angle).powi(3)));
+                acute_angle_bonus *=
+                    0.5 + 0.5 * (1.0 - acute_angle_bonus.min(acute_angle(last_last_angle).powi(3)));
+            }
+        }
+
+        if previous_velocity.max(current_velocity) != 0.0 {
+            // sliders are counted from their start here, since their movement is already counted
+            let previous_velocity = (last.jump_distance + last.travel_distance) / last.strain_time;
+            let current_velocity =
+                (current.jump_distance + current.travel_distance) / current.strain_time;
+
+            let distance_ratio = (PI / 2.0 * (previous_velocity - current_velocity).abs()
+                / previous_velocity.max(current_velocity))
+            .sin()
+            .powi(2);
+            let overlap_velocity_buff = (125.0 / current.strain_time.min(last.strain_time))
+                .min((previous_velocity - current_velocity).abs());
+            let non_overlap_velocity_buff = (previous_velocity - current_velocity).abs()
+                * (PI / 2.0 * (current.jump_distance.min(last.jump_distance) / 100.0).min(1.0))
+                    .sin()
+                    .powi(2);
+
+            velocity_change_bonus =
+                overlap_velocity_buff.max(non_overlap_velocity_buff) * distance_ratio;
+            // changes between different rhythms are expected, so they're worth less
+            velocity_change_bonus *= (current.strain_time.min(last.strain_time)
+                / current.strain_time.max(last.strain_time))
+            .powi(2);
+        }
+
+        if last.slider {
+            slider_bonus = velocity(last.travel_distance, last.travel_time);
+        }
+
+        aim_strain += (acute_angle_bonus * ACUTE_ANGLE_MULTIPLIER).max(
+            wide_angle_bonus * WIDE_ANGLE_MULTIPLIER
+                + velocity_change_bonus * VELOCITY_CHANGE_MULTIPLIER,
+        );
+        if self.with_sliders {
+            aim_strain += slider_bonus * SLIDER_MULTIPLIER;
+        }
+
+        aim_strain
+    }
+
+    fn difficulty_value(&self) -> f64 {
+        reduced_difficulty_value(self.peaks.peaks(), 10, 1.06)
+    }
+}
+
+fn wide_angle(angle: f64) -> f64 {
+    (3.0 / 4.0 * (angle.clamp(PI / 6.0, 5.0 / 6.0 * PI) - PI / 6.0))
+        .sin()
+        .powi(2)
+}
+
+fn acute_angle(angle: f64) -> f64 {
+    1.0 - wide_angle(angle)
+}
+
+struct Speed {
+    great_window: f64,
+    strain: f64,
+    rhythm: f64,
+    peaks: StrainPeaks,
+}
+
+impl Speed {
+    const SKILL_MULTIPLIER: f64 = 1375.0;
+    const STRAIN_DECAY_BASE: f64 = 0.3;
+    const SINGLE_SPACING_THRESHOLD: f64 = 125.0;
+    const RHYTHM_MULTIPLIER: f64 = 0.75;
+    const HISTORY_TIME_MAX: f64 = 5000.0;
+    const HISTORY_LENGTH: usize = 32;
+    const MIN_SPEED_BONUS: f64 = 75.0;
+    const SPEED_BALANCING_FACTOR: f64 = 40.0;
+
+    fn new(great_window: f64) -> Self {
+        Self {
+            great_window,
+            strain: 0.0,
+            rhythm: 0.0,
+            peaks: StrainPeaks::new(SECTION_LENGTH),
+        }
+    }
+
+    fn process(&mut self, objects: &[DifficultyObject], index: usize) {
+        let current = &objects[index];
+        while let Some(section_start) = self.peaks.next_section(current.start_time) {
+            let time = section_start - objects[index - 1].start_time;
+            self.peaks.start_section(
+                self.strain * self.rhythm * strain::decay(Self::STRAIN_DECAY_BASE, time),
+            );
+        }
+
+        self.strain *= strain::decay(Self::STRAIN_DECAY_BASE, current.delta_time);
+        self.strain += self.strain_value_of(objects, index) * Self::SKILL_MULTIPLIER;
+        self.rhythm = self.rhythm_bonus(objects, index);
+        self.peaks.update(self.strain * self.rhythm);
+    }
+
+    /// Reward rhythms which change often, by how long the patterns between changes are.
+    fn rhythm_bonus(&self, objects: &[DifficultyObject], index: usize) -> f64 {
+        let current = &objects[index];
+        if current.spinner {
+            return 0.0;
+        }
+
+        let previous = |offset: usize| &objects[index - 1 - offset];
+
+        let mut previous_island_size = 0;
+        let mut rhythm_complexity_sum = 0.0;
+        let mut island_size = 1;
+        let mut start_ratio = 0.0;
+        let mut first_delta_switch = false;
+
+        let historical_note_count = index.min(Self::HISTORY_LENGTH);
+
+        let mut rhythm_start = 0;
+        while rhythm_start + 2 < historical_note_count
+            && current.start_time - previous(rhythm_start).start_time < Self::HISTORY_TIME_MAX
+        {
+            rhythm_start += 1;
+        }
+
+        for i in (1..=rhythm_start).rev() {
+            let current_object = previous(i - 1);
+            let previous_object = previous(i);
+            let last_object = previous(i + 1);
+
+            // older notes matter less
+            let mut historical_decay = (Self::HISTORY_TIME_MAX
+                - (current.start_time - current_object.start_time))
+                .max(0.0)
+                / Self::HISTORY_TIME_MAX;
+            historical_decay = ((historical_note_count - i) as f64 / historical_note_count as f64)
+                .min(historical_decay);
+
+            let current_delta = current_object.strain_time;
+            let previous_delta = previous_object.strain_time;
+            let last_delta = last_object.strain_time;
+
+            let current_ratio = 1.0
+                + 6.0
+                    * (PI
+                        / (previous_delta.min(current_delta) / previous_delta.max(current_delta)))
+                    .sin()
+                    .powi(2)
+                    .min(0.5);
+
+            // changes within the hit window can be played as the same rhythm
+            let window_penalty =
+                (((previous_delta - current_delta).abs() - self.great_window * 0.6).max(0.0)
+                    / (self.great_window * 0.6))
+                    .min(1.0);
+
+            let mut effective_ratio = window_penalty * current_ratio;
+
+            if first_delta_switch {
+                if !(previous_delta > 1.25 * current_delta || previous_delta * 1.25 < current_delta)
+                {
+                    if island_size < 7 {
+                        island_size += 1;
+                    }
+                } else {
+                    if current_object.slider {
+                        effective_ratio *= 0.125;
+                    }
+                    if previous_object.slider {
+                        effective_ratio *= 0.25;
+                    }
+                    if previous_island_size == island_size {
+                        effective_ratio *= 0.25;
+                    }
+                    if previous_island_size % 2 == island_size % 2 {
+                        effective_ratio *= 0.50;
+                    }
+                    if last_delta > previous_delta + 10.0 && previous_delta > current_delta + 10.0 {
+                        effective_ratio *= 0.125;
+                    }
+
+                    rhythm_complexity_sum += (effective_ratio * start_ratio).sqrt()
+                        * historical_decay
+                        * (4.0 + island_size as f64).sqrt()
+                        / 2.0
+                        * (4.0 + previous_island_size as f64).sqrt()
+                        / 2.0;
+
+                    start_ratio = effective_ratio;
+                    previous_island_size = island_size;
+
+                    if previous_delta * 1.25 < current_delta {
+                        first_delta_switch = false;
+                    }
+
+                    island_size = 1;
+                }
+            } else if previous_delta > 1.25 * current_delta {
+                first_delta_switch = true;
+                start_ratio = effective_ratio;
+                island_size = 1;
+            }
+        }
+
+        (4.0 + rhythm_complexity_sum * Self::RHYTHM_MULTIPLIER).sqrt() / 2.0
+    }
+
+    fn strain_value_of(&self, objects: &[DifficultyObject], index: usize) -> f64 {
+        let current = &objects[index];
+        if current.spinner {
+            return 0.0;
+        }
+
+        let previous = index.checked_sub(1).map(|index| &objects[index]);
+
+        let mut strain_time = current.strain_time;
+        let great_window_full = self.great_window * 2.0;
+        let speed_window_ratio = strain_time / great_window_full;
+
+        // doubles with long gaps in between are easier than their spacing suggests
+        if let Some(previous) = previous {
+            if strain_time < great_window_full && previous.strain_time > strain_time {
+                strain_time = lerp(previous.strain_time, strain_time, speed_window_ratio);
+            }
+        }
+
+        // cap the speed to the hit window of a great
+        strain_time /= (strain_time / great_window_full / 0.93).clamp(0.92, 1.0);
+
+        let mut speed_bonus = 1.0;
+        if strain_time < Self::MIN_SPEED_BONUS {
+            speed_bonus += 0.75
+                * ((Self::MIN_SPEED_BONUS - strain_time) / Self::SPEED_BALANCING_FACTOR).powi(2);
+        }
+
+        let travel_distance = previous.map_or(0.0, |previous| previous.travel_distance);
+        let distance = Self::SINGLE_SPACING_THRESHOLD.min(travel_distance + current.jump_distance);
+
+        (speed_bonus + speed_bonus * (distance / Self::SINGLE_SPACING_THRESHOLD).powf(3.5))
+            / strain_time
+    }
+
+    fn difficulty_value(&self) -> f64 {
+        reduced_difficulty_value(self.peaks.peaks(), 5, 1.04)
+    }
+}
+
+struct Flashlight {
+    hidden: bool,
+    strain: f64,
+    peaks: StrainPeaks,
+}
+
+impl Flashlight {
+    const SKILL_MULTIPLIER: f64 = 0.15;
+    const STRAIN_DECAY_BASE: f64 = 0.15;
+    const HISTORY_LENGTH: usize = 10;
+    const HIDDEN_BONUS: f64 = 0.2;
+
+    fn new(hidden: bool) -> Self {
+        Self {
+            hidden,
+            strain: 0.0,
+            peaks: StrainPeaks::new(SECTION_LENGTH),
+        }
+    }
+
+    fn process(&mut self, objects: &[DifficultyObject], index: usize) {
+        let current = &objects[index];
+        while let Some(section_start) = self.peaks.next_section(current.start_time) {
+            let time = section_start - objects[index - 1].start_time;
+            self.peaks
+                .start_section(self.strain * strain::decay(Self::STRAIN_DECAY_BASE, time));
+        }
+
+        self.strain *= strain::decay(Self::STRAIN_DECAY_BASE, current.delta_time);
+        self.strain += self.strain_value_of(objects, index) * Self::SKILL_MULTIPLIER;
+        self.peaks.update(self.strain);
+    }
+
+    /// Reward jumps to objects far from the recent ones, which flashlight hides.
+    fn strain_value_of(&self, objects: &[DifficultyObject], index: usize) -> f64 {
+        let current = &objects[index];
+        if current.spinner {
+            return 0.0;
+        }
+
+        let scaling_factor = 52.0 / current.radius;
+        let mut small_distance_nerf = 1.0;
+        let mut cumulative_strain_time = 0.0;
+        let mut result = 0.0;
+
+        for i in 0..index.min(Self::HISTORY_LENGTH) {
+            let previous = &objects[index - 1 - i];
+            if previous.spinner {
+                continue;
+            }
+
+            let jump_distance = (current.stacked_position - previous.end_position).length();
+            cumulative_strain_time += previous.strain_time;
+
+            // jumps to nearby objects are still visible
+            if i == 0 {
+                small_distance_nerf = (jump_distance / 75.0).min(1.0);
+            }
+
+            // stacked objects are easy to read
+            let stack_nerf = (previous.jump_distance / scaling_factor / 25.0).min(1.0);
+
+            result += 0.8_f64.powi(i as i32) * stack_nerf * scaling_factor * jump_distance
+                / cumulative_strain_time;
+        }
+
+        if self.hidden {
+            result *= 1.0 + Self::HIDDEN_BONUS;
+        }
+
+        (small_distance_nerf * result).powi(2)
+    }
+
+    fn difficulty_value(&self) -> f64 {
+        self.peaks.peaks().iter().sum::<f64>() * 1.06
+    }
+}
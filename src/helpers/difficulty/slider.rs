@@ -0,0 +1,500 @@
+use super::beatmap::Pos;
+
+const BEZIER_TOLERANCE: f64 = 0.25;
+const CATMULL_DETAIL: usize = 50;
+const CIRCULAR_ARC_TOLERANCE: f64 = 0.1;
+
+/// Sliders longer than this don't get ticks.
+const MAX_TICK_LENGTH: f64 = 100_000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathType {
+    Linear,
+    PerfectCurve,
+    Catmull,
+    Bezier,
+}
+
+/// The curve a slider follows, approximated by line segments as osu! does.
+#[derive(Clone, Debug)]
+pub struct SliderPath {
+    /// Control points relative to the slider's position, the last of each segment typed
+    control_points: Vec<(Pos, Option<PathType>)>,
+    path: Vec<Pos>,
+    cumulative_length: Vec<f64>,
+}
+
+impl SliderPath {
+    /// Build a path from the control points of a `.osu` file, whose first point is the slider's
+    /// position, stretched or cut to the expected distance.
+    pub fn new(path_type: PathType, points: Vec<Pos>, expected_distance: Option<f64>) -> Self {
+        let mut path = Self {
+            control_points: control_points(path_type, points),
+            path: Vec::new(),
+            cumulative_length: Vec::new(),
+        };
+        path.calculate_path();
+        path.calculate_length(expected_distance);
+
+        path
+    }
+
+    pub fn distance(&self) -> f64 {
+        self.cumulative_length.last().copied().unwrap_or(0.0)
+    }
+
+    /// The last point the path was defined with, relative to the slider's position.
+    pub fn last_control_point(&self) -> Pos {
+        self.control_points
+            .last()
+            .map_or(Pos::default(), |(pos, _)| *pos)
+    }
+
+    /// The position at a fraction of the path, relative to the slider's position.
+    pub fn position_at(&self, progress: f64) -> Pos {
+        let distance = progress.clamp(0.0, 1.0) * self.distance();
+        let index = self
+            .cumulative_length
+            .partition_point(|length| *length < distance);
+
+        if self.path.is_empty() {
+            return Pos::default();
+        }
+        if index == 0 {
+            return self.path[0];
+        }
+        if index >= self.path.len() {
+            return self.path[self.path.len() - 1];
+        }
+
+        let (start, end) = (self.path[index - 1], self.path[index]);
+        let (start_length, end_length) = (
+            self.cumulative_length[index - 1],
+            self.cumulative_length[index],
+        );
+        if (end_length - start_length).abs() < 1e-3 {
+            return start;
+        }
+
+        start + (end - start) * ((distance - start_length) / (end_length - start_length))
+    }
+
+    /// Position at the end of the last span, relative to the slider's position.
+    pub fn end_position(&self, spans: usize) -> Pos {
+        self.position_at((spans % 2) as f64)
+    }
+
+    fn calculate_path(&mut self) {
+        let mut start = 0;
+
+        for index in 0..self.control_points.len() {
+            if self.control_points[index].1.is_none() && index < self.control_points.len() - 1 {
+                continue;
+            }
+
+            let segment: Vec<Pos> = self.control_points[start..=index]
+                .iter()
+                .map(|(pos, _)| *pos)
+                .collect();
+            let segment_type = self.control_points[start].1.unwrap_or(PathType::Linear);
+
+            for pos in approximate(segment_type, &segment) {
+                if self.path.last() != Some(&pos) {
+                    self.path.push(pos);
+                }
+            }
+
+            start = index;
+        }
+    }
+
+    fn calculate_length(&mut self, expected_distance: Option<f64>) {
+        let mut length = 0.0;
+        self.cumulative_length.push(0.0);
+        for pair in self.path.windows(2) {
+            length += pair[1].distance(pair[0]);
+            self.cumulative_length.push(length);
+        }
+
+        let expected_distance = match expected_distance {
+            Some(expected_distance) if expected_distance != length => expected_distance,
+            _ => return,
+        };
+
+        // osu! doesn't extend sliders whose last two control points are the same
+        let points = &self.control_points;
+        if points.len() >= 2
+            && points[points.len() - 1].0 == points[points.len() - 2].0
+            && expected_distance > length
+        {
+            return;
+        }
+
+        // the last length is always replaced
+        self.cumulative_length.pop();
+        if self.path.is_empty() {
+            self.cumulative_length.push(0.0);
+            return;
+        }
+
+        let mut end_index = self.path.len() - 1;
+        if length > expected_distance {
+            while self
+                .cumulative_length
+                .last()
+                .is_some_and(|length| *length >= expected_distance)
+            {
+                self.cumulative_length.pop();
+                self.path.remove(end_index);
+                if end_index == 0 {
+                    break;
+                }
+                end_index -= 1;
+            }
+        }
+
+        if end_index == 0 || self.cumulative_length.is_empty() {
+            self.cumulative_length.push(0.0);
+            return;
+        }
+
+        let direction = (self.path[end_index] - self.path[end_index - 1]).normalize();
+        let remaining =
+            expected_distance - self.cumulative_length[self.cumulative_length.len() - 1];
+        self.path[end_index] = self.path[end_index - 1] + direction * remaining;
+        self.cumulative_length.push(expected_distance);
+    }
+}
+
+/// Split the points into segments at repeated points, which `.osu` files use to start a new
+/// segment of the same type, by typing the last point of each segment.
+fn control_points(mut path_type: PathType, points: Vec<Pos>) -> Vec<(Pos, Option<PathType>)> {
+    if path_type == PathType::PerfectCurve {
+        if points.len() != 3 {
+            path_type = PathType::Bezier;
+        } else if is_linear(&points) {
+            path_type = PathType::Linear;
+        }
+    }
+
+    let mut vertices: Vec<(Pos, Option<PathType>)> =
+        points.into_iter().map(|pos| (pos, None)).collect();
+    vertices[0].1 = Some(path_type);
+
+    let mut control_points = Vec::with_capacity(vertices.len());
+    let mut start = 0;
+
+    for end in 1..vertices.len() {
+        if vertices[end].0 != vertices[end - 1].0 {
+            continue;
+        }
+        // catmull sliders can't have segments, apart from a repeat of the slider's position
+        if path_type == PathType::Catmull && end > 1 {
+            continue;
+        }
+        // the last point can't start a new segment
+        if end == vertices.len() - 1 {
+            continue;
+        }
+
+        vertices[end - 1].1 = Some(path_type);
+        control_points.extend_from_slice(&vertices[start..end]);
+        start = end + 1;
+    }
+
+    if start < vertices.len() {
+        control_points.extend_from_slice(&vertices[start..]);
+    }
+
+    control_points
+}
+
+fn is_linear(points: &[Pos]) -> bool {
+    ((points[1].y - points[0].y) * (points[2].x - points[0].x)
+        - (points[1].x - points[0].x) * (points[2].y - points[0].y))
+        .abs()
+        < 1e-3
+}
+
+fn approximate(path_type: PathType, points: &[Pos]) -> Vec<Pos> {
+    match path_type {
+        PathType::Linear => points.to_vec(),
+        PathType::PerfectCurve if points.len() == 3 => {
+            circular_arc(points).unwrap_or_else(|| bezier(points))
+        }
+        PathType::Catmull => catmull(points),
+        _ => bezier(points),
+    }
+}
+
+/// Approximate a bezier curve by subdividing it until each piece is flat enough.
+fn bezier(points: &[Pos]) -> Vec<Pos> {
+    let count = points.len();
+    let mut output = Vec::new();
+    if count == 0 {
+        return output;
+    }
+
+    let mut to_flatten = vec![points.to_vec()];
+    let mut left = vec![Pos::default(); count * 2 - 1];
+    let mut right = vec![Pos::default(); count];
+
+    while let Some(mut parent) = to_flatten.pop() {
+        if bezier_is_flat_enough(&parent) {
+            bezier_approximate(&parent, &mut output, &mut left, &mut right);
+            continue;
+        }
+
+        let mut right_child = vec![Pos::default(); count];
+        bezier_subdivide(&parent, &mut left, &mut right_child);
+        parent.copy_from_slice(&left[..count]);
+
+        to_flatten.push(right_child);
+        to_flatten.push(parent);
+    }
+
+    output.push(points[count - 1]);
+    output
+}
+
+fn bezier_is_flat_enough(points: &[Pos]) -> bool {
+    points.windows(3).all(|window| {
+        (window[0] - window[1] * 2.0 + window[2]).length_squared()
+            <= BEZIER_TOLERANCE * BEZIER_TOLERANCE * 4.0
+    })
+}
+
+/// Split a bezier curve in half with de Casteljau's algorithm.
+fn bezier_subdivide(points: &[Pos], left: &mut [Pos], right: &mut [Pos]) {
+    let count = points.len();
+    let mut midpoints = points.to_vec();
+
+    for i in 0..count {
+        left[i] = midpoints[0];
+        right[count - i - 1] = midpoints[count - i - 1];
+
+        for j in 0..count - i - 1 {
+            midpoints[j] = (midpoints[j] + midpoints[j + 1]) * 0.5;
+        }
+    }
+}
+
+fn bezier_approximate(points: &[Pos], output: &mut Vec<Pos>, left: &mut [Pos], right: &mut [Pos]) {
+    let count = points.len();
+    bezier_subdivide(points, left, right);
+
+    left[count..2 * count - 1].copy_from_slice(&right[1..count]);
+
+    output.push(points[0]);
+    for i in 1..count - 1 {
+        let index = 2 * i;
+        output.push((left[index - 1] + left[index] * 2.0 + left[index + 1]) * 0.25);
+    }
+}
+
+/// Approximate the arc through three points, if they aren't too close to a line.
+fn circular_arc(points: &[Pos]) -> Option<Vec<Pos>> {
+    let (a, b, c) = (points[0], points[1], points[2]);
+    if is_linear(points) {
+        return None;
+    }
+
+    let d = 2.0 * (a.x * (b - c).y + b.x * (c - a).y + c.x * (a - b).y);
+    let (a_sq, b_sq, c_sq) = (a.length_squared(), b.length_squared(), c.length_squared());
+    let centre = Pos::new(
+        a_sq * (b - c).y + b_sq * (c - a).y + c_sq * (a - b).y,
+        a_sq * (c - b).x + b_sq * (a - c).x + c_sq * (b - a).x,
+    ) * (1.0 / d);
+
+    let (d_a, d_c) = (a - centre, c - centre);
+    let radius = d_a.length();
+    let theta_start = d_a.y.atan2(d_a.x);
+    let mut theta_end = d_c.y.atan2(d_c.x);
+    while theta_end < theta_start {
+        theta_end += 2.0 * std::f64::consts::PI;
+    }
+
+    let mut direction = 1.0;
+    let mut theta_range = theta_end - theta_start;
+
+    // draw the arc on the side of a to c that b lies on
+    let ortho_a_to_c = Pos::new((c - a).y, -(c - a).x);
+    if ortho_a_to_c.dot(b - a) < 0.0 {
+        direction = -direction;
+        theta_range = 2.0 * std::f64::consts::PI - theta_range;
+    }
+
+    let amount = match 2.0 * radius <= CIRCULAR_ARC_TOLERANCE {
+        true => 2,
+        false => ((theta_range / (2.0 * (1.0 - CIRCULAR_ARC_TOLERANCE / radius).acos())).ceil()
+            as usize)
+            .max(2),
+    };
+
+    Some(
+        (0..amount)
+            .map(|i| {
+                let theta =
+                    theta_start + direction * (i as f64 / (amount - 1) as f64) * theta_range;
+                centre + Pos::new(theta.cos(), theta.sin()) * radius
+            })
+            .collect(),
+    )
+}
+
+fn catmull(points: &[Pos]) -> Vec<Pos> {
+    let count = points.len();
+    let mut output = Vec::with_capacity(count.saturating_sub(1) * CATMULL_DETAIL * 2);
+
+    for i in 0..count.saturating_sub(1) {
+        let v1 = if i > 0 { points[i - 1] } else { points[i] };
+        let v2 = points[i];
+        let v3 = if i < count - 1 {
+            points[i + 1]
+        } else {
+            v2 * 2.0 - v1
+        };
+        let v4 = if i < count - 2 {
+            points[i + 2]
+        } else {
+            v3 * 2.0 - v2
+        };
+
+        for c in 0..CATMULL_DETAIL {
+            output.push(catmull_point(
+                v1,
+                v2,
+                v3,
+                v4,
+                c as f64 / CATMULL_DETAIL as f64,
+            ));
+            output.push(catmull_point(
+                v1,
+                v2,
+                v3,
+                v4,
+                (c + 1) as f64 / CATMULL_DETAIL as f64,
+            ));
+        }
+    }
+
+    output
+}
+
+fn catmull_point(v1: Pos, v2: Pos, v3: Pos, v4: Pos, t: f64) -> Pos {
+    let t2 = t * t;
+    let t3 = t * t2;
+    let axis = |p1: f64, p2: f64, p3: f64, p4: f64| {
+        0.5 * (2.0 * p2
+            + (-p1 + p3) * t
+            + (2.0 * p1 - 5.0 * p2 + 4.0 * p3 - p4) * t2
+            + (-p1 + 3.0 * p2 - 3.0 * p3 + p4) * t3)
+    };
+
+    Pos::new(axis(v1.x, v2.x, v3.x, v4.x), axis(v1.y, v2.y, v3.y, v4.y))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SliderEventKind {
+    Head,
+    Tick,
+    Repeat,
+    /// The tail osu! judges slightly before the end of the slider
+    LegacyLastTick,
+    Tail,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SliderEvent {
+    pub kind: SliderEventKind,
+    pub time: f64,
+    /// Fraction of the path the event is at
+    pub path_progress: f64,
+}
+
+/// The judged points of a slider, in order of time within each span.
+pub fn slider_events(
+    start_time: f64,
+    span_duration: f64,
+    velocity: f64,
+    tick_distance: f64,
+    total_distance: f64,
+    spans: usize,
+    legacy_last_tick_offset: f64,
+) -> Vec<SliderEvent> {
+    let length = total_distance.min(MAX_TICK_LENGTH);
+    let tick_distance = tick_distance.clamp(0.0, length);
+    let min_distance_from_end = velocity * 10.0;
+
+    let mut events = vec![SliderEvent {
+        kind: SliderEventKind::Head,
+        time: start_time,
+        path_progress: 0.0,
+    }];
+
+    for span in 0..spans {
+        let span_start_time = start_time + span as f64 * span_duration;
+        let reversed = span % 2 == 1;
+
+        if tick_distance != 0.0 {
+            let mut ticks = Vec::new();
+            let mut distance = tick_distance;
+            while distance <= length {
+                if distance >= length - min_distance_from_end {
+                    break;
+                }
+
+                let path_progress = distance / length;
+                let time_progress = match reversed {
+                    true => 1.0 - path_progress,
+                    false => path_progress,
+                };
+                ticks.push(SliderEvent {
+                    kind: SliderEventKind::Tick,
+                    time: span_start_time + time_progress * span_duration,
+                    path_progress,
+                });
+
+                distance += tick_distance;
+            }
+
+            if reversed {
+                ticks.reverse();
+            }
+            events.extend(ticks);
+        }
+
+        if span < spans - 1 {
+            events.push(SliderEvent {
+                kind: SliderEventKind::Repeat,
+                time: span_start_time + span_duration,
+                path_progress: ((span + 1) % 2) as f64,
+            });
+        }
+    }
+
+    let total_duration = spans as f64 * span_duration;
+    let final_span_start_time = start_time + (spans - 1) as f64 * span_duration;
+    let final_span_end_time = (start_time + total_duration / 2.0)
+        .max(final_span_start_time + span_duration - legacy_last_tick_offset);
+    let mut final_progress = match span_duration > 0.0 {
+        true => (final_span_end_time - final_span_start_time) / span_duration,
+        false => 1.0,
+    };
+    if spans.is_multiple_of(2) {
+        final_progress = 1.0 - final_progress;
+    }
+
+    events.push(SliderEvent {
+        kind: SliderEventKind::LegacyLastTick,
+        time: final_span_end_time,
+        path_progress: final_progress,
+    });
+    events.push(SliderEvent {
+        kind: SliderEventKind::Tail,
+        time: start_time + total_duration,
+        path_progress: (spans % 2) as f64,
+    });
+
+    events
+}
@@ -0,0 +1,70 @@
+/// The peak strains of a skill in consecutive sections of a beatmap.
+pub struct StrainPeaks {
+    section_length: f64,
+    section_end: Option<f64>,
+    peak: f64,
+    peaks: Vec<f64>,
+}
+
+impl StrainPeaks {
+    pub fn new(section_length: f64) -> Self {
+        Self {
+            section_length,
+            section_end: None,
+            peak: 0.0,
+            peaks: Vec::new(),
+        }
+    }
+
+    /// Close the next section that ends before an object starting at a time, returning when
+    /// the following section starts so its initial strain can be set with `start_section`.
+    pub fn next_section(&mut self, time: f64) -> Option<f64> {
+        let section_end = *self
+            .section_end
+            .get_or_insert((time / self.section_length).ceil() * self.section_length);
+
+        if time <= section_end {
+            return None;
+        }
+
+        self.peaks.push(self.peak);
+        self.section_end = Some(section_end + self.section_length);
+
+        Some(section_end)
+    }
+
+    pub fn start_section(&mut self, initial_strain: f64) {
+        self.peak = initial_strain;
+    }
+
+    pub fn update(&mut self, strain: f64) {
+        self.peak = self.peak.max(strain);
+    }
+
+    /// The peaks of every section so far, including the current one.
+    pub fn peaks(&self) -> Vec<f64> {
+        let mut peaks = self.peaks.clone();
+        peaks.push(self.peak);
+
+        peaks
+    }
+}
+
+/// How much strain decays over a time in milliseconds.
+pub fn decay(base: f64, time: f64) -> f64 {
+    base.powf(time / 1000.0)
+}
+
+/// Sum the peaks from the highest, each weighted less than the one before.
+pub fn weighted_sum(mut peaks: Vec<f64>, decay_weight: f64) -> f64 {
+    peaks.sort_by(|a, b| b.total_cmp(a));
+
+    let mut weight = 1.0;
+    let mut difficulty = 0.0;
+    for peak in peaks {
+        difficulty += peak * weight;
+        weight *= decay_weight;
+    }
+
+    difficulty
+}
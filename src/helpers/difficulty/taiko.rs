@@ -0,0 +1,637 @@
+use std::collections::VecDeque;
+
+use rosu_v2::prelude::{BeatmapDifficultyAttributes, GameMode, GameModeAttributes, GameMods};
+
+use super::{
+    adjust_difficulty,
+    beatmap::{Beatmap, HitObjectKind, CLAP, WHISTLE},
+    clock_rate, difficulty_range,
+    strain::{self, StrainPeaks},
+};
+
+const SECTION_LENGTH: f64 = 400.0;
+const DIFFICULTY_MULTIPLIER: f64 = 1.35;
+const FINAL_MULTIPLIER: f64 = 0.0625;
+const RHYTHM_SKILL_MULTIPLIER: f64 = 0.2 * FINAL_MULTIPLIER;
+const COLOUR_SKILL_MULTIPLIER: f64 = 0.375 * FINAL_MULTIPLIER;
+const STAMINA_SKILL_MULTIPLIER: f64 = 0.375 * FINAL_MULTIPLIER;
+
+/// How much faster than osu!standard sliders taiko drumrolls move.
+const LEGACY_VELOCITY_MULTIPLIER: f64 = 1.4;
+
+/// Ratios between the gaps before and after a note, with how hard it is to change to them.
+const COMMON_RHYTHMS: [(f64, f64); 9] = [
+    (1.0, 0.0),
+    (2.0 / 1.0, 0.3),
+    (1.0 / 2.0, 0.5),
+    (3.0 / 1.0, 0.3),
+    (1.0 / 3.0, 0.35),
+    (3.0 / 2.0, 0.6),
+    (2.0 / 3.0, 0.4),
+    (5.0 / 4.0, 0.5),
+    (4.0 / 5.0, 0.7),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum TaikoObject {
+    /// A don, or a kat if rim
+    Hit {
+        rim: bool,
+    },
+    DrumRoll,
+    Swell,
+}
+
+/// Convert the beatmap's objects to taiko notes with their start times, splitting short
+/// osu!standard sliders into a note on every beat as osu! does.
+fn objects(beatmap: &Beatmap) -> Vec<(f64, TaikoObject)> {
+    let convert = beatmap.mode == GameMode::Osu;
+    let is_rim = |sound: u8| sound & (WHISTLE | CLAP) != 0;
+    let mut objects = Vec::with_capacity(beatmap.hit_objects.len());
+
+    for hit_object in &beatmap.hit_objects {
+        let start_time = hit_object.start_time;
+
+        match &hit_object.kind {
+            HitObjectKind::Slider {
+                path,
+                spans,
+                node_sounds,
+            } => {
+                let distance = path.distance() * *spans as f64 * LEGACY_VELOCITY_MULTIPLIER;
+                let timing_beat_length = beatmap.beat_length_at(start_time);
+                let mut beat_length = timing_beat_length / beatmap.slider_velocity_at(start_time);
+
+                let slider_scoring_point_distance =
+                    100.0 * beatmap.slider_multiplier / beatmap.slider_tick_rate;
+                let taiko_velocity = slider_scoring_point_distance * beatmap.slider_tick_rate;
+                let taiko_duration = (distance / taiko_velocity * beat_length) as i32 as f64;
+
+                let osu_velocity = taiko_velocity * (1000.0 / beat_length);
+                // osu! only converts with the velocity adjusted beat length before version 8
+                if beatmap.format_version >= 8 {
+                    beat_length = timing_beat_length;
+                }
+                let tick_spacing =
+                    (beat_length / beatmap.slider_tick_rate).min(taiko_duration / *spans as f64);
+
+                let split = convert
+                    && tick_spacing > 0.0
+                    && distance / osu_velocity * 1000.0 < 2.0 * beat_length;
+                if !split {
+                    objects.push((start_time, TaikoObject::DrumRoll));
+                    continue;
+                }
+
+                let mut time = start_time;
+                let mut node = 0;
+                while time <= start_time + taiko_duration + tick_spacing / 8.0 {
+                    let rim = is_rim(node_sounds.get(node).copied().unwrap_or(hit_object.sound));
+                    objects.push((time, TaikoObject::Hit { rim }));
+
+                    node = (node + 1) % node_sounds.len().max(1);
+                    if tick_spacing.abs() < 1e-7 {
+                        break;
+                    }
+                    time += tick_spacing;
+                }
+            }
+            HitObjectKind::Spinner { .. } => objects.push((start_time, TaikoObject::Swell)),
+            HitObjectKind::Circle | HitObjectKind::Hold { .. } => objects.push((
+                start_time,
+                TaikoObject::Hit {
+                    rim: is_rim(hit_object.sound),
+                },
+            )),
+        }
+    }
+
+    objects.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    objects
+}
+
+/// A taiko object as seen from the ones before it, with the times scaled by the clock rate.
+struct DifficultyObject {
+    object: TaikoObject,
+    start_time: f64,
+    delta_time: f64,
+    /// Index of the closest of the common rhythms
+    rhythm: usize,
+    /// Index among the notes of the same colour
+    mono_index: Option<usize>,
+    /// Index among all notes
+    note_index: Option<usize>,
+    colour: Colour,
+}
+
+impl DifficultyObject {
+    fn rim(&self) -> Option<bool> {
+        match self.object {
+            TaikoObject::Hit { rim } => Some(rim),
+            _ => None,
+        }
+    }
+}
+
+/// The colour patterns an object is the first of.
+#[derive(Default)]
+struct Colour {
+    mono_streak: Option<usize>,
+    alternating_mono_pattern: Option<usize>,
+    repeating_hit_patterns: Option<usize>,
+}
+
+/// Consecutive notes of the same colour.
+struct MonoStreak {
+    objects: Vec<usize>,
+    rim: Option<bool>,
+    parent: usize,
+    index: usize,
+}
+
+/// Consecutive mono streaks of the same length.
+struct AlternatingMonoPattern {
+    mono_streaks: Vec<usize>,
+    parent: usize,
+    index: usize,
+}
+
+/// Alternating mono patterns which repeat one another.
+struct RepeatingHitPatterns {
+    alternating_mono_patterns: Vec<usize>,
+    repetition_interval: usize,
+}
+
+const MAX_REPETITION_INTERVAL: usize = 16;
+
+struct ColourEncoding {
+    mono_streaks: Vec<MonoStreak>,
+    alternating_mono_patterns: Vec<AlternatingMonoPattern>,
+    repeating_hit_patterns: Vec<RepeatingHitPatterns>,
+}
+
+impl ColourEncoding {
+    /// Group the objects into ever larger colour patterns, marking each object as the first of
+    /// the patterns it starts.
+    fn encode(objects: &mut [DifficultyObject], notes: &[usize]) -> Self {
+        let mut mono_streaks: Vec<MonoStreak> = Vec::new();
+        for index in 0..objects.len() {
+            let previous_note = objects[index]
+                .note_index
+                .and_then(|note_index| note_index.checked_sub(1))
+                .map(|note_index| notes[note_index]);
+
+            let new_streak = match (mono_streaks.last(), previous_note) {
+                (Some(_), Some(previous_note)) => {
+                    objects[index].rim() != objects[previous_note].rim()
+                }
+                _ => true,
+            };
+            if new_streak {
+                mono_streaks.push(MonoStreak {
+                    objects: Vec::new(),
+                    rim: objects[index].rim(),
+                    parent: 0,
+                    index: 0,
+                });
+            }
+            mono_streaks.last_mut().unwrap().objects.push(index);
+        }
+
+        let mut alternating_mono_patterns: Vec<AlternatingMonoPattern> = Vec::new();
+        for index in 0..mono_streaks.len() {
+            let new_pattern = index == 0
+                || mono_streaks[index].objects.len() != mono_streaks[index - 1].objects.len();
+            if new_pattern {
+                alternating_mono_patterns.push(AlternatingMonoPattern {
+                    mono_streaks: Vec::new(),
+                    parent: 0,
+                    index: 0,
+                });
+            }
+            alternating_mono_patterns
+                .last_mut()
+                .unwrap()
+                .mono_streaks
+                .push(index);
+        }
+
+        let mut encoding = Self {
+            mono_streaks,
+            alternating_mono_patterns,
+            repeating_hit_patterns: Vec::new(),
+        };
+
+        let pattern_count = encoding.alternating_mono_patterns.len();
+        let is_coupled = |encoding: &Self, index: usize| {
+            index + 2 < pattern_count && encoding.is_repetition_of(index, index + 2)
+        };
+
+        let mut index = 0;
+        while index < pattern_count {
+            let mut patterns = Vec::new();
+            if !is_coupled(&encoding, index) {
+                patterns.push(index);
+                index += 1;
+            } else {
+                while is_coupled(&encoding, index) {
+                    patterns.push(index);
+                    index += 1;
+                }
+                patterns.push(index);
+                patterns.push(index + 1);
+                index += 2;
+            }
+
+            encoding.repeating_hit_patterns.push(RepeatingHitPatterns {
+                alternating_mono_patterns: patterns,
+                repetition_interval: MAX_REPETITION_INTERVAL + 1,
+            });
+        }
+
+        for index in 0..encoding.repeating_hit_patterns.len() {
+            encoding.repeating_hit_patterns[index].repetition_interval =
+                encoding.repetition_interval(index);
+        }
+
+        for (repeating_index, repeating) in encoding.repeating_hit_patterns.iter().enumerate() {
+            let first_pattern = repeating.alternating_mono_patterns[0];
+            let first_object = encoding.mono_streaks
+                [encoding.alternating_mono_patterns[first_pattern].mono_streaks[0]]
+                .objects[0];
+            objects[first_object].colour.repeating_hit_patterns = Some(repeating_index);
+
+            for (index, pattern_index) in repeating.alternating_mono_patterns.iter().enumerate() {
+                let pattern = &mut encoding.alternating_mono_patterns[*pattern_index];
+                pattern.parent = repeating_index;
+                pattern.index = index;
+
+                let first_object = encoding.mono_streaks[pattern.mono_streaks[0]].objects[0];
+                objects[first_object].colour.alternating_mono_pattern = Some(*pattern_index);
+
+                for (index, streak_index) in pattern.mono_streaks.iter().enumerate() {
+                    let streak = &mut encoding.mono_streaks[*streak_index];
+                    streak.parent = *pattern_index;
+                    streak.index = index;
+
+                    objects[streak.objects[0]].colour.mono_streak = Some(*streak_index);
+                }
+            }
+        }
+
+        encoding
+    }
+
+    fn is_repetition_of(&self, pattern: usize, other: usize) -> bool {
+        let pattern = &self.alternating_mono_patterns[pattern];
+        let other = &self.alternating_mono_patterns[other];
+        let first_streak = &self.mono_streaks[pattern.mono_streaks[0]];
+        let other_first_streak = &self.mono_streaks[other.mono_streaks[0]];
+
+        first_streak.objects.len() == other_first_streak.objects.len()
+            && pattern.mono_streaks.len() == other.mono_streaks.len()
+            && first_streak.rim == other_first_streak.rim
+    }
+
+    /// How many repeating hit patterns back the same one last appeared.
+    fn repetition_interval(&self, index: usize) -> usize {
+        let patterns = &self.repeating_hit_patterns[index].alternating_mono_patterns;
+
+        for interval in 1..MAX_REPETITION_INTERVAL {
+            let other = match index.checked_sub(interval) {
+                Some(other) => &self.repeating_hit_patterns[other].alternating_mono_patterns,
+                None => break,
+            };
+
+            let repeats = other.len() == patterns.len()
+                && patterns
+                    .iter()
+                    .zip(other)
+                    .all(|(pattern, other)| self.is_repetition_of(*pattern, *other));
+            if repeats {
+                return interval.min(MAX_REPETITION_INTERVAL);
+            }
+        }
+
+        MAX_REPETITION_INTERVAL + 1
+    }
+
+    /// How hard changing colour at the object is.
+    fn difficulty_of(&self, object: &DifficultyObject) -> f64 {
+        let mut difficulty = 0.0;
+
+        if let Some(streak) = object.colour.mono_streak {
+            let streak = &self.mono_streaks[streak];
+            difficulty += sigmoid(streak.index as f64, 2.0, 2.0, 0.5, 1.0)
+                * self.pattern_difficulty(streak.parent)
+                * 0.5;
+        }
+        if let Some(pattern) = object.colour.alternating_mono_pattern {
+            difficulty += self.pattern_difficulty(pattern);
+        }
+        if let Some(repeating) = object.colour.repeating_hit_patterns {
+            difficulty += self.repeating_difficulty(repeating);
+        }
+
+        difficulty
+    }
+
+    fn pattern_difficulty(&self, pattern: usize) -> f64 {
+        let pattern = &self.alternating_mono_patterns[pattern];
+        sigmoid(pattern.index as f64, 2.0, 2.0, 0.5, 1.0)
+            * self.repeating_difficulty(pattern.parent)
+    }
+
+    fn repeating_difficulty(&self, repeating: usize) -> f64 {
+        let interval = self.repeating_hit_patterns[repeating].repetition_interval as f64;
+        2.0 * (1.0 - sigmoid(interval, 2.0, 2.0, 0.5, 1.0))
+    }
+}
+
+fn sigmoid(value: f64, center: f64, width: f64, middle: f64, height: f64) -> f64 {
+    (std::f64::consts::E * -(value - center) / width).tanh() * (height / 2.0) + middle
+}
+
+pub fn calculate(beatmap: &Beatmap, mods: GameMods) -> BeatmapDifficultyAttributes {
+    let clock_rate = clock_rate(mods);
+    let od = adjust_difficulty(beatmap.od, mods, 1.4);
+    let great_hit_window = difficulty_range(od, 50.0, 35.0, 20.0) / clock_rate;
+
+    let taiko_objects = objects(beatmap);
+    let max_combo = taiko_objects
+        .iter()
+        .filter(|(_, object)| matches!(object, TaikoObject::Hit { .. }))
+        .count() as u32;
+
+    let mut objects: Vec<DifficultyObject> = Vec::new();
+    let mut notes = Vec::new();
+    let mut mono_counts = [0, 0];
+    for index in 2..taiko_objects.len() {
+        let (start_time, object) = taiko_objects[index];
+        let (last_start_time, _) = taiko_objects[index - 1];
+        let (last_last_start_time, _) = taiko_objects[index - 2];
+
+        let delta_time = (start_time - last_start_time) / clock_rate;
+        let previous_length = (last_start_time - last_last_start_time) / clock_rate;
+        let ratio = delta_time / previous_length;
+        let rhythm = (0..COMMON_RHYTHMS.len())
+            .min_by(|a, b| {
+                (COMMON_RHYTHMS[*a].0 - ratio)
+                    .abs()
+                    .total_cmp(&(COMMON_RHYTHMS[*b].0 - ratio).abs())
+            })
+            .unwrap_or(0);
+
+        let (mono_index, note_index) = match object {
+            TaikoObject::Hit { rim } => {
+                let mono_count = &mut mono_counts[rim as usize];
+                *mono_count += 1;
+                notes.push(objects.len());
+                (Some(*mono_count - 1), Some(notes.len() - 1))
+            }
+            _ => (None, None),
+        };
+
+        objects.push(DifficultyObject {
+            object,
+            start_time: start_time / clock_rate,
+            delta_time,
+            rhythm,
+            mono_index,
+            note_index,
+            colour: Colour::default(),
+        });
+    }
+
+    let colour_encoding = ColourEncoding::encode(&mut objects, &notes);
+
+    let mut rhythm = Rhythm::new();
+    let mut rhythm_strain = StrainDecay::new(10.0, 0.0);
+    let mut colour = StrainDecay::new(0.12, 0.8);
+    let mut stamina = StrainDecay::new(1.1, 0.4);
+    // notes of each colour by their index among that colour
+    let mut monos: [Vec<usize>; 2] = [Vec::new(), Vec::new()];
+    for index in 0..objects.len() {
+        if let (Some(rim), Some(_)) = (objects[index].rim(), objects[index].mono_index) {
+            monos[rim as usize].push(index);
+        }
+
+        rhythm_strain.process(&objects, index, rhythm.difficulty_of(&objects, index));
+        colour.process(
+            &objects,
+            index,
+            colour_encoding.difficulty_of(&objects[index]),
+        );
+        stamina.process(
+            &objects,
+            index,
+            stamina_difficulty_of(&objects, &monos, index),
+        );
+    }
+
+    let colour_peaks = colour.peaks.peaks();
+    let rhythm_peaks = rhythm_strain.peaks.peaks();
+    let stamina_peaks = stamina.peaks.peaks();
+    let peaks: Vec<f64> = colour_peaks
+        .iter()
+        .zip(&rhythm_peaks)
+        .zip(&stamina_peaks)
+        .map(|((colour, rhythm), stamina)| {
+            let peak = norm(
+                1.5,
+                colour * COLOUR_SKILL_MULTIPLIER,
+                stamina * STAMINA_SKILL_MULTIPLIER,
+            );
+            norm(2.0, peak, rhythm * RHYTHM_SKILL_MULTIPLIER)
+        })
+        .filter(|peak| *peak > 0.0)
+        .collect();
+
+    let colour_rating =
+        strain::weighted_sum(colour_peaks, 0.9) * COLOUR_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+    let rhythm_rating =
+        strain::weighted_sum(rhythm_peaks, 0.9) * RHYTHM_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+    let stamina_rating =
+        strain::weighted_sum(stamina_peaks, 0.9) * STAMINA_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+    let combined_rating = strain::weighted_sum(peaks, 0.9) * DIFFICULTY_MULTIPLIER;
+
+    BeatmapDifficultyAttributes {
+        max_combo,
+        stars: rescale(combined_rating * 1.4) as f32,
+        attrs: GameModeAttributes::Taiko {
+            stamina_difficulty: stamina_rating as f32,
+            rhythm_difficulty: rhythm_rating as f32,
+            colour_difficulty: colour_rating as f32,
+            peak_difficulty: combined_rating as f32,
+            great_hit_window: great_hit_window as f32,
+        },
+    }
+}
+
+fn norm(p: f64, a: f64, b: f64) -> f64 {
+    (a.powf(p) + b.powf(p)).powf(1.0 / p)
+}
+
+/// Scale star ratings down logarithmically, so the hardest beatmaps don't run away.
+fn rescale(stars: f64) -> f64 {
+    match stars < 0.0 {
+        true => stars,
+        false => 10.43 * (stars / 8.0 + 1.0).ln(),
+    }
+}
+
+/// How hard a note is to hit in time with the same hand as the last note of its colour, which
+/// players alternate hands for.
+fn stamina_difficulty_of(
+    objects: &[DifficultyObject],
+    monos: &[Vec<usize>; 2],
+    index: usize,
+) -> f64 {
+    let current = &objects[index];
+    let (rim, mono_index) = match (current.rim(), current.mono_index) {
+        (Some(rim), Some(mono_index)) => (rim, mono_index),
+        _ => return 0.0,
+    };
+
+    let key_previous = match mono_index.checked_sub(2) {
+        Some(key_previous) => &objects[monos[rim as usize][key_previous]],
+        None => return 0.0,
+    };
+
+    // capped to 50ms between notes on the same key
+    let interval = (current.start_time - key_previous.start_time).max(50.0);
+    0.5 + 30.0 / interval
+}
+
+/// A skill whose strain decays exponentially, adding each object's difficulty.
+struct StrainDecay {
+    skill_multiplier: f64,
+    strain_decay_base: f64,
+    strain: f64,
+    peaks: StrainPeaks,
+}
+
+impl StrainDecay {
+    fn new(skill_multiplier: f64, strain_decay_base: f64) -> Self {
+        Self {
+            skill_multiplier,
+            strain_decay_base,
+            strain: 0.0,
+            peaks: StrainPeaks::new(SECTION_LENGTH),
+        }
+    }
+
+    fn process(&mut self, objects: &[DifficultyObject], index: usize, difficulty: f64) {
+        let current = &objects[index];
+        while let Some(section_start) = self.peaks.next_section(current.start_time) {
+            let time = section_start - objects[index - 1].start_time;
+            self.peaks
+                .start_section(self.strain * strain::decay(self.strain_decay_base, time));
+        }
+
+        self.strain *= strain::decay(self.strain_decay_base, current.delta_time);
+        self.strain += difficulty * self.skill_multiplier;
+        self.peaks.update(self.strain);
+    }
+}
+
+/// Rewards rhythm changes, unless they repeat recent ones.
+struct Rhythm {
+    history: VecDeque<usize>,
+    strain: f64,
+    notes_since_rhythm_change: usize,
+}
+
+impl Rhythm {
+    const STRAIN_DECAY: f64 = 0.96;
+    const HISTORY_MAX_LENGTH: usize = 8;
+
+    fn new() -> Self {
+        Self {
+            history: VecDeque::with_capacity(Self::HISTORY_MAX_LENGTH),
+            strain: 0.0,
+            notes_since_rhythm_change: 0,
+        }
+    }
+
+    fn difficulty_of(&mut self, objects: &[DifficultyObject], index: usize) -> f64 {
+        let current = &objects[index];
+        if current.rim().is_none() {
+            self.reset();
+            return 0.0;
+        }
+
+        self.strain *= Self::STRAIN_DECAY;
+        self.notes_since_rhythm_change += 1;
+
+        let rhythm_difficulty = COMMON_RHYTHMS[current.rhythm].1;
+        if rhythm_difficulty == 0.0 {
+            return 0.0;
+        }
+
+        let mut object_strain = rhythm_difficulty;
+        object_strain *= self.repetition_penalties(objects, index);
+        object_strain *= pattern_length_penalty(self.notes_since_rhythm_change);
+        object_strain *= self.speed_penalty(current.delta_time);
+
+        self.notes_since_rhythm_change = 0;
+        self.strain += object_strain;
+
+        self.strain
+    }
+
+    /// Penalise the rhythm for each recent pattern of it that repeats an earlier one.
+    fn repetition_penalties(&mut self, objects: &[DifficultyObject], index: usize) -> f64 {
+        if self.history.len() == Self::HISTORY_MAX_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(index);
+
+        let mut penalty = 1.0;
+        for patterns_to_compare in 2..=Self::HISTORY_MAX_LENGTH / 2 {
+            let latest = match self.history.len().checked_sub(patterns_to_compare) {
+                Some(latest) => latest,
+                None => continue,
+            };
+
+            for start in (0..latest).rev() {
+                let same_pattern = (0..patterns_to_compare).all(|offset| {
+                    objects[self.history[start + offset]].rhythm
+                        == objects[self.history[latest + offset]].rhythm
+                });
+                if !same_pattern {
+                    continue;
+                }
+
+                let notes_since = index - self.history[start];
+                penalty *= (0.032 * notes_since as f64).min(1.0);
+                break;
+            }
+        }
+
+        penalty
+    }
+
+    fn speed_penalty(&mut self, delta_time: f64) -> f64 {
+        if delta_time < 80.0 {
+            1.0
+        } else if delta_time < 210.0 {
+            (1.4 - 0.005 * delta_time).max(0.0)
+        } else {
+            self.reset();
+            0.0
+        }
+    }
+
+    fn reset(&mut self) {
+        self.strain = 0.0;
+        self.notes_since_rhythm_change = 0;
+    }
+}
+
+fn pattern_length_penalty(pattern_length: usize) -> f64 {
+    let short_pattern_penalty = (0.15 * pattern_length as f64).min(1.0);
+    let long_pattern_penalty = (2.5 - 0.15 * pattern_length as f64).clamp(0.0, 1.0);
+
+    short_pattern_penalty.min(long_pattern_penalty)
+}
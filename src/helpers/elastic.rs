@@ -1,13 +1,17 @@
 use elasticsearch::{
-    indices::{IndicesCreateParts, IndicesExistsParts},
-    BulkOperation, BulkOperations, CreateParts, Elasticsearch, UpdateParts,
+    indices::{
+        IndicesCreateParts, IndicesExistsParts, IndicesGetMappingParts, IndicesPutMappingParts,
+        IndicesRefreshParts,
+    },
+    BulkOperation, BulkOperations, CreateParts, DeleteByQueryParts, DeleteParts, Elasticsearch,
+    GetParts, IndexParts, MgetParts, OpenPointInTimeParts, UpdateByQueryParts, UpdateParts,
 };
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 
 pub async fn index_exists(database: &Elasticsearch, index: &str) -> anyhow::Result<bool> {
     let result = database
         .indices()
-        .exists(IndicesExistsParts::Index(&vec![index]))
+        .exists(IndicesExistsParts::Index(&[index]))
         .send()
         .await?
         .status_code()
@@ -38,11 +42,110 @@ pub async fn create_index_if_not_exists(
     Ok(())
 }
 
+/// Make every change to an index so far visible to searches.
+pub async fn refresh_index(database: &Elasticsearch, index: &str) -> anyhow::Result<()> {
+    database
+        .indices()
+        .refresh(IndicesRefreshParts::Index(&[index]))
+        .send()
+        .await?
+        .error_for_status_code()?;
+
+    Ok(())
+}
+
+/// Get the mapping of an index, as returned for the first index matched.
+pub async fn get_mapping(
+    database: &Elasticsearch,
+    index: &str,
+) -> anyhow::Result<serde_json::Value> {
+    let mapping = database
+        .indices()
+        .get_mapping(IndicesGetMappingParts::Index(&[index]))
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json::<serde_json::Value>()
+        .await?
+        .as_object()
+        .and_then(|indices| indices.values().next().cloned())
+        .and_then(|index| index.get("mappings").cloned())
+        .unwrap_or_default();
+
+    Ok(mapping)
+}
+
+pub async fn put_mapping(
+    database: &Elasticsearch,
+    index: &str,
+    mapping: serde_json::Value,
+) -> anyhow::Result<()> {
+    database
+        .indices()
+        .put_mapping(IndicesPutMappingParts::Index(&[index]))
+        .body(mapping)
+        .send()
+        .await?
+        .error_for_status_code()?;
+
+    Ok(())
+}
+
 pub struct ElasticDocument<T: Serialize> {
     pub id: String,
     pub data: T,
 }
 
+pub async fn get<T: DeserializeOwned>(
+    database: &Elasticsearch,
+    index: &str,
+    id: &str,
+) -> anyhow::Result<Option<T>> {
+    let elastic_response = database.get(GetParts::IndexId(index, id)).send().await?;
+
+    if elastic_response.status_code() == 404 {
+        return Ok(None);
+    }
+
+    let document = elastic_response
+        .json::<serde_json::Value>()
+        .await?
+        .pointer("/_source")
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+    Ok(document)
+}
+
+/// Get many documents by id in a single request, skipping any which don't exist.
+pub async fn mget<T: DeserializeOwned>(
+    database: &Elasticsearch,
+    index: &str,
+    ids: &[String],
+) -> anyhow::Result<Vec<T>> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let documents = database
+        .mget(MgetParts::Index(index))
+        .body(serde_json::json!({ "ids": ids }))
+        .send()
+        .await?
+        .json::<serde_json::Value>()
+        .await?
+        .pointer("/docs")
+        .and_then(|docs| docs.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|doc| {
+            doc.get("_source")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+        })
+        .collect();
+
+    Ok(documents)
+}
+
 pub async fn create<T: Serialize>(
     database: &Elasticsearch,
     index: &str,
@@ -57,6 +160,21 @@ pub async fn create<T: Serialize>(
     Ok(())
 }
 
+/// Create a document, or replace it if one with the same id exists.
+pub async fn index<T: Serialize>(
+    database: &Elasticsearch,
+    index: &str,
+    document: ElasticDocument<T>,
+) -> anyhow::Result<()> {
+    database
+        .index(IndexParts::IndexId(index, &document.id))
+        .body(document.data)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
 pub async fn bulk_create<T: Serialize>(
     database: &Elasticsearch,
     index: &str,
@@ -86,9 +204,112 @@ pub async fn update<T: Serialize>(
 ) -> anyhow::Result<()> {
     database
         .update(UpdateParts::IndexId(index, &document.id))
-        .body(document.data)
+        .body(serde_json::json!({ "doc": document.data }))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+pub async fn update_by_query(
+    database: &Elasticsearch,
+    index: &str,
+    body: serde_json::Value,
+) -> anyhow::Result<()> {
+    database
+        .update_by_query(UpdateByQueryParts::Index(&[index]))
+        .body(body)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+pub async fn delete(database: &Elasticsearch, index: &str, id: &str) -> anyhow::Result<()> {
+    database
+        .delete(DeleteParts::IndexId(index, id))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+pub async fn delete_by_query(
+    database: &Elasticsearch,
+    index: &str,
+    body: serde_json::Value,
+) -> anyhow::Result<()> {
+    database
+        .delete_by_query(DeleteByQueryParts::Index(&[index]))
+        .body(body)
+        .send()
+        .await?
+        .error_for_status_code()?;
+
+    Ok(())
+}
+
+/// Open a point-in-time on an index, returning its id.
+pub async fn open_point_in_time(
+    database: &Elasticsearch,
+    index: &str,
+    keep_alive: &str,
+) -> anyhow::Result<String> {
+    let pit_id = database
+        .open_point_in_time(OpenPointInTimeParts::Index(&[index]))
+        .keep_alive(keep_alive)
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json::<serde_json::Value>()
+        .await?
+        .get("id")
+        .and_then(|id| id.as_str())
+        .map(|id| id.to_string())
+        .ok_or_else(|| anyhow::anyhow!("no point-in-time id was returned"))?;
+
+    Ok(pit_id)
+}
+
+pub async fn close_point_in_time(database: &Elasticsearch, pit_id: &str) -> anyhow::Result<()> {
+    database
+        .close_point_in_time()
+        .body(serde_json::json!({ "id": pit_id }))
         .send()
         .await?;
 
     Ok(())
 }
+
+/// Wrap a query so matching documents are scored at random, optionally weighted by a numeric
+/// field. Documents with the same `seed_field` value get the same score, and the same seed
+/// gives the same order as long as the matching documents don't change.
+pub fn random_query(
+    query: serde_json::Value,
+    seed: u32,
+    seed_field: &str,
+    weight_field: Option<&str>,
+) -> serde_json::Value {
+    match weight_field {
+        Some(weight_field) => {
+            // raising a uniform random number to 1 / weight and taking the highest picks each
+            // document with probability proportional to its weight
+            serde_json::json!({
+                "script_score": {
+                    "query": query,
+                    "script": {
+                        "source": "double weight = doc[params.field].size() == 0 ? 0 : doc[params.field].value; return Math.pow(randomScore(params.seed, params.seed_field), 1.0 / (weight + 1));",
+                        "params": { "seed": seed, "seed_field": seed_field, "field": weight_field }
+                    }
+                }
+            })
+        }
+        None => serde_json::json!({
+            "function_score": {
+                "query": query,
+                "random_score": { "seed": seed, "field": seed_field },
+                "boost_mode": "replace"
+            }
+        }),
+    }
+}
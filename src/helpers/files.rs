@@ -0,0 +1,36 @@
+use std::path::Path;
+
+/// Size of a file in bytes, or 0 if it doesn't exist.
+pub async fn size(path: &Path) -> anyhow::Result<u64> {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(anyhow::anyhow!("failed to check {}: {}", path.display(), e)),
+    }
+}
+
+/// Write a file through a uniquely named temporary file beside it, so readers never see a
+/// partial file and concurrent writers of the same file don't trip over each other.
+pub async fn write_atomically(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let file_name = path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("invalid file name {}", path.display()))?;
+    let temp_path = path.with_file_name(format!("{}.{:016x}.tmp", file_name, fastrand::u64(..)));
+
+    let written = match tokio::fs::write(&temp_path, contents).await {
+        Ok(()) => tokio::fs::rename(&temp_path, path).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        return Err(anyhow::anyhow!("failed to write {}: {}", path.display(), e));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use moka::{sync::Cache, Expiry};
+use rosu_v2::prelude::RankStatus;
+
+#[derive(Clone)]
+struct CacheEntry<V> {
+    value: V,
+    ttl: Duration,
+}
+
+struct EntryExpiry;
+
+impl<V> Expiry<u32, CacheEntry<V>> for EntryExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &u32,
+        entry: &CacheEntry<V>,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+}
+
+/// Bounded in-memory cache of documents in front of elasticsearch, keyed by id.
+///
+/// Entries for statuses that can no longer change are kept for longer. A capacity of 0
+/// disables the cache.
+pub struct HotCache<V> {
+    cache: Option<Cache<u32, CacheEntry<V>>>,
+    ranked_ttl: Duration,
+    unranked_ttl: Duration,
+}
+
+impl<V> HotCache<V>
+where
+    V: Clone + Send + Sync + 'static,
+{
+    pub fn new(capacity: u64, ranked_ttl: Duration, unranked_ttl: Duration) -> Self {
+        let cache = (capacity > 0).then(|| {
+            Cache::builder()
+                .max_capacity(capacity)
+                .expire_after(EntryExpiry)
+                .build()
+        });
+
+        Self {
+            cache,
+            ranked_ttl,
+            unranked_ttl,
+        }
+    }
+
+    pub fn get(&self, id: u32) -> Option<V> {
+        self.cache
+            .as_ref()
+            .and_then(|cache| cache.get(&id))
+            .map(|entry| entry.value)
+    }
+
+    pub fn insert(&self, id: u32, value: V, status: RankStatus) {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return,
+        };
+
+        let ttl = self.ttl(status);
+        cache.insert(id, CacheEntry { value, ttl });
+    }
+
+    fn ttl(&self, status: RankStatus) -> Duration {
+        match status {
+            RankStatus::Ranked | RankStatus::Approved | RankStatus::Loved => self.ranked_ttl,
+            _ => self.unranked_ttl,
+        }
+    }
+
+    pub fn invalidate(&self, id: u32) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: u64) -> HotCache<&'static str> {
+        HotCache::new(capacity, Duration::from_secs(3600), Duration::from_secs(60))
+    }
+
+    #[test]
+    fn keeps_settled_statuses_for_longer() {
+        let cache = cache(10);
+
+        for status in [RankStatus::Ranked, RankStatus::Approved, RankStatus::Loved] {
+            assert_eq!(cache.ttl(status), Duration::from_secs(3600));
+        }
+
+        for status in [
+            RankStatus::Graveyard,
+            RankStatus::WIP,
+            RankStatus::Pending,
+            RankStatus::Qualified,
+        ] {
+            assert_eq!(cache.ttl(status), Duration::from_secs(60));
+        }
+    }
+
+    #[test]
+    fn returns_inserted_entries() {
+        let cache = cache(10);
+
+        cache.insert(1, "ranked", RankStatus::Ranked);
+        cache.insert(2, "pending", RankStatus::Pending);
+
+        assert_eq!(cache.get(1), Some("ranked"));
+        assert_eq!(cache.get(2), Some("pending"));
+        assert_eq!(cache.get(3), None);
+    }
+
+    #[test]
+    fn capacity_of_zero_disables_the_cache() {
+        let cache = cache(0);
+
+        cache.insert(1, "ranked", RankStatus::Ranked);
+
+        assert_eq!(cache.get(1), None);
+    }
+
+    #[test]
+    fn invalidated_entries_are_gone() {
+        let cache = cache(10);
+
+        cache.insert(1, "ranked", RankStatus::Ranked);
+        cache.insert(2, "loved", RankStatus::Loved);
+        cache.invalidate(1);
+
+        assert_eq!(cache.get(1), None);
+        assert_eq!(cache.get(2), Some("loved"));
+    }
+}
@@ -1 +1,12 @@
+pub mod archive;
+pub mod archive_stats;
+pub mod audio;
+pub mod difficulty;
 pub mod elastic;
+pub mod files;
+pub mod hot_cache;
+pub mod mp3;
+pub mod osu_file;
+pub mod performance;
+pub mod render;
+pub mod single_flight;
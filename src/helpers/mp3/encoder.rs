@@ -0,0 +1,610 @@
+//! A small MPEG-1 layer III encoder for mono audio, following the structure of shine: long
+//! blocks only, no psychoacoustic model and no bit reservoir. Each granule is quantized as finely
+//! as its share of the frame allows, which is plenty for short previews at a low bitrate.
+
+use std::f32::consts::PI;
+
+use super::tables::{Table, ANALYSIS_WINDOW, QUAD_BITS, QUAD_CODES, SCALEFACTOR_BANDS, TABLES};
+
+/// Sample rate the encoder takes its input at.
+pub const SAMPLE_RATE: u32 = 32_000;
+
+/// Frames are 64 kbps, which at 32 kHz makes every one of them 288 bytes without padding.
+const FRAME_LENGTH: usize = 288;
+
+/// MPEG-1 layer III without CRC, 64 kbps, 32 kHz, mono and marked as an original.
+const HEADER: [u8; 4] = [0xff, 0xfb, 0x58, 0xc4];
+
+const SIDE_INFO_LENGTH: usize = 17;
+
+/// Bits of main data for each of the two granules of a frame.
+const GRANULE_BITS: usize = (FRAME_LENGTH - HEADER.len() - SIDE_INFO_LENGTH) * 8 / 2;
+
+const GRANULE_SAMPLES: usize = 576;
+
+/// Spectral lines above about 11 kHz are dropped, leaving their bits to the ones that matter at
+/// this bitrate.
+const BANDWIDTH: usize = 396;
+
+/// Largest quantized value, the most table 24 can code with its 13 linbits.
+const MAX_QUANTIZED: u32 = 15 + (1 << 13) - 1;
+
+/// Region counts for big values ending in each scalefactor band, as shine picks them.
+const SUBDIVISIONS: [(usize, usize); 23] = [
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 1),
+    (1, 1),
+    (1, 1),
+    (1, 2),
+    (2, 2),
+    (2, 3),
+    (2, 3),
+    (3, 4),
+    (3, 4),
+    (3, 4),
+    (4, 5),
+    (4, 5),
+    (4, 6),
+    (5, 6),
+    (5, 6),
+    (5, 7),
+    (6, 7),
+    (6, 7),
+];
+
+/// Coefficients of the alias reduction butterflies, from table B.9.
+const ALIAS_COEFFICIENTS: [f32; 8] = [
+    -0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037,
+];
+
+/// Encode mono samples at [`SAMPLE_RATE`] to a constant bitrate MP3 stream. The decoded audio
+/// starts a little over 500 samples late, as with any layer III encoder, so the end is padded
+/// with silence to keep all of it.
+pub fn encode(samples: &[f32]) -> Vec<u8> {
+    let frame_samples = 2 * GRANULE_SAMPLES;
+    let frames = (samples.len() + frame_samples).div_ceil(frame_samples);
+
+    let mut encoder = Encoder::new();
+    let mut stream = Vec::with_capacity(frames * FRAME_LENGTH);
+
+    let mut padded = samples.to_vec();
+    padded.resize(frames * frame_samples, 0.0);
+
+    for frame in padded.chunks_exact(frame_samples) {
+        let granules = [
+            encoder.granule(&frame[..GRANULE_SAMPLES]),
+            encoder.granule(&frame[GRANULE_SAMPLES..]),
+        ];
+
+        write_frame(&granules, &mut stream);
+    }
+
+    stream
+}
+
+struct Encoder {
+    /// The last 512 input samples, newest first.
+    fifo: [f32; 512],
+    /// Subband samples of the previous granule, which the MDCT overlaps with the current one.
+    previous: [[f32; 18]; 32],
+    /// Matrixing of the polyphase filterbank, by subband.
+    matrix: Vec<[f32; 64]>,
+    /// The windowed MDCT, by spectral line of a subband.
+    mdct: Vec<[f32; 36]>,
+    /// Butterfly coefficients `(cs, ca)` of the alias reduction.
+    butterflies: [(f32, f32); 8],
+}
+
+impl Encoder {
+    fn new() -> Self {
+        let matrix = (0..32)
+            .map(|k| {
+                let mut row = [0.0; 64];
+                for (i, value) in row.iter_mut().enumerate() {
+                    *value = ((2 * k + 1) as f32 * (i as f32 - 16.0) * PI / 64.0).cos();
+                }
+                row
+            })
+            .collect();
+
+        let mdct = (0..18)
+            .map(|k| {
+                let mut row = [0.0; 36];
+                for (n, value) in row.iter_mut().enumerate() {
+                    let window = (PI / 36.0 * (n as f32 + 0.5)).sin();
+                    *value =
+                        window * (PI / 72.0 * (2 * n + 19) as f32 * (2 * k + 1) as f32).cos() / 9.0;
+                }
+                row
+            })
+            .collect();
+
+        let butterflies = ALIAS_COEFFICIENTS.map(|c| {
+            let norm = (1.0 + c * c).sqrt();
+            (1.0 / norm, c / norm)
+        });
+
+        Encoder {
+            fifo: [0.0; 512],
+            previous: [[0.0; 18]; 32],
+            matrix,
+            mdct,
+            butterflies,
+        }
+    }
+
+    /// Turn 576 samples into the spectrum of a granule and quantize it to fit its bits.
+    fn granule(&mut self, samples: &[f32]) -> Granule {
+        let mut subbands = [[0.0; 18]; 32];
+
+        for (time, block) in samples.chunks_exact(32).enumerate() {
+            let filtered = self.filter(block);
+
+            for (subband, sample) in filtered.iter().enumerate() {
+                // the decoder inverts the frequencies of odd subbands, so the encoder does too
+                subbands[subband][time] = if subband % 2 == 1 && time % 2 == 1 {
+                    -sample
+                } else {
+                    *sample
+                };
+            }
+        }
+
+        let mut spectrum = [0.0; GRANULE_SAMPLES];
+
+        for (subband, lines) in spectrum.chunks_exact_mut(18).enumerate() {
+            let mut input = [0.0; 36];
+            input[..18].copy_from_slice(&self.previous[subband]);
+            input[18..].copy_from_slice(&subbands[subband]);
+
+            for (line, row) in lines.iter_mut().zip(&self.mdct) {
+                *line = row.iter().zip(&input).map(|(a, b)| a * b).sum();
+            }
+        }
+
+        self.previous = subbands;
+
+        // undo the aliasing the decoder's butterflies will add between neighbouring subbands
+        for subband in 1..32 {
+            for (i, (cs, ca)) in self.butterflies.iter().enumerate() {
+                let lower = 18 * subband - 1 - i;
+                let upper = 18 * subband + i;
+                let (l, u) = (spectrum[lower], spectrum[upper]);
+
+                spectrum[lower] = l * cs + u * ca;
+                spectrum[upper] = u * cs - l * ca;
+            }
+        }
+
+        spectrum[BANDWIDTH..].fill(0.0);
+
+        Granule::quantize(&spectrum)
+    }
+
+    /// Run 32 new samples through the polyphase filterbank, giving a sample of each subband.
+    fn filter(&mut self, block: &[f32]) -> [f32; 32] {
+        self.fifo.copy_within(..480, 32);
+        for (i, sample) in block.iter().enumerate() {
+            self.fifo[31 - i] = *sample;
+        }
+
+        let mut partial = [0.0; 64];
+        for (i, value) in partial.iter_mut().enumerate() {
+            *value = (0..8)
+                .map(|j| ANALYSIS_WINDOW[i + 64 * j] * self.fifo[i + 64 * j])
+                .sum();
+        }
+
+        let mut subbands = [0.0; 32];
+        for (subband, row) in subbands.iter_mut().zip(&self.matrix) {
+            *subband = row.iter().zip(&partial).map(|(a, b)| a * b).sum();
+        }
+
+        subbands
+    }
+}
+
+/// A quantized granule along with how its side information describes it.
+struct Granule {
+    values: [i32; GRANULE_SAMPLES],
+    global_gain: u32,
+    /// Number of pairs of values coded with the big value tables.
+    big_values: usize,
+    /// Number of quadruples of values no larger than 1, after the big values.
+    count1: usize,
+    table_select: [usize; 3],
+    region0_count: usize,
+    region1_count: usize,
+    count1_table: usize,
+    /// Length of the granule's Huffman codes in bits.
+    length: usize,
+}
+
+impl Granule {
+    /// Quantize a spectrum with the smallest step size whose codes fit in the granule.
+    fn quantize(spectrum: &[f32; GRANULE_SAMPLES]) -> Granule {
+        let fits = |global_gain| {
+            Granule::with_gain(spectrum, global_gain)
+                .filter(|granule| granule.length <= GRANULE_BITS)
+        };
+
+        // larger gains mean coarser steps and fewer bits, so the finest fitting one is found
+        // by bisection
+        let (mut low, mut high) = (0, 255);
+        let mut best = fits(high);
+
+        while low < high {
+            let middle = (low + high) / 2;
+
+            match fits(middle) {
+                Some(granule) => {
+                    best = Some(granule);
+                    high = middle;
+                }
+                None => low = middle + 1,
+            }
+        }
+
+        best.unwrap_or_else(|| Granule::layout([0; GRANULE_SAMPLES], 0))
+    }
+
+    /// Quantize a spectrum with a global gain, unless some value gets too large to code.
+    fn with_gain(spectrum: &[f32; GRANULE_SAMPLES], global_gain: u32) -> Option<Granule> {
+        let step = 2f32.powf((global_gain as f32 - 210.0) / 4.0);
+        let mut values = [0; GRANULE_SAMPLES];
+
+        for (value, line) in values.iter_mut().zip(spectrum) {
+            let magnitude = ((line.abs() / step).powf(0.75) + 0.4054).floor();
+            if magnitude > MAX_QUANTIZED as f32 {
+                return None;
+            }
+
+            *value = magnitude as i32 * if *line < 0.0 { -1 } else { 1 };
+        }
+
+        Some(Granule::layout(values, global_gain))
+    }
+
+    /// Split quantized values into big values, count1 quadruples and trailing zeros, and pick the
+    /// regions and tables that code them in the fewest bits.
+    fn layout(values: [i32; GRANULE_SAMPLES], global_gain: u32) -> Granule {
+        let mut end = GRANULE_SAMPLES;
+        while end > 1 && values[end - 1] == 0 && values[end - 2] == 0 {
+            end -= 2;
+        }
+
+        let mut count1_start = end;
+        while count1_start > 3
+            && values[count1_start - 4..count1_start]
+                .iter()
+                .all(|value| value.abs() <= 1)
+        {
+            count1_start -= 4;
+        }
+
+        let (region0_count, region1_count, region1_start, region2_start) = regions(count1_start);
+        let bounds = [
+            (0, region1_start),
+            (region1_start, region2_start),
+            (region2_start, count1_start),
+        ];
+
+        let mut table_select = [0; 3];
+        let mut length = 0;
+
+        for (table, (start, end)) in table_select.iter_mut().zip(bounds) {
+            let (best, bits) = best_table(&values[start..end]);
+            *table = best;
+            length += bits;
+        }
+
+        let count1 = &values[count1_start..end];
+        let (count1_table, count1_bits) = (0..2)
+            .map(|table| (table, quads_length(count1, table)))
+            .min_by_key(|(_, bits)| *bits)
+            .unwrap();
+
+        Granule {
+            values,
+            global_gain,
+            big_values: count1_start / 2,
+            count1: (end - count1_start) / 4,
+            table_select,
+            region0_count,
+            region1_count,
+            count1_table,
+            length: length + count1_bits,
+        }
+    }
+
+    fn write_side_info(&self, writer: &mut BitWriter) {
+        writer.put(self.length as u32, 12);
+        writer.put(self.big_values as u32, 9);
+        writer.put(self.global_gain, 8);
+        // no scalefactors
+        writer.put(0, 4);
+        // no window switching
+        writer.put(0, 1);
+        for table in self.table_select {
+            writer.put(table as u32, 5);
+        }
+        writer.put(self.region0_count as u32, 4);
+        writer.put(self.region1_count as u32, 3);
+        // no preflag and the default scalefactor scale
+        writer.put(0, 2);
+        writer.put(self.count1_table as u32, 1);
+    }
+
+    fn write_main_data(&self, writer: &mut BitWriter) {
+        let (_, _, region1_start, region2_start) = regions(self.big_values * 2);
+        let big_values_end = self.big_values * 2;
+
+        for (index, pair) in self.values[..big_values_end].chunks_exact(2).enumerate() {
+            let region = match index * 2 {
+                i if i < region1_start => 0,
+                i if i < region2_start => 1,
+                _ => 2,
+            };
+
+            if let Some(table) = &TABLES[self.table_select[region]] {
+                write_pair(writer, table, pair[0], pair[1]);
+            }
+        }
+
+        let count1_end = big_values_end + self.count1 * 4;
+        for quad in self.values[big_values_end..count1_end].chunks_exact(4) {
+            let index = quad_index(quad);
+            writer.put(
+                QUAD_CODES[self.count1_table][index],
+                QUAD_BITS[self.count1_table][index] as u32,
+            );
+
+            for value in quad.iter().filter(|value| **value != 0) {
+                writer.put((*value < 0) as u32, 1);
+            }
+        }
+    }
+}
+
+/// Region counts and where the second and third regions start for big values ending at `end`,
+/// clamped to the big values.
+fn regions(end: usize) -> (usize, usize, usize, usize) {
+    if end == 0 {
+        return (0, 0, 0, 0);
+    }
+
+    let bands = SCALEFACTOR_BANDS
+        .iter()
+        .position(|band| *band >= end)
+        .unwrap_or(SCALEFACTOR_BANDS.len() - 1);
+    let (mut region0_count, mut region1_count) = SUBDIVISIONS[bands];
+
+    while region0_count > 0 && SCALEFACTOR_BANDS[region0_count + 1] > end {
+        region0_count -= 1;
+    }
+    while region1_count > 0 && SCALEFACTOR_BANDS[region0_count + region1_count + 2] > end {
+        region1_count -= 1;
+    }
+
+    let region1_start = SCALEFACTOR_BANDS[region0_count + 1].min(end);
+    let region2_start = SCALEFACTOR_BANDS[region0_count + region1_count + 2].min(end);
+
+    (region0_count, region1_count, region1_start, region2_start)
+}
+
+/// The table coding a region of big values in the fewest bits, along with that many bits.
+fn best_table(values: &[i32]) -> (usize, usize) {
+    let max = values
+        .iter()
+        .map(|value| value.unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    if max == 0 {
+        return (0, 0);
+    }
+
+    let fitting = |range: std::ops::Range<usize>| {
+        range.filter(move |table| {
+            TABLES[*table]
+                .as_ref()
+                .is_some_and(|candidate| candidate.max_value() >= max)
+        })
+    };
+
+    // tables with linbits grow in size with their linbits, so only the smallest fitting one of
+    // each family is worth trying
+    let candidates: Vec<usize> = if max <= 15 {
+        fitting(1..16).collect()
+    } else {
+        fitting(16..24)
+            .take(1)
+            .chain(fitting(24..32).take(1))
+            .collect()
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|table| {
+            let candidate = TABLES[table].as_ref()?;
+            let bits = values
+                .chunks_exact(2)
+                .map(|pair| pair_length(candidate, pair[0], pair[1]))
+                .sum();
+            Some((table, bits))
+        })
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap()
+}
+
+fn pair_length(table: &Table, x: i32, y: i32) -> usize {
+    let (x, y) = (x.unsigned_abs(), y.unsigned_abs());
+    let escaped = |value: u32| match table.linbits {
+        0 => (value, 0),
+        linbits => (value.min(15), if value >= 15 { linbits } else { 0 }),
+    };
+    let (code_x, linbits_x) = escaped(x);
+    let (code_y, linbits_y) = escaped(y);
+
+    table.bits[code_x as usize * table.wrap + code_y as usize] as usize
+        + (linbits_x + linbits_y) as usize
+        + (x != 0) as usize
+        + (y != 0) as usize
+}
+
+fn write_pair(writer: &mut BitWriter, table: &Table, x: i32, y: i32) {
+    let index = |value: u32| match table.linbits {
+        0 => value,
+        _ => value.min(15),
+    };
+    let (code_x, code_y) = (index(x.unsigned_abs()), index(y.unsigned_abs()));
+    let code = code_x as usize * table.wrap + code_y as usize;
+
+    writer.put(table.codes[code], table.bits[code] as u32);
+
+    for (value, code) in [(x, code_x), (y, code_y)] {
+        if value == 0 {
+            continue;
+        }
+        if table.linbits > 0 && code == 15 {
+            writer.put(value.unsigned_abs() - 15, table.linbits);
+        }
+        writer.put((value < 0) as u32, 1);
+    }
+}
+
+fn quad_index(quad: &[i32]) -> usize {
+    quad.iter()
+        .fold(0, |index, value| (index << 1) | (*value != 0) as usize)
+}
+
+fn quads_length(values: &[i32], table: usize) -> usize {
+    values
+        .chunks_exact(4)
+        .map(|quad| {
+            QUAD_BITS[table][quad_index(quad)] as usize
+                + quad.iter().filter(|value| **value != 0).count()
+        })
+        .sum()
+}
+
+fn write_frame(granules: &[Granule; 2], stream: &mut Vec<u8>) {
+    stream.extend_from_slice(&HEADER);
+
+    let mut side_info = BitWriter::default();
+    // main data starts in this frame, no private bits and no shared scalefactors
+    side_info.put(0, 9 + 5 + 4);
+    for granule in granules {
+        granule.write_side_info(&mut side_info);
+    }
+    stream.extend_from_slice(&side_info.bytes);
+
+    let mut main_data = BitWriter::default();
+    for granule in granules {
+        granule.write_main_data(&mut main_data);
+    }
+
+    let mut main_data = main_data.bytes;
+    main_data.resize(FRAME_LENGTH - HEADER.len() - SIDE_INFO_LENGTH, 0);
+    stream.extend_from_slice(&main_data);
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Bits used of the last byte, 0 when it's full.
+    used: u32,
+}
+
+impl BitWriter {
+    /// Append the lowest `bits` bits of a value, most significant first.
+    fn put(&mut self, value: u32, bits: u32) {
+        for bit in (0..bits).rev() {
+            if self.used == 0 {
+                self.bytes.push(0);
+            }
+
+            let last = self.bytes.last_mut().unwrap();
+            *last |= (((value >> bit) & 1) as u8) << (7 - self.used);
+            self.used = (self.used + 1) % 8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::audio;
+
+    fn sine(frequency: f32, amplitude: f32, length: usize) -> Vec<f32> {
+        (0..length)
+            .map(|i| amplitude * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn encodes_frames_of_constant_size() {
+        let encoded = encode(&sine(440.0, 0.5, SAMPLE_RATE as usize));
+
+        // a second of audio and the padding for the encoder delay
+        assert_eq!(encoded.len(), 29 * FRAME_LENGTH);
+        for frame in encoded.chunks_exact(FRAME_LENGTH) {
+            assert_eq!(frame[..4], HEADER);
+        }
+    }
+
+    #[test]
+    fn round_trips_sines() {
+        for frequency in [100.0, 440.0, 3000.0, 7000.0] {
+            let encoded = encode(&sine(frequency, 0.5, SAMPLE_RATE as usize));
+            let decoded = audio::decode(encoded, Some("mp3"), None).unwrap();
+            assert_eq!(decoded.sample_rate, SAMPLE_RATE);
+
+            // half a second from the middle, away from the fading in at the start
+            let middle = &decoded.samples[8000..24000];
+            let crossings = middle
+                .windows(2)
+                .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+                .count();
+
+            assert!((rms(middle) - 0.5 / 2f32.sqrt()).abs() < 0.005);
+            assert!((crossings as f32 - frequency).abs() <= 2.0);
+        }
+    }
+
+    #[test]
+    fn drops_frequencies_above_the_bandwidth() {
+        let encoded = encode(&sine(13_000.0, 0.5, SAMPLE_RATE as usize));
+        let decoded = audio::decode(encoded, Some("mp3"), None).unwrap();
+
+        assert!(rms(&decoded.samples) < 0.01);
+    }
+
+    #[test]
+    fn fits_noise_in_its_frames() {
+        let mut state = 1u32;
+        let noise: Vec<f32> = (0..SAMPLE_RATE as usize)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                ((state >> 16) as f32 / 32768.0 - 1.0) * 0.8
+            })
+            .collect();
+
+        let encoded = encode(&noise);
+        assert_eq!(encoded.len(), 29 * FRAME_LENGTH);
+
+        // every frame decodes, as frames that fail to are silent
+        let decoded = audio::decode(encoded, Some("mp3"), None).unwrap();
+        assert!(decoded.samples[2000..30000]
+            .iter()
+            .all(|sample| *sample != 0.0));
+        assert!(rms(&decoded.samples[2000..30000]) > 0.3);
+    }
+}
@@ -0,0 +1,279 @@
+mod encoder;
+mod tables;
+
+use crate::helpers::audio;
+
+pub use encoder::{encode, SAMPLE_RATE};
+
+/// Bitrates in kbps by bitrate index, for MPEG-1 layers I, II and III.
+const MPEG1_BITRATES: [[u32; 15]; 3] = [
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+];
+
+/// Bitrates in kbps by bitrate index, for MPEG-2 and 2.5 layer I, then layers II and III.
+const MPEG2_BITRATES: [[u32; 15]; 2] = [
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+/// Frames kept ahead of a cut section, enough to hold the largest bit reservoir layer III can
+/// refer back to.
+const LEAD_FRAMES: usize = 10;
+
+const SAMPLE_RATES: [[u32; 3]; 3] = [
+    [44100, 48000, 32000], // MPEG-1
+    [22050, 24000, 16000], // MPEG-2
+    [11025, 12000, 8000],  // MPEG-2.5
+];
+
+struct Frame {
+    offset: usize,
+    length: usize,
+    /// Microseconds of audio in the frame
+    duration: u64,
+}
+
+/// Read the frame header at the start of the data, if there is a valid one.
+fn frame_header(data: &[u8]) -> Option<(usize, u64)> {
+    if data.len() < 4 || data[0] != 0xff || data[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+
+    // 0 is MPEG-1, 1 is MPEG-2 and 2 is MPEG-2.5
+    let version = match (data[1] >> 3) & 0b11 {
+        0b11 => 0,
+        0b10 => 1,
+        0b00 => 2,
+        _ => return None,
+    };
+    // 0 is layer I, 1 is layer II and 2 is layer III
+    let layer = match (data[1] >> 1) & 0b11 {
+        0b11 => 0,
+        0b10 => 1,
+        0b01 => 2,
+        _ => return None,
+    };
+
+    let bitrate_index = (data[2] >> 4) as usize;
+    let sample_rate_index = ((data[2] >> 2) & 0b11) as usize;
+    let padding = ((data[2] >> 1) & 1) as usize;
+
+    // free format streams don't give a bitrate to find the frame length from
+    if bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+
+    let bitrate = match version {
+        0 => MPEG1_BITRATES[layer][bitrate_index],
+        _ => MPEG2_BITRATES[(layer > 0) as usize][bitrate_index],
+    } as usize
+        * 1000;
+    let sample_rate = SAMPLE_RATES[version][sample_rate_index] as usize;
+
+    let (samples, length) = match (layer, version) {
+        (0, _) => (384, (12 * bitrate / sample_rate + padding) * 4),
+        (1, _) | (2, 0) => (1152, 144 * bitrate / sample_rate + padding),
+        _ => (576, 72 * bitrate / sample_rate + padding),
+    };
+
+    Some((length, samples as u64 * 1_000_000 / sample_rate as u64))
+}
+
+/// Find the audio frames of an MP3 file, skipping tags and anything else between frames.
+fn frames(audio: &[u8]) -> Vec<Frame> {
+    let mut offset = 0;
+
+    // an id3v2 tag's size is stored in 7 bit bytes, followed by a footer if flagged
+    if audio.len() >= 10 && &audio[..3] == b"ID3" {
+        let size = audio[6..10]
+            .iter()
+            .fold(0, |size, byte| (size << 7) | (*byte & 0x7f) as usize);
+        let footer = if audio[5] & 0x10 != 0 { 10 } else { 0 };
+
+        offset = 10 + size + footer;
+    }
+
+    let mut frames = Vec::new();
+
+    while offset + 4 <= audio.len() {
+        match frame_header(&audio[offset..]) {
+            Some((length, duration)) if offset + length <= audio.len() => {
+                frames.push(Frame {
+                    offset,
+                    length,
+                    duration,
+                });
+                offset += length;
+            }
+            _ => offset += 1,
+        }
+    }
+
+    // a xing or info frame at the start holds the length of the whole file, not audio
+    if let Some(first) = frames.first() {
+        let head = &audio[first.offset..(first.offset + first.length).min(first.offset + 64)];
+        if head
+            .windows(4)
+            .any(|window| window == b"Xing" || window == b"Info")
+        {
+            frames.remove(0);
+        }
+    }
+
+    frames
+}
+
+/// A section cut out of an MP3 file.
+pub struct Section {
+    pub audio: Vec<u8>,
+    /// Microseconds of audio before the start of the section, from the frames kept ahead of it.
+    pub lead: u64,
+}
+
+/// Cut a section out of an MP3 file along frame boundaries, without re-encoding. When no start
+/// is given the section starts 40% into the audio, as osu! does for previews. A few frames are
+/// kept ahead of the section, since the first frames of a section can store part of their data in
+/// the ones before them.
+///
+/// Returns `None` if no MP3 frames were found.
+pub fn cut(audio: &[u8], start_ms: Option<u32>, length_ms: u32) -> Option<Section> {
+    let frames = frames(audio);
+    if frames.is_empty() {
+        return None;
+    }
+
+    let total: u64 = frames.iter().map(|frame| frame.duration).sum();
+    let start = audio::section_start(total, start_ms, length_ms);
+    let end = start + length_ms as u64 * 1000;
+
+    let mut time = 0;
+    let mut first = frames.len();
+    let mut last = frames.len();
+
+    for (index, frame) in frames.iter().enumerate() {
+        if time + frame.duration > start && first == frames.len() {
+            first = index;
+        }
+        if time >= end {
+            last = index;
+            break;
+        }
+
+        time += frame.duration;
+    }
+
+    let first = first.saturating_sub(LEAD_FRAMES);
+    let lead_start: u64 = frames[..first].iter().map(|frame| frame.duration).sum();
+
+    let mut section = Vec::new();
+    for frame in &frames[first..last] {
+        section.extend_from_slice(&audio[frame.offset..frame.offset + frame.length]);
+    }
+
+    Some(Section {
+        audio: section,
+        lead: start - lead_start,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MPEG-1 layer III at 128 kbps and 44.1 kHz, 417 bytes long.
+    const MPEG1: [u8; 4] = [0xff, 0xfb, 0x90, 0x00];
+
+    /// A frame with a header and silence.
+    fn frame(header: [u8; 4]) -> Vec<u8> {
+        let (length, _) = frame_header(&header).unwrap();
+        let mut frame = header.to_vec();
+        frame.resize(length, 0);
+        frame
+    }
+
+    fn stream(count: usize) -> Vec<u8> {
+        (0..count).flat_map(|_| frame(MPEG1)).collect()
+    }
+
+    #[test]
+    fn reads_frame_headers() {
+        assert_eq!(frame_header(&MPEG1), Some((417, 26_122)));
+        // padded
+        assert_eq!(frame_header(&[0xff, 0xfb, 0x92, 0x00]), Some((418, 26_122)));
+        // MPEG-2 at 64 kbps and 22.05 kHz
+        assert_eq!(frame_header(&[0xff, 0xf3, 0x80, 0x00]), Some((208, 26_122)));
+        // MPEG-2.5 at 32 kbps and 11.025 kHz
+        assert_eq!(frame_header(&[0xff, 0xe3, 0x40, 0x00]), Some((208, 52_244)));
+        // MPEG-1 layer I at 32 kbps and 32 kHz
+        assert_eq!(frame_header(&[0xff, 0xff, 0x18, 0x00]), Some((48, 12_000)));
+
+        // free format, a reserved sample rate and a reserved version
+        assert_eq!(frame_header(&[0xff, 0xfb, 0x00, 0x00]), None);
+        assert_eq!(frame_header(&[0xff, 0xfb, 0x9c, 0x00]), None);
+        assert_eq!(frame_header(&[0xff, 0xeb, 0x90, 0x00]), None);
+        assert_eq!(frame_header(&[0xff, 0xfb, 0x90]), None);
+    }
+
+    #[test]
+    fn skips_xing_frames() {
+        let mut xing = frame(MPEG1);
+        xing[36..40].copy_from_slice(b"Xing");
+        let audio = [xing, stream(3)].concat();
+
+        let frames = frames(&audio);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].offset, 417);
+    }
+
+    #[test]
+    fn skips_id3_tags_with_footers() {
+        // the tag holds something that looks like a frame, and a footer after its size
+        let mut tag = b"ID3\x04\x00\x10\x00\x00\x03\x41".to_vec();
+        let mut body = frame([0xff, 0xfb, 0x18, 0x00]);
+        body.resize(0x1c1, 0);
+        tag.extend(body);
+        tag.extend(b"3DI\x04\x00\x10\x00\x00\x03\x41");
+        let audio = [tag, stream(2)].concat();
+
+        let frames = frames(&audio);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].offset, 10 + 0x1c1 + 10);
+    }
+
+    #[test]
+    fn drops_truncated_frames() {
+        let mut audio = stream(3);
+        audio.truncate(417 * 2 + 100);
+
+        assert_eq!(frames(&audio).len(), 2);
+        assert!(cut(&audio[..3], None, 1000).is_none());
+        assert!(cut(b"ID3\x04\x00\x00\x00\x00\x7f\x7f", None, 1000).is_none());
+    }
+
+    #[test]
+    fn cuts_sections_with_lead_frames() {
+        let audio = stream(100);
+
+        let section = cut(&audio, Some(1000), 500).unwrap();
+        // the section starts in frame 38 and ends before frame 58, with 10 frames ahead of it
+        assert_eq!(section.audio.len(), 30 * 417);
+        assert_eq!(section.lead, 1_000_000 - 28 * 26_122);
+
+        // 40% in by default, and never past the end
+        let section = cut(&audio, None, 500).unwrap();
+        assert_eq!(section.lead, 2_612_200 * 2 / 5 - 30 * 26_122);
+        let section = cut(&audio, Some(60_000), 500).unwrap();
+        assert_eq!(section.lead, 2_112_200 - 70 * 26_122);
+        assert_eq!(section.audio.len(), 30 * 417);
+    }
+}
@@ -0,0 +1,570 @@
+//! Tables for encoding MPEG-1 layer III, from ISO/IEC 11172-3 annexes B and C.
+
+/// A Huffman code table for pairs of big values, indexed by `x * wrap + y`.
+pub(super) struct Table {
+    pub codes: &'static [u32],
+    pub bits: &'static [u8],
+    pub wrap: usize,
+    pub linbits: u32,
+}
+
+impl Table {
+    /// Largest value the table codes, counting the values escaped with linbits.
+    pub fn max_value(&self) -> u32 {
+        match self.linbits {
+            0 => self.wrap as u32 - 1,
+            linbits => 15 + (1 << linbits) - 1,
+        }
+    }
+}
+
+#[rustfmt::skip]
+const CODES_1: [u32; 4] = [
+    1, 1,
+    1, 0,
+];
+
+#[rustfmt::skip]
+const BITS_1: [u8; 4] = [
+    1, 3,
+    2, 3,
+];
+
+#[rustfmt::skip]
+const CODES_2: [u32; 9] = [
+    1, 2, 1,
+    3, 1, 1,
+    3, 2, 0,
+];
+
+#[rustfmt::skip]
+const BITS_2: [u8; 9] = [
+    1, 3, 6,
+    3, 3, 5,
+    5, 5, 6,
+];
+
+#[rustfmt::skip]
+const CODES_3: [u32; 9] = [
+    3, 2, 1,
+    1, 1, 1,
+    3, 2, 0,
+];
+
+#[rustfmt::skip]
+const BITS_3: [u8; 9] = [
+    2, 2, 6,
+    3, 2, 5,
+    5, 5, 6,
+];
+
+#[rustfmt::skip]
+const CODES_5: [u32; 16] = [
+    1, 2, 6, 5,
+    3, 1, 4, 4,
+    7, 5, 7, 1,
+    6, 1, 1, 0,
+];
+
+#[rustfmt::skip]
+const BITS_5: [u8; 16] = [
+    1, 3, 6, 7,
+    3, 3, 6, 7,
+    6, 6, 7, 8,
+    7, 6, 7, 8,
+];
+
+#[rustfmt::skip]
+const CODES_6: [u32; 16] = [
+    7, 3, 5, 1,
+    6, 2, 3, 2,
+    5, 4, 4, 1,
+    3, 3, 2, 0,
+];
+
+#[rustfmt::skip]
+const BITS_6: [u8; 16] = [
+    3, 3, 5, 7,
+    3, 2, 4, 5,
+    4, 4, 5, 6,
+    6, 5, 6, 7,
+];
+
+#[rustfmt::skip]
+const CODES_7: [u32; 36] = [
+    1, 2, 10, 19, 16, 10,
+    3, 3, 7, 10, 5, 3,
+    11, 4, 13, 17, 8, 4,
+    12, 11, 18, 15, 11, 2,
+    7, 6, 9, 14, 3, 1,
+    6, 4, 5, 3, 2, 0,
+];
+
+#[rustfmt::skip]
+const BITS_7: [u8; 36] = [
+    1, 3, 6, 8, 8, 9,
+    3, 4, 6, 7, 7, 8,
+    6, 5, 7, 8, 8, 9,
+    7, 7, 8, 9, 9, 9,
+    7, 7, 8, 9, 9, 10,
+    8, 8, 9, 10, 10, 10,
+];
+
+#[rustfmt::skip]
+const CODES_8: [u32; 36] = [
+    3, 4, 6, 18, 12, 5,
+    5, 1, 2, 16, 9, 3,
+    7, 3, 5, 14, 7, 3,
+    19, 17, 15, 13, 10, 4,
+    13, 5, 8, 11, 5, 1,
+    12, 4, 4, 1, 1, 0,
+];
+
+#[rustfmt::skip]
+const BITS_8: [u8; 36] = [
+    2, 3, 6, 8, 8, 9,
+    3, 2, 4, 8, 8, 8,
+    6, 4, 6, 8, 8, 9,
+    8, 8, 8, 9, 9, 10,
+    8, 7, 8, 9, 10, 10,
+    9, 8, 9, 9, 11, 11,
+];
+
+#[rustfmt::skip]
+const CODES_9: [u32; 36] = [
+    7, 5, 9, 14, 15, 7,
+    6, 4, 5, 5, 6, 7,
+    7, 6, 8, 8, 8, 5,
+    15, 6, 9, 10, 5, 1,
+    11, 7, 9, 6, 4, 1,
+    14, 4, 6, 2, 6, 0,
+];
+
+#[rustfmt::skip]
+const BITS_9: [u8; 36] = [
+    3, 3, 5, 6, 8, 9,
+    3, 3, 4, 5, 6, 8,
+    4, 4, 5, 6, 7, 8,
+    6, 5, 6, 7, 7, 8,
+    7, 6, 7, 7, 8, 9,
+    8, 7, 8, 8, 9, 9,
+];
+
+#[rustfmt::skip]
+const CODES_10: [u32; 64] = [
+    1, 2, 10, 23, 35, 30, 12, 17,
+    3, 3, 8, 12, 18, 21, 12, 7,
+    11, 9, 15, 21, 32, 40, 19, 6,
+    14, 13, 22, 34, 46, 23, 18, 7,
+    20, 19, 33, 47, 27, 22, 9, 3,
+    31, 22, 41, 26, 21, 20, 5, 3,
+    14, 13, 10, 11, 16, 6, 5, 1,
+    9, 8, 7, 8, 4, 4, 2, 0,
+];
+
+#[rustfmt::skip]
+const BITS_10: [u8; 64] = [
+    1, 3, 6, 8, 9, 9, 9, 10,
+    3, 4, 6, 7, 8, 9, 8, 8,
+    6, 6, 7, 8, 9, 10, 9, 9,
+    7, 7, 8, 9, 10, 10, 9, 10,
+    8, 8, 9, 10, 10, 10, 10, 10,
+    9, 9, 10, 10, 11, 11, 10, 11,
+    8, 8, 9, 10, 10, 10, 11, 11,
+    9, 8, 9, 10, 10, 11, 11, 11,
+];
+
+#[rustfmt::skip]
+const CODES_11: [u32; 64] = [
+    3, 4, 10, 24, 34, 33, 21, 15,
+    5, 3, 4, 10, 32, 17, 11, 10,
+    11, 7, 13, 18, 30, 31, 20, 5,
+    25, 11, 19, 59, 27, 18, 12, 5,
+    35, 33, 31, 58, 30, 16, 7, 5,
+    28, 26, 32, 19, 17, 15, 8, 14,
+    14, 12, 9, 13, 14, 9, 4, 1,
+    11, 4, 6, 6, 6, 3, 2, 0,
+];
+
+#[rustfmt::skip]
+const BITS_11: [u8; 64] = [
+    2, 3, 5, 7, 8, 9, 8, 9,
+    3, 3, 4, 6, 8, 8, 7, 8,
+    5, 5, 6, 7, 8, 9, 8, 8,
+    7, 6, 7, 9, 8, 10, 8, 9,
+    8, 8, 8, 9, 9, 10, 9, 10,
+    8, 8, 9, 10, 10, 11, 10, 11,
+    8, 7, 7, 8, 9, 10, 10, 10,
+    8, 7, 8, 9, 10, 10, 10, 10,
+];
+
+#[rustfmt::skip]
+const CODES_12: [u32; 64] = [
+    9, 6, 16, 33, 41, 39, 38, 26,
+    7, 5, 6, 9, 23, 16, 26, 11,
+    17, 7, 11, 14, 21, 30, 10, 7,
+    17, 10, 15, 12, 18, 28, 14, 5,
+    32, 13, 22, 19, 18, 16, 9, 5,
+    40, 17, 31, 29, 17, 13, 4, 2,
+    27, 12, 11, 15, 10, 7, 4, 1,
+    27, 12, 8, 12, 6, 3, 1, 0,
+];
+
+#[rustfmt::skip]
+const BITS_12: [u8; 64] = [
+    4, 3, 5, 7, 8, 9, 9, 9,
+    3, 3, 4, 5, 7, 7, 8, 8,
+    5, 4, 5, 6, 7, 8, 7, 8,
+    6, 5, 6, 6, 7, 8, 8, 8,
+    7, 6, 7, 7, 8, 8, 8, 9,
+    8, 7, 8, 8, 8, 9, 8, 9,
+    8, 7, 7, 8, 8, 9, 9, 10,
+    9, 8, 8, 9, 9, 9, 9, 10,
+];
+
+#[rustfmt::skip]
+const CODES_13: [u32; 256] = [
+    1, 5, 14, 21, 34, 51, 46, 71, 42, 52, 68, 52, 67, 44, 43, 19,
+    3, 4, 12, 19, 31, 26, 44, 33, 31, 24, 32, 24, 31, 35, 22, 14,
+    15, 13, 23, 36, 59, 49, 77, 65, 29, 40, 30, 40, 27, 33, 42, 16,
+    22, 20, 37, 61, 56, 79, 73, 64, 43, 76, 56, 37, 26, 31, 25, 14,
+    35, 16, 60, 57, 97, 75, 114, 91, 54, 73, 55, 41, 48, 53, 23, 24,
+    58, 27, 50, 96, 76, 70, 93, 84, 77, 58, 79, 29, 74, 49, 41, 17,
+    47, 45, 78, 74, 115, 94, 90, 79, 69, 83, 71, 50, 59, 38, 36, 15,
+    72, 34, 56, 95, 92, 85, 91, 90, 86, 73, 77, 65, 51, 44, 43, 42,
+    43, 20, 30, 44, 55, 78, 72, 87, 78, 61, 46, 54, 37, 30, 20, 16,
+    53, 25, 41, 37, 44, 59, 54, 81, 66, 76, 57, 54, 37, 18, 39, 11,
+    35, 33, 31, 57, 42, 82, 72, 80, 47, 58, 55, 21, 22, 26, 38, 22,
+    53, 25, 23, 38, 70, 60, 51, 36, 55, 26, 34, 23, 27, 14, 9, 7,
+    34, 32, 28, 39, 49, 75, 30, 52, 48, 40, 52, 28, 18, 17, 9, 5,
+    45, 21, 34, 64, 56, 50, 49, 45, 31, 19, 12, 15, 10, 7, 6, 3,
+    48, 23, 20, 39, 36, 35, 53, 21, 16, 23, 13, 10, 6, 1, 4, 2,
+    16, 15, 17, 27, 25, 20, 29, 11, 17, 12, 16, 8, 1, 1, 0, 1,
+];
+
+#[rustfmt::skip]
+const BITS_13: [u8; 256] = [
+    1, 4, 6, 7, 8, 9, 9, 10, 9, 10, 11, 11, 12, 12, 13, 13,
+    3, 4, 6, 7, 8, 8, 9, 9, 9, 9, 10, 10, 11, 12, 12, 12,
+    6, 6, 7, 8, 9, 9, 10, 10, 9, 10, 10, 11, 11, 12, 13, 13,
+    7, 7, 8, 9, 9, 10, 10, 10, 10, 11, 11, 11, 11, 12, 13, 13,
+    8, 7, 9, 9, 10, 10, 11, 11, 10, 11, 11, 12, 12, 13, 13, 14,
+    9, 8, 9, 10, 10, 10, 11, 11, 11, 11, 12, 11, 13, 13, 14, 14,
+    9, 9, 10, 10, 11, 11, 11, 11, 11, 12, 12, 12, 13, 13, 14, 14,
+    10, 9, 10, 11, 11, 11, 12, 12, 12, 12, 13, 13, 13, 14, 16, 16,
+    9, 8, 9, 10, 10, 11, 11, 12, 12, 12, 12, 13, 13, 14, 15, 15,
+    10, 9, 10, 10, 11, 11, 11, 13, 12, 13, 13, 14, 14, 14, 16, 15,
+    10, 10, 10, 11, 11, 12, 12, 13, 12, 13, 14, 13, 14, 15, 16, 17,
+    11, 10, 10, 11, 12, 12, 12, 12, 13, 13, 13, 14, 15, 15, 15, 16,
+    11, 11, 11, 12, 12, 13, 12, 13, 14, 14, 15, 15, 15, 16, 16, 16,
+    12, 11, 12, 13, 13, 13, 14, 14, 14, 14, 14, 15, 16, 15, 16, 16,
+    13, 12, 12, 13, 13, 13, 15, 14, 14, 17, 15, 15, 15, 17, 16, 16,
+    12, 12, 13, 14, 14, 14, 15, 14, 15, 15, 16, 16, 19, 18, 19, 16,
+];
+
+#[rustfmt::skip]
+const CODES_15: [u32; 256] = [
+    7, 12, 18, 53, 47, 76, 124, 108, 89, 123, 108, 119, 107, 81, 122, 63,
+    13, 5, 16, 27, 46, 36, 61, 51, 42, 70, 52, 83, 65, 41, 59, 36,
+    19, 17, 15, 24, 41, 34, 59, 48, 40, 64, 50, 78, 62, 80, 56, 33,
+    29, 28, 25, 43, 39, 63, 55, 93, 76, 59, 93, 72, 54, 75, 50, 29,
+    52, 22, 42, 40, 67, 57, 95, 79, 72, 57, 89, 69, 49, 66, 46, 27,
+    77, 37, 35, 66, 58, 52, 91, 74, 62, 48, 79, 63, 90, 62, 40, 38,
+    125, 32, 60, 56, 50, 92, 78, 65, 55, 87, 71, 51, 73, 51, 70, 30,
+    109, 53, 49, 94, 88, 75, 66, 122, 91, 73, 56, 42, 64, 44, 21, 25,
+    90, 43, 41, 77, 73, 63, 56, 92, 77, 66, 47, 67, 48, 53, 36, 20,
+    71, 34, 67, 60, 58, 49, 88, 76, 67, 106, 71, 54, 38, 39, 23, 15,
+    109, 53, 51, 47, 90, 82, 58, 57, 48, 72, 57, 41, 23, 27, 62, 9,
+    86, 42, 40, 37, 70, 64, 52, 43, 70, 55, 42, 25, 29, 18, 11, 11,
+    118, 68, 30, 55, 50, 46, 74, 65, 49, 39, 24, 16, 22, 13, 14, 7,
+    91, 44, 39, 38, 34, 63, 52, 45, 31, 52, 28, 19, 14, 8, 9, 3,
+    123, 60, 58, 53, 47, 43, 32, 22, 37, 24, 17, 12, 15, 10, 2, 1,
+    71, 37, 34, 30, 28, 20, 17, 26, 21, 16, 10, 6, 8, 6, 2, 0,
+];
+
+#[rustfmt::skip]
+const BITS_15: [u8; 256] = [
+    3, 4, 5, 7, 7, 8, 9, 9, 9, 10, 10, 11, 11, 11, 12, 13,
+    4, 3, 5, 6, 7, 7, 8, 8, 8, 9, 9, 10, 10, 10, 11, 11,
+    5, 5, 5, 6, 7, 7, 8, 8, 8, 9, 9, 10, 10, 11, 11, 11,
+    6, 6, 6, 7, 7, 8, 8, 9, 9, 9, 10, 10, 10, 11, 11, 11,
+    7, 6, 7, 7, 8, 8, 9, 9, 9, 9, 10, 10, 10, 11, 11, 11,
+    8, 7, 7, 8, 8, 8, 9, 9, 9, 9, 10, 10, 11, 11, 11, 12,
+    9, 7, 8, 8, 8, 9, 9, 9, 9, 10, 10, 10, 11, 11, 12, 12,
+    9, 8, 8, 9, 9, 9, 9, 10, 10, 10, 10, 10, 11, 11, 11, 12,
+    9, 8, 8, 9, 9, 9, 9, 10, 10, 10, 10, 11, 11, 12, 12, 12,
+    9, 8, 9, 9, 9, 9, 10, 10, 10, 11, 11, 11, 11, 12, 12, 12,
+    10, 9, 9, 9, 10, 10, 10, 10, 10, 11, 11, 11, 11, 12, 13, 12,
+    10, 9, 9, 9, 10, 10, 10, 10, 11, 11, 11, 11, 12, 12, 12, 13,
+    11, 10, 9, 10, 10, 10, 11, 11, 11, 11, 11, 11, 12, 12, 13, 13,
+    11, 10, 10, 10, 10, 11, 11, 11, 11, 12, 12, 12, 12, 12, 13, 13,
+    12, 11, 11, 11, 11, 11, 11, 11, 12, 12, 12, 12, 13, 13, 12, 13,
+    12, 11, 11, 11, 11, 11, 11, 12, 12, 12, 12, 12, 13, 13, 13, 13,
+];
+
+#[rustfmt::skip]
+const CODES_16: [u32; 256] = [
+    1, 5, 14, 44, 74, 63, 110, 93, 172, 149, 138, 242, 225, 195, 376, 17,
+    3, 4, 12, 20, 35, 62, 53, 47, 83, 75, 68, 119, 201, 107, 207, 9,
+    15, 13, 23, 38, 67, 58, 103, 90, 161, 72, 127, 117, 110, 209, 206, 16,
+    45, 21, 39, 69, 64, 114, 99, 87, 158, 140, 252, 212, 199, 387, 365, 26,
+    75, 36, 68, 65, 115, 101, 179, 164, 155, 264, 246, 226, 395, 382, 362, 9,
+    66, 30, 59, 56, 102, 185, 173, 265, 142, 253, 232, 400, 388, 378, 445, 16,
+    111, 54, 52, 100, 184, 178, 160, 133, 257, 244, 228, 217, 385, 366, 715, 10,
+    98, 48, 91, 88, 165, 157, 148, 261, 248, 407, 397, 372, 380, 889, 884, 8,
+    85, 84, 81, 159, 156, 143, 260, 249, 427, 401, 392, 383, 727, 713, 708, 7,
+    154, 76, 73, 141, 131, 256, 245, 426, 406, 394, 384, 735, 359, 710, 352, 11,
+    139, 129, 67, 125, 247, 233, 229, 219, 393, 743, 737, 720, 885, 882, 439, 4,
+    243, 120, 118, 115, 227, 223, 396, 746, 742, 736, 721, 712, 706, 223, 436, 6,
+    202, 224, 222, 218, 216, 389, 386, 381, 364, 888, 443, 707, 440, 437, 1728, 4,
+    747, 211, 210, 208, 370, 379, 734, 723, 714, 1735, 883, 877, 876, 3459, 865, 2,
+    377, 369, 102, 187, 726, 722, 358, 711, 709, 866, 1734, 871, 3458, 870, 434, 0,
+    12, 10, 7, 11, 10, 17, 11, 9, 13, 12, 10, 7, 5, 3, 1, 3,
+];
+
+#[rustfmt::skip]
+const BITS_16: [u8; 256] = [
+    1, 4, 6, 8, 9, 9, 10, 10, 11, 11, 11, 12, 12, 12, 13, 9,
+    3, 4, 6, 7, 8, 9, 9, 9, 10, 10, 10, 11, 12, 11, 12, 8,
+    6, 6, 7, 8, 9, 9, 10, 10, 11, 10, 11, 11, 11, 12, 12, 9,
+    8, 7, 8, 9, 9, 10, 10, 10, 11, 11, 12, 12, 12, 13, 13, 10,
+    9, 8, 9, 9, 10, 10, 11, 11, 11, 12, 12, 12, 13, 13, 13, 9,
+    9, 8, 9, 9, 10, 11, 11, 12, 11, 12, 12, 13, 13, 13, 14, 10,
+    10, 9, 9, 10, 11, 11, 11, 11, 12, 12, 12, 12, 13, 13, 14, 10,
+    10, 9, 10, 10, 11, 11, 11, 12, 12, 13, 13, 13, 13, 15, 15, 10,
+    10, 10, 10, 11, 11, 11, 12, 12, 13, 13, 13, 13, 14, 14, 14, 10,
+    11, 10, 10, 11, 11, 12, 12, 13, 13, 13, 13, 14, 13, 14, 13, 11,
+    11, 11, 10, 11, 12, 12, 12, 12, 13, 14, 14, 14, 15, 15, 14, 10,
+    12, 11, 11, 11, 12, 12, 13, 14, 14, 14, 14, 14, 14, 13, 14, 11,
+    12, 12, 12, 12, 12, 13, 13, 13, 13, 15, 14, 14, 14, 14, 16, 11,
+    14, 12, 12, 12, 13, 13, 14, 14, 14, 16, 15, 15, 15, 17, 15, 11,
+    13, 13, 11, 12, 14, 14, 13, 14, 14, 15, 16, 15, 17, 15, 14, 11,
+    9, 8, 8, 9, 9, 10, 10, 10, 11, 11, 11, 11, 11, 11, 11, 8,
+];
+
+#[rustfmt::skip]
+const CODES_24: [u32; 256] = [
+    15, 13, 46, 80, 146, 262, 248, 434, 426, 669, 653, 649, 621, 517, 1032, 88,
+    14, 12, 21, 38, 71, 130, 122, 216, 209, 198, 327, 345, 319, 297, 279, 42,
+    47, 22, 41, 74, 68, 128, 120, 221, 207, 194, 182, 340, 315, 295, 541, 18,
+    81, 39, 75, 70, 134, 125, 116, 220, 204, 190, 178, 325, 311, 293, 271, 16,
+    147, 72, 69, 135, 127, 118, 112, 210, 200, 188, 352, 323, 306, 285, 540, 14,
+    263, 66, 129, 126, 119, 114, 214, 202, 192, 180, 341, 317, 301, 281, 262, 12,
+    249, 123, 121, 117, 113, 215, 206, 195, 185, 347, 330, 308, 291, 272, 520, 10,
+    435, 115, 111, 109, 211, 203, 196, 187, 353, 332, 313, 298, 283, 531, 381, 17,
+    427, 212, 208, 205, 201, 193, 186, 177, 169, 320, 303, 286, 268, 514, 377, 16,
+    335, 199, 197, 191, 189, 181, 174, 333, 321, 305, 289, 275, 521, 379, 371, 11,
+    668, 184, 183, 179, 175, 344, 331, 314, 304, 290, 277, 530, 383, 373, 366, 10,
+    652, 346, 171, 168, 164, 318, 309, 299, 287, 276, 263, 513, 375, 368, 362, 6,
+    648, 322, 316, 312, 307, 302, 292, 284, 269, 261, 512, 376, 370, 364, 359, 4,
+    620, 300, 296, 294, 288, 282, 273, 266, 515, 380, 374, 369, 365, 361, 357, 2,
+    1033, 280, 278, 274, 267, 264, 259, 382, 378, 372, 367, 363, 360, 358, 356, 0,
+    43, 20, 19, 17, 15, 13, 11, 9, 7, 6, 4, 7, 5, 3, 1, 3,
+];
+
+#[rustfmt::skip]
+const BITS_24: [u8; 256] = [
+    4, 4, 6, 7, 8, 9, 9, 10, 10, 11, 11, 11, 11, 11, 12, 9,
+    4, 4, 5, 6, 7, 8, 8, 9, 9, 9, 10, 10, 10, 10, 10, 8,
+    6, 5, 6, 7, 7, 8, 8, 9, 9, 9, 9, 10, 10, 10, 11, 7,
+    7, 6, 7, 7, 8, 8, 8, 9, 9, 9, 9, 10, 10, 10, 10, 7,
+    8, 7, 7, 8, 8, 8, 8, 9, 9, 9, 10, 10, 10, 10, 11, 7,
+    9, 7, 8, 8, 8, 8, 9, 9, 9, 9, 10, 10, 10, 10, 10, 7,
+    9, 8, 8, 8, 8, 9, 9, 9, 9, 10, 10, 10, 10, 10, 11, 7,
+    10, 8, 8, 8, 9, 9, 9, 9, 10, 10, 10, 10, 10, 11, 11, 8,
+    10, 9, 9, 9, 9, 9, 9, 9, 9, 10, 10, 10, 10, 11, 11, 8,
+    10, 9, 9, 9, 9, 9, 9, 10, 10, 10, 10, 10, 11, 11, 11, 8,
+    11, 9, 9, 9, 9, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 8,
+    11, 10, 9, 9, 9, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 8,
+    11, 10, 10, 10, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11, 8,
+    11, 10, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11, 11, 11, 8,
+    12, 10, 10, 10, 10, 10, 10, 11, 11, 11, 11, 11, 11, 11, 11, 8,
+    8, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 8, 8, 8, 8, 4,
+];
+
+/// Big value tables by table number, where tables 0, 4 and 14 aren't used.
+#[rustfmt::skip]
+pub(super) const TABLES: [Option<Table>; 32] = [
+    None,
+    Some(Table { codes: &CODES_1, bits: &BITS_1, wrap: 2, linbits: 0 }),
+    Some(Table { codes: &CODES_2, bits: &BITS_2, wrap: 3, linbits: 0 }),
+    Some(Table { codes: &CODES_3, bits: &BITS_3, wrap: 3, linbits: 0 }),
+    None,
+    Some(Table { codes: &CODES_5, bits: &BITS_5, wrap: 4, linbits: 0 }),
+    Some(Table { codes: &CODES_6, bits: &BITS_6, wrap: 4, linbits: 0 }),
+    Some(Table { codes: &CODES_7, bits: &BITS_7, wrap: 6, linbits: 0 }),
+    Some(Table { codes: &CODES_8, bits: &BITS_8, wrap: 6, linbits: 0 }),
+    Some(Table { codes: &CODES_9, bits: &BITS_9, wrap: 6, linbits: 0 }),
+    Some(Table { codes: &CODES_10, bits: &BITS_10, wrap: 8, linbits: 0 }),
+    Some(Table { codes: &CODES_11, bits: &BITS_11, wrap: 8, linbits: 0 }),
+    Some(Table { codes: &CODES_12, bits: &BITS_12, wrap: 8, linbits: 0 }),
+    Some(Table { codes: &CODES_13, bits: &BITS_13, wrap: 16, linbits: 0 }),
+    None,
+    Some(Table { codes: &CODES_15, bits: &BITS_15, wrap: 16, linbits: 0 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 1 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 2 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 3 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 4 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 6 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 8 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 10 }),
+    Some(Table { codes: &CODES_16, bits: &BITS_16, wrap: 16, linbits: 13 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 4 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 5 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 6 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 7 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 8 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 9 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 11 }),
+    Some(Table { codes: &CODES_24, bits: &BITS_24, wrap: 16, linbits: 13 }),
+];
+
+/// Count1 quadruple tables A and B, indexed by `8v + 4w + 2x + y`.
+pub(super) const QUAD_CODES: [[u32; 16]; 2] = [
+    [1, 5, 4, 5, 6, 5, 4, 4, 7, 3, 6, 0, 7, 2, 3, 1],
+    [15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+];
+
+pub(super) const QUAD_BITS: [[u8; 16]; 2] = [
+    [1, 4, 4, 5, 4, 6, 5, 6, 4, 5, 5, 6, 5, 6, 6, 6],
+    [4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4],
+];
+
+/// Scalefactor band boundaries of long blocks at 32 kHz.
+pub(super) const SCALEFACTOR_BANDS: [usize; 23] = [
+    0, 4, 8, 12, 16, 20, 24, 30, 36, 44, 54, 66, 82, 102, 126, 156, 194, 240, 296, 364, 448, 550,
+    576,
+];
+
+/// Analysis window of the polyphase filterbank (table C.1), the synthesis window divided by 32.
+#[rustfmt::skip]
+pub(super) const ANALYSIS_WINDOW: [f32; 512] = [
+    0.0, -4.76844e-7, -4.76844e-7, -4.76844e-7,
+    -4.76844e-7, -4.76844e-7, -4.76844e-7, -9.53688e-7,
+    -9.53688e-7, -9.53688e-7, -9.53688e-7, -1.4305e-6,
+    -1.4305e-6, -1.907344e-6, -1.907344e-6, -2.384187e-6,
+    -2.384187e-6, -2.861031e-6, -3.337875e-6, -3.337875e-6,
+    -3.814688e-6, -4.291531e-6, -4.768375e-6, -5.245219e-6,
+    -6.198875e-6, -6.675719e-6, -7.629406e-6, -8.106219e-6,
+    -9.059906e-6, -1.0013594e-5, -1.1444094e-5, -1.2397781e-5,
+    -1.3828281e-5, -1.4781938e-5, -1.6689313e-5, -1.8119812e-5,
+    -1.9550313e-5, -2.1457687e-5, -2.3365032e-5, -2.5272375e-5,
+    -2.7656562e-5, -3.004075e-5, -3.2424938e-5, -3.4809123e-5,
+    -3.7670125e-5, -4.0531155e-5, -4.339219e-5, -4.625322e-5,
+    -4.9591064e-5, -5.2928935e-5, -5.5789937e-5, -5.9604656e-5,
+    -6.29425e-5, -6.628037e-5, -7.009506e-5, -7.343294e-5,
+    -7.677078e-5, -8.058547e-5, -8.392335e-5, -8.7261185e-5,
+    -9.059906e-5, -9.34601e-5, -9.632109e-5, -9.918212e-5,
+    1.01566315e-4, 1.039505e-4, 1.0585784e-4, 1.07288375e-4,
+    1.0824203e-4, 1.0871887e-4, 1.0871887e-4, 1.0824203e-4,
+    1.0681153e-4, 1.05381e-4, 1.0252e-4, 9.918212e-5,
+    9.536744e-5, 9.0122216e-5, 8.440019e-5, 7.772447e-5,
+    6.961822e-5, 6.055831e-5, 5.054475e-5, 3.957747e-5,
+    2.717972e-5, 1.3828281e-5, -9.53688e-7, -1.7166125e-5,
+    -3.4332283e-5, -5.2928935e-5, -7.295609e-5, -9.3936906e-5,
+    -1.1634828e-4, -1.4019012e-4, -1.654625e-4, -1.9121169e-4,
+    -2.1886826e-4, -2.474785e-4, -2.770424e-4, -3.0755997e-4,
+    -3.3903122e-4, -3.7145615e-4, -4.043579e-4, -4.3821335e-4,
+    -4.7254562e-4, -5.0735474e-4, -5.4216385e-4, -5.7697296e-4,
+    -6.117821e-4, -6.465912e-4, -6.8092346e-4, -7.1430206e-4,
+    -7.472038e-4, -7.791519e-4, -8.096695e-4, -8.3875656e-4,
+    -8.664131e-4, -8.916855e-4, -9.150505e-4, -9.355545e-4,
+    -9.5415115e-4, -9.689331e-4, -9.80854e-4, -9.894371e-4,
+    -9.942055e-4, -9.951591e-4, -9.918213e-4, -9.83715e-4,
+    9.713173e-4, 9.536743e-4, 9.3078613e-4, 9.0265274e-4,
+    8.687973e-4, 8.292198e-4, 7.839203e-4, 7.3194504e-4,
+    6.7424774e-4, 6.1035156e-4, 5.393028e-4, 4.6253204e-4,
+    3.7860873e-4, 2.8848648e-4, 1.9168854e-4, 8.8214874e-5,
+    -2.1457687e-5, -1.3732909e-4, -2.5987625e-4, -3.8814545e-4,
+    -5.221367e-4, -6.6185e-4, -8.068085e-4, -9.5653534e-4,
+    -1.1110306e-3, -1.2698174e-3, -1.4324188e-3, -1.5978813e-3,
+    -1.7666817e-3, -1.9373894e-3, -2.1100044e-3, -2.2830963e-3,
+    -2.4571419e-3, -2.6307106e-3, -2.8033257e-3, -2.9740334e-3,
+    -3.14188e-3, -3.3068657e-3, -3.467083e-3, -3.622532e-3,
+    -3.771782e-3, -3.914356e-3, -4.0488243e-3, -4.1747093e-3,
+    -4.2905807e-3, -4.3959618e-3, -4.4898987e-3, -4.570484e-3,
+    -4.638195e-3, -4.691124e-3, -4.7283173e-3, -4.7488213e-3,
+    -4.752159e-3, -4.737377e-3, -4.703045e-3, -4.6491623e-3,
+    -4.573822e-3, -4.477024e-3, -4.357815e-3, -4.2152405e-3,
+    -4.049301e-3, -3.8585663e-3, -3.643036e-3, -3.4017563e-3,
+    3.1347275e-3, 2.8414726e-3, 2.521515e-3, 2.1748543e-3,
+    1.8005371e-3, 1.3995171e-3, 9.713173e-4, 5.159378e-4,
+    3.3378594e-5, -4.7588345e-4, -1.0118484e-3, -1.5735626e-3,
+    -2.1615028e-3, -2.7742386e-3, -3.411293e-3, -4.0721893e-3,
+    -4.7564507e-3, -5.4621696e-3, -6.1893463e-3, -6.937027e-3,
+    -7.7033043e-3, -8.487225e-3, -9.287834e-3, -1.0103703e-2,
+    -1.0933399e-2, -1.1775017e-2, -1.2627602e-2, -1.3489246e-2,
+    -1.43585205e-2, -1.5233517e-2, -1.6112804e-2, -1.6994476e-2,
+    -1.7876148e-2, -1.8756866e-2, -1.9634247e-2, -2.0506859e-2,
+    -2.1372318e-2, -2.2228718e-2, -2.307415e-2, -2.3907185e-2,
+    -2.4725437e-2, -2.5527e-2, -2.631092e-2, -2.707386e-2,
+    -2.7815342e-2, -2.8532982e-2, -2.9224873e-2, -2.989006e-2,
+    -3.0526638e-2, -3.1132698e-2, -3.170681e-2, -3.224802e-2,
+    -3.2754898e-2, -3.3225536e-2, -3.3659935e-2, -3.405571e-2,
+    -3.441286e-2, -3.4730434e-2, -3.5007e-2, -3.524208e-2,
+    -3.54352e-2, -3.5586357e-2, -3.5694122e-2, -3.5758972e-2,
+    3.5780907e-2, 3.5758972e-2, 3.5694122e-2, 3.5586357e-2,
+    3.54352e-2, 3.524208e-2, 3.5007e-2, 3.4730434e-2,
+    3.441286e-2, 3.405571e-2, 3.3659935e-2, 3.3225536e-2,
+    3.2754898e-2, 3.224802e-2, 3.170681e-2, 3.1132698e-2,
+    3.0526638e-2, 2.989006e-2, 2.9224873e-2, 2.8532982e-2,
+    2.7815342e-2, 2.707386e-2, 2.631092e-2, 2.5527e-2,
+    2.4725437e-2, 2.3907185e-2, 2.307415e-2, 2.2228718e-2,
+    2.1372318e-2, 2.0506859e-2, 1.9634247e-2, 1.8756866e-2,
+    1.7876148e-2, 1.6994476e-2, 1.6112804e-2, 1.5233517e-2,
+    1.43585205e-2, 1.3489246e-2, 1.2627602e-2, 1.1775017e-2,
+    1.0933399e-2, 1.0103703e-2, 9.287834e-3, 8.487225e-3,
+    7.7033043e-3, 6.937027e-3, 6.1893463e-3, 5.4621696e-3,
+    4.7564507e-3, 4.0721893e-3, 3.411293e-3, 2.7742386e-3,
+    2.1615028e-3, 1.5735626e-3, 1.0118484e-3, 4.7588345e-4,
+    -3.3378594e-5, -5.159378e-4, -9.713173e-4, -1.3995171e-3,
+    -1.8005371e-3, -2.1748543e-3, -2.521515e-3, -2.8414726e-3,
+    3.1347275e-3, 3.4017563e-3, 3.643036e-3, 3.8585663e-3,
+    4.049301e-3, 4.2152405e-3, 4.357815e-3, 4.477024e-3,
+    4.573822e-3, 4.6491623e-3, 4.703045e-3, 4.737377e-3,
+    4.752159e-3, 4.7488213e-3, 4.7283173e-3, 4.691124e-3,
+    4.638195e-3, 4.570484e-3, 4.4898987e-3, 4.3959618e-3,
+    4.2905807e-3, 4.1747093e-3, 4.0488243e-3, 3.914356e-3,
+    3.771782e-3, 3.622532e-3, 3.467083e-3, 3.3068657e-3,
+    3.14188e-3, 2.9740334e-3, 2.8033257e-3, 2.6307106e-3,
+    2.4571419e-3, 2.2830963e-3, 2.1100044e-3, 1.9373894e-3,
+    1.7666817e-3, 1.5978813e-3, 1.4324188e-3, 1.2698174e-3,
+    1.1110306e-3, 9.5653534e-4, 8.068085e-4, 6.6185e-4,
+    5.221367e-4, 3.8814545e-4, 2.5987625e-4, 1.3732909e-4,
+    2.1457687e-5, -8.8214874e-5, -1.9168854e-4, -2.8848648e-4,
+    -3.7860873e-4, -4.6253204e-4, -5.393028e-4, -6.1035156e-4,
+    -6.7424774e-4, -7.3194504e-4, -7.839203e-4, -8.292198e-4,
+    -8.687973e-4, -9.0265274e-4, -9.3078613e-4, -9.536743e-4,
+    9.713173e-4, 9.83715e-4, 9.918213e-4, 9.951591e-4,
+    9.942055e-4, 9.894371e-4, 9.80854e-4, 9.689331e-4,
+    9.5415115e-4, 9.355545e-4, 9.150505e-4, 8.916855e-4,
+    8.664131e-4, 8.3875656e-4, 8.096695e-4, 7.791519e-4,
+    7.472038e-4, 7.1430206e-4, 6.8092346e-4, 6.465912e-4,
+    6.117821e-4, 5.7697296e-4, 5.4216385e-4, 5.0735474e-4,
+    4.7254562e-4, 4.3821335e-4, 4.043579e-4, 3.7145615e-4,
+    3.3903122e-4, 3.0755997e-4, 2.770424e-4, 2.474785e-4,
+    2.1886826e-4, 1.9121169e-4, 1.654625e-4, 1.4019012e-4,
+    1.1634828e-4, 9.3936906e-5, 7.295609e-5, 5.2928935e-5,
+    3.4332283e-5, 1.7166125e-5, 9.53688e-7, -1.3828281e-5,
+    -2.717972e-5, -3.957747e-5, -5.054475e-5, -6.055831e-5,
+    -6.961822e-5, -7.772447e-5, -8.440019e-5, -9.0122216e-5,
+    -9.536744e-5, -9.918212e-5, -1.0252e-4, -1.05381e-4,
+    -1.0681153e-4, -1.0824203e-4, -1.0871887e-4, -1.0871887e-4,
+    -1.0824203e-4, -1.07288375e-4, -1.0585784e-4, -1.039505e-4,
+    1.01566315e-4, 9.918212e-5, 9.632109e-5, 9.34601e-5,
+    9.059906e-5, 8.7261185e-5, 8.392335e-5, 8.058547e-5,
+    7.677078e-5, 7.343294e-5, 7.009506e-5, 6.628037e-5,
+    6.29425e-5, 5.9604656e-5, 5.5789937e-5, 5.2928935e-5,
+    4.9591064e-5, 4.625322e-5, 4.339219e-5, 4.0531155e-5,
+    3.7670125e-5, 3.4809123e-5, 3.2424938e-5, 3.004075e-5,
+    2.7656562e-5, 2.5272375e-5, 2.3365032e-5, 2.1457687e-5,
+    1.9550313e-5, 1.8119812e-5, 1.6689313e-5, 1.4781938e-5,
+    1.3828281e-5, 1.2397781e-5, 1.1444094e-5, 1.0013594e-5,
+    9.059906e-6, 8.106219e-6, 7.629406e-6, 6.675719e-6,
+    6.198875e-6, 5.245219e-6, 4.768375e-6, 4.291531e-6,
+    3.814688e-6, 3.337875e-6, 3.337875e-6, 2.861031e-6,
+    2.384187e-6, 2.384187e-6, 1.907344e-6, 1.907344e-6,
+    1.4305e-6, 1.4305e-6, 9.53688e-7, 9.53688e-7,
+    9.53688e-7, 9.53688e-7, 4.76844e-7, 4.76844e-7,
+    4.76844e-7, 4.76844e-7, 4.76844e-7, 4.76844e-7,
+];
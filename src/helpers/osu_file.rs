@@ -0,0 +1,314 @@
+use crate::models::parsed_beatmap::{BpmChange, ObjectSection, ParsedBeatmap};
+
+/// Length of the sections hit objects are counted in, in milliseconds.
+const OBJECT_SECTION_LENGTH: i32 = 10_000;
+
+const CIRCLE: u32 = 1;
+const SLIDER: u32 = 2;
+const SPINNER: u32 = 8;
+const HOLD: u32 = 128;
+
+/// A `.osu` file, with the ids it names and its parsed metadata.
+pub struct OsuFile {
+    pub beatmap_id: Option<u32>,
+    pub version: Option<String>,
+    pub parsed: ParsedBeatmap,
+}
+
+/// Strip the quotes file names in the events section are usually wrapped in.
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches('"').to_string()
+}
+
+/// Parse a `.osu` file. Lines which can't be understood are skipped, as osu! does.
+pub fn parse(content: &str) -> OsuFile {
+    let mut beatmap_id = None;
+    let mut version = None;
+    let mut parsed = ParsedBeatmap {
+        // osu! shows a normal countdown unless told otherwise
+        countdown: 1,
+        ..Default::default()
+    };
+    let mut sections: Vec<ObjectSection> = Vec::new();
+    let mut section = "";
+
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            section = &line[1..line.len() - 1];
+            continue;
+        }
+
+        match section {
+            "General" | "Metadata" => {
+                let (key, value) = match line.split_once(':') {
+                    Some((key, value)) => (key.trim(), value.trim()),
+                    None => continue,
+                };
+
+                match key {
+                    "AudioFilename" => parsed.audio_filename = Some(value.to_string()),
+                    "PreviewTime" => {
+                        parsed.preview_time = value.parse().ok().filter(|time| *time >= 0)
+                    }
+                    "Countdown" => parsed.countdown = value.parse().unwrap_or(1),
+                    "EpilepsyWarning" => parsed.epilepsy_warning = value == "1",
+                    "LetterboxInBreaks" => parsed.letterbox_in_breaks = value == "1",
+                    "BeatmapID" => beatmap_id = value.parse().ok().filter(|id| *id > 0),
+                    "Version" => version = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            "Events" => {
+                let fields: Vec<&str> = line.split(',').collect();
+
+                match fields[0] {
+                    "0" | "Background" if fields.len() > 2 => {
+                        parsed.background_filename = Some(unquote(fields[2]));
+                    }
+                    "Sprite" | "Animation" | "4" | "6" => parsed.storyboard = true,
+                    _ => {}
+                }
+            }
+            "TimingPoints" => {
+                let fields: Vec<&str> = line.split(',').collect();
+                if fields.len() < 2 {
+                    continue;
+                }
+
+                let (time, beat_length) = match (
+                    fields[0].trim().parse::<f64>(),
+                    fields[1].trim().parse::<f64>(),
+                ) {
+                    (Ok(time), Ok(beat_length)) => (time, beat_length),
+                    _ => continue,
+                };
+
+                parsed.timing_points += 1;
+
+                // files from before inherited points were marked only use negative beat lengths
+                let uninherited = match fields.get(6) {
+                    Some(uninherited) => uninherited.trim() == "1",
+                    None => beat_length > 0.0,
+                };
+                if !uninherited || beat_length <= 0.0 {
+                    continue;
+                }
+
+                let bpm = (60_000.0 / beat_length) as f32;
+                if parsed.bpm_changes.last().map(|change| change.bpm) == Some(bpm) {
+                    continue;
+                }
+
+                parsed.min_bpm = Some(parsed.min_bpm.map_or(bpm, |min_bpm| min_bpm.min(bpm)));
+                parsed.max_bpm = Some(parsed.max_bpm.map_or(bpm, |max_bpm| max_bpm.max(bpm)));
+                parsed.bpm_changes.push(BpmChange {
+                    time: time as i32,
+                    bpm,
+                });
+            }
+            "HitObjects" => {
+                let fields: Vec<&str> = line.split(',').collect();
+                if fields.len() < 4 {
+                    continue;
+                }
+
+                let (time, object_type) = match (
+                    fields[2].trim().parse::<f64>(),
+                    fields[3].trim().parse::<u32>(),
+                ) {
+                    (Ok(time), Ok(object_type)) => (time as i32, object_type),
+                    _ => continue,
+                };
+
+                let start_time = time.max(0) / OBJECT_SECTION_LENGTH * OBJECT_SECTION_LENGTH;
+                let object_section = match sections.last_mut() {
+                    Some(last) if last.start_time == start_time => last,
+                    _ => {
+                        sections.push(ObjectSection {
+                            start_time,
+                            ..Default::default()
+                        });
+                        sections.last_mut().unwrap()
+                    }
+                };
+
+                if object_type & CIRCLE != 0 {
+                    parsed.circles += 1;
+                    object_section.circles += 1;
+                } else if object_type & SLIDER != 0 {
+                    parsed.sliders += 1;
+                    object_section.sliders += 1;
+                } else if object_type & SPINNER != 0 {
+                    parsed.spinners += 1;
+                    object_section.spinners += 1;
+                } else if object_type & HOLD != 0 {
+                    parsed.holds += 1;
+                    object_section.holds += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // hit objects are usually sorted, but merge sections in case they weren't
+    sections.sort_by_key(|section| section.start_time);
+    for section in sections {
+        match parsed.object_sections.last_mut() {
+            Some(last) if last.start_time == section.start_time => {
+                last.circles += section.circles;
+                last.sliders += section.sliders;
+                last.spinners += section.spinners;
+                last.holds += section.holds;
+            }
+            _ => parsed.object_sections.push(section),
+        }
+    }
+
+    OsuFile {
+        beatmap_id,
+        version,
+        parsed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_general_metadata_and_events_sections() {
+        let osu_file = parse(
+            "\u{feff}osu file format v14\r\n\
+             \r\n\
+             [General]\r\n\
+             AudioFilename: audio.mp3\r\n\
+             PreviewTime: 40000\r\n\
+             Countdown: 0\r\n\
+             EpilepsyWarning: 1\r\n\
+             \r\n\
+             [Metadata]\r\n\
+             Version:Insane\r\n\
+             BeatmapID:1234\r\n\
+             \r\n\
+             [Events]\r\n\
+             //Background and Video events\r\n\
+             0,0,\"bg.jpg\",0,0\r\n\
+             Sprite,Foreground,Centre,\"sb\\star.png\",320,240\r\n",
+        );
+
+        assert_eq!(osu_file.beatmap_id, Some(1234));
+        assert_eq!(osu_file.version.as_deref(), Some("Insane"));
+        assert_eq!(osu_file.parsed.audio_filename.as_deref(), Some("audio.mp3"));
+        assert_eq!(
+            osu_file.parsed.background_filename.as_deref(),
+            Some("bg.jpg")
+        );
+        assert_eq!(osu_file.parsed.preview_time, Some(40000));
+        assert_eq!(osu_file.parsed.countdown, 0);
+        assert!(osu_file.parsed.epilepsy_warning);
+        assert!(!osu_file.parsed.letterbox_in_breaks);
+        assert!(osu_file.parsed.storyboard);
+    }
+
+    #[test]
+    fn defaults_what_the_file_leaves_out() {
+        let osu_file = parse("osu file format v5\n\n[General]\nPreviewTime: -1\nBeatmapID: 0\n");
+
+        assert_eq!(osu_file.beatmap_id, None);
+        assert_eq!(osu_file.parsed.preview_time, None);
+        assert_eq!(osu_file.parsed.countdown, 1);
+        assert!(!osu_file.parsed.storyboard);
+    }
+
+    #[test]
+    fn reads_bpm_changes_from_uninherited_timing_points() {
+        let osu_file = parse(
+            "[TimingPoints]\n\
+             100,500,4,2,0,100,1,0\n\
+             2000,-50,4,2,0,100,0,0\n\
+             4000,500,4,2,0,100,1,0\n\
+             6000,400,4,2,0,100,1,0\n\
+             \n\
+             [HitObjects]\n",
+        );
+
+        assert_eq!(osu_file.parsed.timing_points, 4);
+        assert_eq!(
+            osu_file.parsed.bpm_changes,
+            [
+                BpmChange {
+                    time: 100,
+                    bpm: 120.0
+                },
+                BpmChange {
+                    time: 6000,
+                    bpm: 150.0
+                },
+            ]
+        );
+        assert_eq!(osu_file.parsed.min_bpm, Some(120.0));
+        assert_eq!(osu_file.parsed.max_bpm, Some(150.0));
+    }
+
+    #[test]
+    fn tells_legacy_inherited_timing_points_by_their_beat_length() {
+        // before the uninherited field existed, inherited points had negative beat lengths
+        let osu_file = parse("[TimingPoints]\n0,300,4\n1000,-100,4\n2000,250,4\n");
+
+        assert_eq!(osu_file.parsed.timing_points, 3);
+        assert_eq!(osu_file.parsed.min_bpm, Some(200.0));
+        assert_eq!(osu_file.parsed.max_bpm, Some(240.0));
+        assert_eq!(osu_file.parsed.bpm_changes.len(), 2);
+    }
+
+    #[test]
+    fn counts_hit_objects_per_section() {
+        let osu_file = parse(
+            "[HitObjects]\n\
+             256,192,1000,5,0,0:0:0:0:\n\
+             256,192,1500,2,0,B|300:200,1,100\n\
+             256,192,12000,12,0,14000,0:0:0:0:\n\
+             64,192,9000,128,0,9500:0:0:0:0:\n\
+             64,192,25000,1,0,0:0:0:0:\n\
+             not,an,object\n",
+        );
+
+        let parsed = osu_file.parsed;
+        assert_eq!(
+            (
+                parsed.circles,
+                parsed.sliders,
+                parsed.spinners,
+                parsed.holds
+            ),
+            (2, 1, 1, 1)
+        );
+        assert_eq!(
+            parsed.object_sections,
+            [
+                ObjectSection {
+                    start_time: 0,
+                    circles: 1,
+                    sliders: 1,
+                    holds: 1,
+                    ..Default::default()
+                },
+                ObjectSection {
+                    start_time: 10_000,
+                    spinners: 1,
+                    ..Default::default()
+                },
+                ObjectSection {
+                    start_time: 20_000,
+                    circles: 1,
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
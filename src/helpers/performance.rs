@@ -0,0 +1,754 @@
+use rosu_v2::prelude::{
+    Beatmap, BeatmapDifficultyAttributes, GameMode, GameModeAttributes, GameMods, ScoreStatistics,
+};
+
+/// A play on a beatmap to calculate performance points for.
+pub struct Score {
+    pub mods: GameMods,
+    pub statistics: ScoreStatistics,
+    pub combo: u32,
+}
+
+/// The mode difficulty attributes were calculated for.
+pub fn mode(attributes: &BeatmapDifficultyAttributes) -> GameMode {
+    match attributes.attrs {
+        GameModeAttributes::Osu { .. } => GameMode::Osu,
+        GameModeAttributes::Taiko { .. } => GameMode::Taiko,
+        GameModeAttributes::Catch { .. } => GameMode::Catch,
+        GameModeAttributes::Mania { .. } => GameMode::Mania,
+    }
+}
+
+/// Count the objects which are judged in a full play of the beatmap, in the mode the attributes
+/// were calculated for. Converted beatmaps are counted from the attributes, as the beatmap's
+/// counts are those of its own mode.
+pub fn total_hits(beatmap: &Beatmap, attributes: &BeatmapDifficultyAttributes) -> u32 {
+    match attributes.attrs {
+        GameModeAttributes::Osu { .. } => {
+            beatmap.count_circles + beatmap.count_sliders + beatmap.count_spinners
+        }
+        // every hit adds to the combo, while drumrolls and swells aren't judged
+        GameModeAttributes::Taiko { .. } => attributes.max_combo,
+        // every fruit and droplet adds to the combo
+        GameModeAttributes::Catch { .. } => attributes.max_combo,
+        GameModeAttributes::Mania { .. } => beatmap.count_circles + beatmap.count_sliders,
+    }
+}
+
+/// Spread hits over judgements, worth the weights from best to worst, so that they add up to the
+/// target, using the best two judgements which can reach it.
+fn spread_hits(hits: u32, target: f64, weights: &[f64]) -> Vec<u32> {
+    let mut counts = vec![0; weights.len()];
+    let total = hits as f64;
+
+    for (index, pair) in weights.windows(2).enumerate() {
+        let (better, worse) = (pair[0], pair[1]);
+        if target < worse * total && index + 2 < weights.len() {
+            continue;
+        }
+
+        let better_count = ((target - worse * total) / (better - worse))
+            .round()
+            .clamp(0.0, total) as u32;
+        counts[index] = better_count;
+        counts[index + 1] = hits - better_count;
+        break;
+    }
+
+    counts
+}
+
+/// Spread the judgements of a play so that it reaches the accuracy, given in percent, favouring
+/// the best judgements.
+pub fn statistics_for_accuracy(
+    mode: GameMode,
+    total_hits: u32,
+    accuracy: f64,
+    misses: u32,
+) -> ScoreStatistics {
+    let misses = misses.min(total_hits);
+    let remaining = total_hits - misses;
+    let accuracy = (accuracy / 100.0).clamp(0.0, 1.0);
+
+    let mut statistics = ScoreStatistics {
+        count_geki: 0,
+        count_300: 0,
+        count_katu: 0,
+        count_100: 0,
+        count_50: 0,
+        count_miss: misses,
+    };
+
+    let total_hits = total_hits as f64;
+    let remaining_hits = remaining as f64;
+
+    match mode {
+        GameMode::Osu => {
+            let counts = spread_hits(
+                remaining,
+                accuracy * 300.0 * total_hits,
+                &[300.0, 100.0, 50.0],
+            );
+
+            statistics.count_300 = counts[0];
+            statistics.count_100 = counts[1];
+            statistics.count_50 = counts[2];
+        }
+        GameMode::Taiko => {
+            let counts = spread_hits(remaining, accuracy * 300.0 * total_hits, &[300.0, 150.0]);
+
+            statistics.count_300 = counts[0];
+            statistics.count_100 = counts[1];
+        }
+        GameMode::Catch => {
+            // fruits and droplets keep the combo, so the accuracy is lost on tiny droplets,
+            // whose misses are counted as katus
+            let missed_tiny_droplets = match accuracy > 0.0 {
+                true => (remaining_hits / accuracy - total_hits).round().max(0.0) as u32,
+                false => 0,
+            };
+
+            statistics.count_300 = remaining;
+            statistics.count_katu = missed_tiny_droplets;
+        }
+        GameMode::Mania => {
+            // the same weights the performance calculation judges accuracy by
+            let counts = spread_hits(
+                remaining,
+                accuracy * 320.0 * total_hits,
+                &[320.0, 300.0, 200.0, 100.0, 50.0],
+            );
+
+            statistics.count_geki = counts[0];
+            statistics.count_300 = counts[1];
+            statistics.count_katu = counts[2];
+            statistics.count_100 = counts[3];
+            statistics.count_50 = counts[4];
+        }
+    }
+
+    statistics
+}
+
+/// Calculate the performance points of a score, using the formulas of osu!'s performance
+/// calculator for the mode the attributes were calculated for.
+pub fn calculate(
+    beatmap: &Beatmap,
+    attributes: &BeatmapDifficultyAttributes,
+    score: &Score,
+) -> f32 {
+    let pp = match attributes.attrs {
+        GameModeAttributes::Osu { .. } => osu(beatmap, attributes, score),
+        GameModeAttributes::Taiko { .. } => taiko(attributes, score),
+        GameModeAttributes::Catch { .. } => catch(attributes, score),
+        GameModeAttributes::Mania { .. } => mania(attributes, score),
+    };
+
+    if pp.is_finite() {
+        pp as f32
+    } else {
+        0.0
+    }
+}
+
+/// Calculate the performance points of a full combo with perfect accuracy.
+pub fn max_pp(beatmap: &Beatmap, attributes: &BeatmapDifficultyAttributes, mods: GameMods) -> f32 {
+    let total_hits = total_hits(beatmap, attributes);
+    let score = Score {
+        mods,
+        statistics: statistics_for_accuracy(mode(attributes), total_hits, 100.0, 0),
+        combo: attributes.max_combo,
+    };
+
+    calculate(beatmap, attributes, &score)
+}
+
+fn osu(beatmap: &Beatmap, attributes: &BeatmapDifficultyAttributes, score: &Score) -> f64 {
+    let (ar, od, aim, speed, flashlight, slider_factor) = match attributes.attrs {
+        GameModeAttributes::Osu {
+            ar,
+            od,
+            aim_difficulty,
+            speed_difficulty,
+            flashlight_difficulty,
+            slider_factor,
+        } => (
+            ar as f64,
+            od as f64,
+            aim_difficulty as f64,
+            speed_difficulty as f64,
+            flashlight_difficulty as f64,
+            slider_factor as f64,
+        ),
+        _ => return 0.0,
+    };
+
+    let statistics = &score.statistics;
+    let mods = score.mods;
+
+    let count_300 = statistics.count_300 as f64;
+    let count_100 = statistics.count_100 as f64;
+    let count_50 = statistics.count_50 as f64;
+    let misses = statistics.count_miss as f64;
+    let total_hits = count_300 + count_100 + count_50 + misses;
+
+    if total_hits == 0.0 {
+        return 0.0;
+    }
+
+    let circles = beatmap.count_circles as f64;
+    let sliders = beatmap.count_sliders as f64;
+    let spinners = beatmap.count_spinners as f64;
+    let max_combo = attributes.max_combo as f64;
+    let combo = score.combo as f64;
+    let accuracy = (count_300 * 6.0 + count_100 * 2.0 + count_50) / (total_hits * 6.0);
+
+    // dropped slider ends break combo without counting as misses
+    let mut combo_based_misses = misses;
+    if sliders > 0.0 {
+        let full_combo_threshold = max_combo - 0.1 * sliders;
+        if combo < full_combo_threshold {
+            combo_based_misses = full_combo_threshold / combo.max(1.0);
+        }
+    }
+    let effective_misses = combo_based_misses
+        .min(count_100 + count_50 + misses)
+        .max(misses);
+
+    let mut multiplier = 1.14;
+    if mods.contains(GameMods::NoFail) {
+        multiplier *= (1.0 - 0.02 * effective_misses).max(0.9);
+    }
+    if mods.contains(GameMods::SpunOut) {
+        multiplier *= 1.0 - (spinners / total_hits).powf(0.85);
+    }
+
+    let length_bonus = 0.95
+        + 0.4 * (total_hits / 2000.0).min(1.0)
+        + match total_hits > 2000.0 {
+            true => (total_hits / 2000.0).log10() * 0.5,
+            false => 0.0,
+        };
+    // misses hurt aim more than speed, whose penalty grows slower with their count
+    let miss_penalty = |exponent: f64| match effective_misses > 0.0 {
+        true => 0.97 * (1.0 - (effective_misses / total_hits).powf(0.775)).powf(exponent),
+        false => 1.0,
+    };
+    let combo_scaling = match max_combo > 0.0 {
+        true => (combo.powf(0.8) / max_combo.powf(0.8)).min(1.0),
+        false => 1.0,
+    };
+
+    let mut aim_value = (5.0 * (aim / 0.0675).max(1.0) - 4.0).powi(3) / 100_000.0;
+    aim_value *= length_bonus * miss_penalty(effective_misses) * combo_scaling;
+
+    let aim_ar_factor = if ar > 10.33 {
+        0.3 * (ar - 10.33)
+    } else if ar < 8.0 {
+        0.05 * (8.0 - ar)
+    } else {
+        0.0
+    };
+    aim_value *= 1.0 + aim_ar_factor * length_bonus;
+    if mods.contains(GameMods::Hidden) {
+        aim_value *= 1.0 + 0.04 * (12.0 - ar);
+    }
+
+    let difficult_sliders = sliders * 0.15;
+    if sliders > 0.0 {
+        let dropped_slider_ends = (count_100 + count_50 + misses)
+            .min(max_combo - combo)
+            .clamp(0.0, difficult_sliders);
+        aim_value *= (1.0 - slider_factor)
+            * (1.0 - dropped_slider_ends / difficult_sliders).powi(3)
+            + slider_factor;
+    }
+    aim_value *= accuracy * (0.98 + od.powi(2) / 2500.0);
+
+    let mut speed_value = (5.0 * (speed / 0.0675).max(1.0) - 4.0).powi(3) / 100_000.0;
+    speed_value *= length_bonus * miss_penalty(effective_misses.powf(0.875)) * combo_scaling;
+
+    let speed_ar_factor = match ar > 10.33 {
+        true => 0.3 * (ar - 10.33),
+        false => 0.0,
+    };
+    speed_value *= 1.0 + speed_ar_factor * length_bonus;
+    if mods.contains(GameMods::Hidden) {
+        speed_value *= 1.0 + 0.04 * (12.0 - ar);
+    }
+    speed_value *= (0.95 + od.powi(2) / 750.0) * accuracy.powf((14.5 - od.max(8.0)) / 2.0);
+    speed_value *= 0.99_f64.powf((count_50 - total_hits / 500.0).max(0.0));
+
+    // only circles are judged on timing
+    let better_accuracy = match circles > 0.0 {
+        true => (((count_300 - (total_hits - circles)) * 6.0 + count_100 * 2.0 + count_50)
+            / (circles * 6.0))
+            .max(0.0),
+        false => 0.0,
+    };
+    let mut accuracy_value = 1.52163_f64.powf(od) * better_accuracy.powi(24) * 2.83;
+    accuracy_value *= (circles / 1000.0).powf(0.3).min(1.15);
+    if mods.contains(GameMods::Hidden) {
+        accuracy_value *= 1.08;
+    }
+    if mods.contains(GameMods::Flashlight) {
+        accuracy_value *= 1.02;
+    }
+
+    let mut flashlight_value = 0.0;
+    if mods.contains(GameMods::Flashlight) {
+        flashlight_value = flashlight.powi(2) * 25.0;
+        flashlight_value *= miss_penalty(effective_misses.powf(0.875)) * combo_scaling;
+        flashlight_value *= 0.7
+            + 0.1 * (total_hits / 200.0).min(1.0)
+            + match total_hits > 200.0 {
+                true => 0.2 * ((total_hits - 200.0) / 200.0).min(1.0),
+                false => 0.0,
+            };
+        flashlight_value *= (0.5 + accuracy / 2.0) * (0.98 + od.powi(2) / 2500.0);
+    }
+
+    (aim_value.powf(1.1)
+        + speed_value.powf(1.1)
+        + accuracy_value.powf(1.1)
+        + flashlight_value.powf(1.1))
+    .powf(1.0 / 1.1)
+        * multiplier
+}
+
+fn taiko(attributes: &BeatmapDifficultyAttributes, score: &Score) -> f64 {
+    let great_hit_window = match attributes.attrs {
+        GameModeAttributes::Taiko {
+            great_hit_window, ..
+        } => great_hit_window as f64,
+        _ => return 0.0,
+    };
+
+    let statistics = &score.statistics;
+    let mods = score.mods;
+    let stars = attributes.stars as f64;
+
+    let count_300 = statistics.count_300 as f64;
+    let count_100 = statistics.count_100 as f64;
+    let misses = statistics.count_miss as f64;
+    let total_hits = count_300 + count_100 + misses;
+
+    if total_hits == 0.0 {
+        return 0.0;
+    }
+
+    let accuracy = (count_300 + count_100 * 0.5) / total_hits;
+    let effective_misses = match count_300 + count_100 > 0.0 {
+        true => (1000.0 / (count_300 + count_100)).max(1.0) * misses,
+        false => misses,
+    };
+
+    let mut multiplier = 1.13;
+    if mods.contains(GameMods::Hidden) {
+        multiplier *= 1.075;
+    }
+    if mods.contains(GameMods::Easy) {
+        multiplier *= 0.975;
+    }
+
+    let length_bonus = 1.0 + 0.1 * (total_hits / 1500.0).min(1.0);
+    let mut difficulty_value = (5.0 * (stars / 0.115).max(1.0) - 4.0).powf(2.25) / 1150.0;
+    difficulty_value *= length_bonus * 0.986_f64.powf(effective_misses);
+    if mods.contains(GameMods::Easy) {
+        difficulty_value *= 0.985;
+    }
+    if mods.contains(GameMods::Hidden) {
+        difficulty_value *= 1.025;
+    }
+    if mods.contains(GameMods::HardRock) {
+        difficulty_value *= 1.05;
+    }
+    if mods.contains(GameMods::Flashlight) {
+        difficulty_value *= 1.05 * length_bonus;
+    }
+    difficulty_value *= accuracy.powi(2);
+
+    let mut accuracy_value = 0.0;
+    if great_hit_window > 0.0 {
+        let accuracy_length_bonus = (total_hits / 1500.0).powf(0.3).min(1.15);
+
+        accuracy_value = (60.0 / great_hit_window).powf(1.1)
+            * accuracy.powi(8)
+            * stars.powf(0.4)
+            * 27.0
+            * accuracy_length_bonus;
+        if mods.contains(GameMods::Hidden | GameMods::Flashlight) {
+            accuracy_value *= (1.075 * accuracy_length_bonus).max(1.05);
+        }
+    }
+
+    (difficulty_value.powf(1.1) + accuracy_value.powf(1.1)).powf(1.0 / 1.1) * multiplier
+}
+
+fn catch(attributes: &BeatmapDifficultyAttributes, score: &Score) -> f64 {
+    let ar = match attributes.attrs {
+        GameModeAttributes::Catch { ar } => ar as f64,
+        _ => return 0.0,
+    };
+
+    let statistics = &score.statistics;
+    let mods = score.mods;
+    let stars = attributes.stars as f64;
+    let max_combo = attributes.max_combo as f64;
+    let combo = score.combo as f64;
+
+    let fruits = statistics.count_300 as f64;
+    let droplets = statistics.count_100 as f64;
+    let tiny_droplets = statistics.count_50 as f64;
+    let missed_tiny_droplets = statistics.count_katu as f64;
+    let misses = statistics.count_miss as f64;
+
+    let combo_hits = fruits + droplets + misses;
+    let total_hits = combo_hits + tiny_droplets + missed_tiny_droplets;
+
+    if total_hits == 0.0 {
+        return 0.0;
+    }
+
+    let accuracy = (fruits + droplets + tiny_droplets) / total_hits;
+
+    let mut value = (5.0 * (stars / 0.0049).max(1.0) - 4.0).powi(2) / 100_000.0;
+
+    let length_bonus = 0.95
+        + 0.3 * (combo_hits / 2500.0).min(1.0)
+        + match combo_hits > 2500.0 {
+            true => (combo_hits / 2500.0).log10() * 0.475,
+            false => 0.0,
+        };
+    value *= length_bonus * 0.97_f64.powf(misses);
+
+    if max_combo > 0.0 {
+        value *= (combo.powf(0.8) / max_combo.powf(0.8)).min(1.0);
+    }
+
+    let mut ar_factor = 1.0;
+    if ar > 9.0 {
+        ar_factor += 0.1 * (ar - 9.0);
+    }
+    if ar > 10.0 {
+        ar_factor += 0.1 * (ar - 10.0);
+    } else if ar < 8.0 {
+        ar_factor += 0.025 * (8.0 - ar);
+    }
+    value *= ar_factor;
+
+    if mods.contains(GameMods::Hidden) {
+        value *= match ar <= 10.0 {
+            true => 1.05 + 0.075 * (10.0 - ar),
+            false => 1.01 + 0.04 * (11.0 - ar.min(11.0)),
+        };
+    }
+    if mods.contains(GameMods::Flashlight) {
+        value *= 1.35 * length_bonus;
+    }
+
+    value *= accuracy.powf(5.5);
+
+    if mods.contains(GameMods::NoFail) {
+        value *= 0.9;
+    }
+
+    value
+}
+
+fn mania(attributes: &BeatmapDifficultyAttributes, score: &Score) -> f64 {
+    let statistics = &score.statistics;
+    let mods = score.mods;
+    let stars = attributes.stars as f64;
+
+    let count_320 = statistics.count_geki as f64;
+    let count_300 = statistics.count_300 as f64;
+    let count_200 = statistics.count_katu as f64;
+    let count_100 = statistics.count_100 as f64;
+    let count_50 = statistics.count_50 as f64;
+    let misses = statistics.count_miss as f64;
+    let total_hits = count_320 + count_300 + count_200 + count_100 + count_50 + misses;
+
+    if total_hits == 0.0 {
+        return 0.0;
+    }
+
+    // perfect judgements are worth slightly more than greats
+    let accuracy = (count_320 * 32.0
+        + count_300 * 30.0
+        + count_200 * 20.0
+        + count_100 * 10.0
+        + count_50 * 5.0)
+        / (total_hits * 32.0);
+
+    let mut multiplier = 8.0;
+    if mods.contains(GameMods::NoFail) {
+        multiplier *= 0.75;
+    }
+    if mods.contains(GameMods::Easy) {
+        multiplier *= 0.5;
+    }
+
+    let difficulty_value = (stars - 0.15).max(0.05).powf(2.2)
+        * (5.0 * accuracy - 4.0).max(0.0)
+        * (1.0 + 0.1 * (total_hits / 1500.0).min(1.0));
+
+    difficulty_value * multiplier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An osu!standard beatmap with the object counts, as osu!'s API returns it.
+    fn beatmap(count_circles: u32, count_sliders: u32, count_spinners: u32) -> Beatmap {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "beatmapset_id": 1,
+            "user_id": 1,
+            "mode": "osu",
+            "version": "Insane",
+            "url": "https://osu.ppy.sh/beatmaps/1",
+            "status": "ranked",
+            "convert": false,
+            "is_scoreable": true,
+            "last_updated": "2022-01-01T00:00:00Z",
+            "ar": 9.3,
+            "cs": 4.0,
+            "drain": 6.0,
+            "accuracy": 8.8,
+            "bpm": 180.0,
+            "difficulty_rating": 5.6,
+            "count_circles": count_circles,
+            "count_sliders": count_sliders,
+            "count_spinners": count_spinners,
+            "passcount": 0,
+            "playcount": 0,
+            "hit_length": 120,
+            "total_length": 120,
+        }))
+        .unwrap()
+    }
+
+    fn statistics(counts: [u32; 6]) -> ScoreStatistics {
+        let [count_geki, count_300, count_katu, count_100, count_50, count_miss] = counts;
+
+        ScoreStatistics {
+            count_geki,
+            count_300,
+            count_katu,
+            count_100,
+            count_50,
+            count_miss,
+        }
+    }
+
+    fn counts(statistics: ScoreStatistics) -> [u32; 6] {
+        [
+            statistics.count_geki,
+            statistics.count_300,
+            statistics.count_katu,
+            statistics.count_100,
+            statistics.count_50,
+            statistics.count_miss,
+        ]
+    }
+
+    /// Performance points of a score, which must be within a hundredth of the reference value.
+    fn assert_pp(
+        beatmap: &Beatmap,
+        attributes: &BeatmapDifficultyAttributes,
+        mods: GameMods,
+        counts: [u32; 6],
+        combo: u32,
+        expected: f32,
+    ) {
+        let score = Score {
+            mods,
+            statistics: statistics(counts),
+            combo,
+        };
+        let pp = calculate(beatmap, attributes, &score);
+
+        assert!(
+            (pp - expected).abs() < 0.01,
+            "expected {} pp for {} but got {}",
+            expected,
+            mods,
+            pp
+        );
+    }
+
+    #[test]
+    fn spreads_accuracy_over_the_best_judgements() {
+        assert_eq!(
+            counts(statistics_for_accuracy(GameMode::Osu, 1000, 95.0, 2)),
+            [0, 926, 0, 72, 0, 2]
+        );
+        assert_eq!(
+            counts(statistics_for_accuracy(GameMode::Osu, 1000, 30.0, 0)),
+            [0, 0, 0, 800, 200, 0]
+        );
+        assert_eq!(
+            counts(statistics_for_accuracy(GameMode::Taiko, 1000, 97.0, 0)),
+            [0, 940, 0, 60, 0, 0]
+        );
+        assert_eq!(
+            counts(statistics_for_accuracy(GameMode::Catch, 1000, 98.0, 0)),
+            [0, 1000, 20, 0, 0, 0]
+        );
+        assert_eq!(
+            counts(statistics_for_accuracy(GameMode::Mania, 1000, 98.0, 0)),
+            [680, 320, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            counts(statistics_for_accuracy(GameMode::Mania, 1000, 90.0, 0)),
+            [0, 880, 120, 0, 0, 0]
+        );
+        assert_eq!(
+            counts(statistics_for_accuracy(GameMode::Mania, 1000, 100.0, 1000)),
+            [0, 0, 0, 0, 0, 1000]
+        );
+    }
+
+    #[test]
+    fn calculates_osu_pp() {
+        let beatmap = beatmap(600, 250, 3);
+        let attributes = |flashlight_difficulty| BeatmapDifficultyAttributes {
+            max_combo: 1200,
+            stars: 5.6,
+            attrs: GameModeAttributes::Osu {
+                ar: 9.3,
+                od: 8.8,
+                aim_difficulty: 2.8,
+                flashlight_difficulty,
+                slider_factor: 0.98,
+                speed_difficulty: 2.5,
+            },
+        };
+
+        let nomod = attributes(0.0);
+        assert_pp(
+            &beatmap,
+            &nomod,
+            GameMods::NoMod,
+            [0, 853, 0, 0, 0, 0],
+            1200,
+            271.778,
+        );
+        assert_pp(
+            &beatmap,
+            &nomod,
+            GameMods::Hidden,
+            [0, 830, 0, 15, 5, 3],
+            700,
+            162.615,
+        );
+
+        let flashlight = attributes(1.9);
+        let mods = GameMods::Hidden | GameMods::Flashlight | GameMods::NoFail;
+        assert_pp(
+            &beatmap,
+            &flashlight,
+            mods,
+            [0, 840, 0, 10, 0, 3],
+            1100,
+            291.005,
+        );
+    }
+
+    #[test]
+    fn calculates_taiko_pp() {
+        let beatmap = beatmap(0, 0, 0);
+        let attributes = BeatmapDifficultyAttributes {
+            max_combo: 1000,
+            stars: 5.2,
+            attrs: GameModeAttributes::Taiko {
+                stamina_difficulty: 0.0,
+                rhythm_difficulty: 0.0,
+                colour_difficulty: 0.0,
+                peak_difficulty: 0.0,
+                great_hit_window: 28.0,
+            },
+        };
+
+        assert_pp(
+            &beatmap,
+            &attributes,
+            GameMods::NoMod,
+            [0, 950, 0, 45, 0, 5],
+            600,
+            256.939,
+        );
+        let mods = GameMods::Hidden | GameMods::Flashlight;
+        assert_pp(
+            &beatmap,
+            &attributes,
+            mods,
+            [0, 1000, 0, 0, 0, 0],
+            1000,
+            360.782,
+        );
+    }
+
+    #[test]
+    fn calculates_catch_pp() {
+        let beatmap = beatmap(0, 0, 0);
+        let attributes = |ar| BeatmapDifficultyAttributes {
+            max_combo: 1500,
+            stars: 6.1,
+            attrs: GameModeAttributes::Catch { ar },
+        };
+
+        let counts = [0, 1200, 12, 280, 400, 20];
+        assert_pp(
+            &beatmap,
+            &attributes(9.6),
+            GameMods::NoMod,
+            counts,
+            900,
+            152.642,
+        );
+        let counts = [0, 1500, 0, 0, 412, 0];
+        assert_pp(
+            &beatmap,
+            &attributes(10.4),
+            GameMods::Hidden,
+            counts,
+            1500,
+            533.495,
+        );
+    }
+
+    #[test]
+    fn calculates_mania_pp() {
+        let beatmap = beatmap(0, 0, 0);
+        let attributes = BeatmapDifficultyAttributes {
+            max_combo: 2500,
+            stars: 4.2,
+            attrs: GameModeAttributes::Mania {
+                great_hit_window: 40.0,
+                score_multiplier: 1.0,
+            },
+        };
+
+        let counts = [1500, 300, 40, 10, 5, 5];
+        assert_pp(
+            &beatmap,
+            &attributes,
+            GameMods::NoMod,
+            counts,
+            2000,
+            165.351,
+        );
+        let mods = GameMods::NoFail | GameMods::Easy;
+        assert_pp(
+            &beatmap,
+            &attributes,
+            mods,
+            [1860, 0, 0, 0, 0, 0],
+            2500,
+            71.600,
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::Cursor;
+
+use image::{
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    DynamicImage, ImageReader, Limits,
+};
+
+use crate::models::image::{ImageFormat, ImageSize};
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Largest width and height of a background decoded, twice that of an 8K image.
+const MAX_DIMENSION: u32 = 16_384;
+/// Most memory decoding a background may take.
+const MAX_DECODE_ALLOCATION: u64 = 256 * 1024 * 1024;
+
+/// Resize an image to a standard size and encode it in the requested format.
+pub fn render(source: &[u8], size: ImageSize, format: ImageFormat) -> anyhow::Result<Vec<u8>> {
+    // backgrounds come from archives anyone can upload, so a tiny file mustn't decode to a
+    // huge image
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOCATION);
+
+    let mut reader = ImageReader::new(Cursor::new(source)).with_guessed_format()?;
+    reader.limits(limits);
+    let image = reader.decode()?;
+
+    let (width, height, crop) = size.dimensions();
+    let resized = if crop {
+        image.resize_to_fill(width, height, FilterType::Lanczos3)
+    } else if image.width() > width || image.height() > height {
+        image.resize(width, height, FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    // neither encoder supports every colour type, but both take 8 bit rgb
+    let resized = DynamicImage::ImageRgb8(resized.to_rgb8());
+    let mut rendered = Cursor::new(Vec::new());
+
+    match format {
+        ImageFormat::Jpeg => resized
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut rendered, JPEG_QUALITY))?,
+        ImageFormat::Webp => {
+            resized.write_with_encoder(WebPEncoder::new_lossless(&mut rendered))?
+        }
+    }
+
+    Ok(rendered.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{codecs::png::PngEncoder, RgbImage};
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_with_encoder(PngEncoder::new(&mut png))
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn renders_to_the_size_and_format() {
+        let rendered = render(&png(300, 200), ImageSize::List, ImageFormat::Jpeg).unwrap();
+        let image = image::load_from_memory(&rendered).unwrap();
+
+        assert_eq!(
+            image::guess_format(&rendered).unwrap(),
+            image::ImageFormat::Jpeg
+        );
+        assert_eq!((image.width(), image.height()), (150, 150));
+    }
+
+    #[test]
+    fn rejects_images_beyond_the_limits() {
+        assert!(render(
+            &png(MAX_DIMENSION + 1, 1),
+            ImageSize::Full,
+            ImageFormat::Jpeg
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{sync::OnceCell, time::Instant};
+
+/// Coalesces concurrent lookups of the same key into a single call, and remembers
+/// keys which were recently confirmed not to exist.
+pub struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, Arc<OnceCell<Option<V>>>>>,
+    not_found: Mutex<HashMap<K, Instant>>,
+    not_found_ttl: Duration,
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new(not_found_ttl: Duration) -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+            not_found: Mutex::new(HashMap::new()),
+            not_found_ttl,
+        }
+    }
+
+    pub async fn run<F, Fut>(&self, key: K, lookup: F) -> anyhow::Result<Option<V>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<Option<V>>>,
+    {
+        if self.is_recently_not_found(&key) {
+            return Ok(None);
+        }
+
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(OnceCell::new()))
+            .clone();
+
+        // if the caller running the lookup fails or is dropped, a waiting caller takes over
+        let result = cell.get_or_try_init(lookup).await.cloned();
+
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight
+                .get(&key)
+                .is_some_and(|current| Arc::ptr_eq(current, &cell))
+            {
+                in_flight.remove(&key);
+            }
+        }
+
+        if let Ok(None) = result {
+            self.remember_not_found(key);
+        }
+
+        result
+    }
+
+    /// Remember that a key was not found by a lookup made outside of [`SingleFlight::run`].
+    pub fn remember_not_found(&self, key: K) {
+        let now = Instant::now();
+        let mut not_found = self.not_found.lock().unwrap();
+        not_found.retain(|_, found_at| now.duration_since(*found_at) < self.not_found_ttl);
+        not_found.insert(key, now);
+    }
+
+    /// Forget that a key was not found, e.g. after it has been indexed.
+    pub fn forget(&self, key: &K) {
+        self.not_found.lock().unwrap().remove(key);
+    }
+
+    pub fn is_recently_not_found(&self, key: &K) -> bool {
+        self.not_found
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|found_at| found_at.elapsed() < self.not_found_ttl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn coalesces_concurrent_lookups() {
+        let flight = SingleFlight::<u32, u32>::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+
+        let lookup = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(Some(7))
+        };
+
+        let (first, second) = tokio::join!(flight.run(1, lookup), flight.run(1, lookup));
+
+        assert_eq!(first.unwrap(), Some(7));
+        assert_eq!(second.unwrap(), Some(7));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn runs_again_once_finished() {
+        let flight = SingleFlight::<u32, u32>::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let found = flight
+                .run(1, || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(Some(7))
+                })
+                .await;
+            assert_eq!(found.unwrap(), Some(7));
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn remembers_not_found_until_forgotten() {
+        let flight = SingleFlight::<u32, u32>::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+        let lookup = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(None)
+        };
+
+        assert_eq!(flight.run(1, lookup).await.unwrap(), None);
+        assert_eq!(flight.run(1, lookup).await.unwrap(), None);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        flight.forget(&1);
+        assert_eq!(flight.run(1, lookup).await.unwrap(), None);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_remember_failures() {
+        let flight = SingleFlight::<u32, u32>::new(Duration::from_secs(60));
+
+        let failed = flight
+            .run(1, || async { Err(anyhow::anyhow!("osu! is down")) })
+            .await;
+        assert!(failed.is_err());
+
+        let found = flight.run(1, || async { Ok(Some(7)) }).await;
+        assert_eq!(found.unwrap(), Some(7));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waiting_caller_takes_over_a_failed_lookup() {
+        let flight = SingleFlight::<u32, u32>::new(Duration::from_secs(60));
+
+        let failing = flight.run(1, || async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err(anyhow::anyhow!("osu! is down"))
+        });
+        let waiting = async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            flight.run(1, || async { Ok(Some(7)) }).await
+        };
+
+        let (failed, found) = tokio::join!(failing, waiting);
+
+        assert!(failed.is_err());
+        assert_eq!(found.unwrap(), Some(7));
+    }
+
+    #[tokio::test]
+    async fn skips_keys_remembered_as_not_found() {
+        let flight = SingleFlight::<u32, u32>::new(Duration::from_secs(60));
+
+        flight.remember_not_found(1);
+
+        assert!(flight.is_recently_not_found(&1));
+        assert!(!flight.is_recently_not_found(&2));
+        assert_eq!(flight.run(1, || async { Ok(Some(7)) }).await.unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expires_not_found_entries() {
+        let flight = SingleFlight::<u32, u32>::new(Duration::from_secs(60));
+
+        assert_eq!(flight.run(1, || async { Ok(None) }).await.unwrap(), None);
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert!(flight.is_recently_not_found(&1));
+        tokio::time::advance(Duration::from_secs(1)).await;
+
+        let found = flight.run(1, || async { Ok(Some(7)) }).await;
+        assert_eq!(found.unwrap(), Some(7));
+    }
+}
@@ -1,21 +1,45 @@
 use std::sync::Arc;
 
+use axum::body::Bytes;
 use config::Config;
 use elasticsearch::Elasticsearch;
+use helpers::{archive_stats::ArchiveStats, hot_cache::HotCache, single_flight::SingleFlight};
+use models::{
+    beatmap::Beatmap,
+    beatmapset::Beatmapset,
+    image::{ImageFormat, ImageKind, ImageSize},
+};
+use repositories::osu::RawClient;
 use rosu_v2::Osu;
 
 pub mod api;
 pub mod config;
 pub mod crawler;
 pub mod helpers;
+pub mod migrator;
 pub mod models;
+pub mod prefetcher;
 pub mod repositories;
 pub mod updater;
 pub mod usecases;
+pub mod verifier;
 
 #[derive(Clone)]
 pub struct Context {
     pub config: Arc<Config>,
     pub database: Elasticsearch,
     pub osu_api: Arc<Osu>,
+    /// Client for the osu! api endpoints rosu-v2 doesn't cover
+    pub osu_raw_api: Arc<RawClient>,
+    pub beatmap_lookups: Arc<SingleFlight<u32, Beatmap>>,
+    pub beatmapset_lookups: Arc<SingleFlight<u32, Beatmapset>>,
+    /// Archive downloads from osu!, keyed by beatmapset id and whether it's without video
+    pub archive_downloads: Arc<SingleFlight<(u32, bool), Bytes>>,
+    /// Background renders, keyed by what they're of, their id, size and format
+    pub image_renders: Arc<SingleFlight<(ImageKind, u32, ImageSize, ImageFormat), Bytes>>,
+    pub beatmap_cache: Arc<HotCache<Beatmap>>,
+    pub beatmapset_cache: Arc<HotCache<Beatmapset>>,
+    pub archive_stats: Arc<ArchiveStats>,
+    /// Held while evicting archives, so concurrent stores don't evict for the same overflow
+    pub archive_evictions: Arc<tokio::sync::Mutex<()>>,
 }
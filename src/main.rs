@@ -1,6 +1,16 @@
 use std::{sync::Arc, time::Duration};
 
-use beatmap_mirror::{api, config::Config, crawler, updater, Context};
+use beatmap_mirror::{
+    api,
+    config::Config,
+    crawler,
+    helpers::{
+        archive_stats::ArchiveStats, elastic, hot_cache::HotCache, single_flight::SingleFlight,
+    },
+    migrator, prefetcher,
+    repositories::osu::RawClient,
+    updater, usecases, verifier, Context,
+};
 use clap::Parser;
 use elasticsearch::{
     auth::Credentials,
@@ -43,17 +53,45 @@ async fn main() -> anyhow::Result<()> {
         .client_secret(config.osu_api_client_secret.clone())
         .build()
         .await?;
+    let osu_raw_api = RawClient::new(&config)?;
+
+    let not_found_ttl = Duration::from_secs(config.not_found_cache_ttl);
+    let ranked_ttl = Duration::from_secs(config.hot_cache_ranked_ttl);
+    let unranked_ttl = Duration::from_secs(config.hot_cache_unranked_ttl);
+
+    let beatmap_cache = HotCache::new(config.hot_cache_capacity, ranked_ttl, unranked_ttl);
+    let beatmapset_cache = HotCache::new(config.hot_cache_capacity, ranked_ttl, unranked_ttl);
 
     let ctx = Context {
         config: Arc::new(config),
         database,
         osu_api: Arc::new(osu_api),
+        osu_raw_api: Arc::new(osu_raw_api),
+        beatmap_lookups: Arc::new(SingleFlight::new(not_found_ttl)),
+        beatmapset_lookups: Arc::new(SingleFlight::new(not_found_ttl)),
+        archive_downloads: Arc::new(SingleFlight::new(not_found_ttl)),
+        image_renders: Arc::new(SingleFlight::new(not_found_ttl)),
+        beatmap_cache: Arc::new(beatmap_cache),
+        beatmapset_cache: Arc::new(beatmapset_cache),
+        archive_stats: Arc::new(ArchiveStats::default()),
+        archive_evictions: Arc::new(tokio::sync::Mutex::new(())),
     };
 
     match ctx.config.app_component.as_str() {
         "crawler" => crawler::serve(ctx).await?,
         "api" => api::serve(ctx).await?,
         "updater" => updater::serve(ctx).await?,
+        "verify" => verifier::serve(ctx).await?,
+        "prefetcher" => prefetcher::serve(ctx).await?,
+        "migrate" => migrator::serve(ctx).await?,
+        "create-admin-key" => {
+            // for setting up a deployment, since creating keys through the api needs one
+            elastic::create_index_if_not_exists(&ctx.database, &ctx.config.elastic_api_keys_index)
+                .await?;
+
+            let api_key = usecases::api_keys::create(&ctx, "admin".to_string(), 0, true).await?;
+            println!("{}", api_key.key);
+        }
         _ => anyhow::bail!("unknown app component: {}", ctx.config.app_component),
     }
 
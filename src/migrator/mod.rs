@@ -0,0 +1,29 @@
+use crate::{helpers::elastic, repositories, usecases, Context};
+
+/// Bring the indexes up to date with the mappings search relies on, reindexing existing
+/// documents, and track archives stored before the store kept track of them. Run once after
+/// upgrading, rather than from every component as they start.
+pub async fn serve(context: Context) -> anyhow::Result<()> {
+    elastic::create_index_if_not_exists(&context.database, &context.config.elastic_beatmaps_index)
+        .await?;
+    elastic::create_index_if_not_exists(
+        &context.database,
+        &context.config.elastic_beatmapsets_index,
+    )
+    .await?;
+
+    repositories::beatmapsets::put_search_mappings(&context).await?;
+
+    if context.config.archive_path.is_some() {
+        elastic::create_index_if_not_exists(
+            &context.database,
+            &context.config.elastic_archives_index,
+        )
+        .await?;
+
+        let tracked = usecases::archives::track_untracked(&context).await?;
+        log::info!("tracked {} previously stored archives", tracked);
+    }
+
+    Ok(())
+}
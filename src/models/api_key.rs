@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct ApiKey {
+    pub key: String,
+    pub name: String,
+    /// 0 means the key is not rate limited
+    pub requests_per_minute: u32,
+    pub admin: bool,
+    pub created_at: DateTime<Utc>,
+}
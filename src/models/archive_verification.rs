@@ -0,0 +1,13 @@
+/// Outcome of checking a stored archive against the checksums of its indexed beatmaps.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArchiveVerification {
+    /// Every indexed beatmap has a matching `.osu` file in the archive
+    Valid,
+    /// The archive was missing beatmaps or couldn't be read, even after fetching the beatmapset
+    /// from osu! again, so it was flagged and downloaded again
+    Stale,
+    /// No archive is stored for the beatmapset
+    NotStored,
+    /// The beatmapset isn't indexed or has no checksums to compare against
+    Unverifiable,
+}
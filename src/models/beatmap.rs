@@ -1,5 +1,7 @@
 use chrono::{DateTime, Utc};
 
+use crate::models::parsed_beatmap::ParsedBeatmap;
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Beatmap {
     pub data: rosu_v2::prelude::Beatmap,
@@ -7,4 +9,9 @@ pub struct Beatmap {
     pub updated_at: DateTime<Utc>,
     pub last_checked: DateTime<Utc>,
     pub crawled: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    /// Read from the beatmap's `.osu` file once its archive has been stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parsed: Option<ParsedBeatmap>,
 }
@@ -7,4 +7,6 @@ pub struct Beatmapset {
     pub updated_at: DateTime<Utc>,
     pub last_checked: DateTime<Utc>,
     pub crawled: bool,
+    #[serde(default)]
+    pub hidden: bool,
 }
@@ -54,6 +54,20 @@ pub struct CheesegullBeatmapset {
     pub star_rating: f32,
 }
 
+impl CheesegullBeatmapset {
+    /// Convert a beatmapset, listing its converted difficulties alongside the native ones.
+    pub fn with_converts(mut beatmapset: Beatmapset) -> Self {
+        if let Some(converts) = beatmapset.converts.take() {
+            beatmapset
+                .maps
+                .get_or_insert_with(Vec::new)
+                .extend(converts);
+        }
+
+        beatmapset.into()
+    }
+}
+
 impl From<Beatmapset> for CheesegullBeatmapset {
     fn from(beatmapset: Beatmapset) -> Self {
         let beatmaps = beatmapset.maps.as_ref().unwrap();
@@ -67,14 +81,17 @@ impl From<Beatmapset> for CheesegullBeatmapset {
             set_id: beatmapset.mapset_id,
             ranked_status: beatmapset.status as u8,
             children_beatmaps: beatmaps
-                .into_iter()
+                .iter()
                 .map(|beatmap| CheesegullBeatmap::from_beatmapset(beatmap, &beatmapset))
                 .collect(),
-            approved_date: beatmapset
-                .ranked_date
-                .map(|date| Utc.timestamp(date.unix_timestamp(), 0).to_rfc3339()),
+            approved_date: beatmapset.ranked_date.map(|date| {
+                Utc.timestamp_opt(date.unix_timestamp(), 0)
+                    .unwrap()
+                    .to_rfc3339()
+            }),
             last_update: Utc
-                .timestamp(beatmapset.last_updated.unix_timestamp(), 0)
+                .timestamp_opt(beatmapset.last_updated.unix_timestamp(), 0)
+                .unwrap()
                 .to_rfc3339(),
             artist: beatmapset.artist,
             title: beatmapset.title,
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlKind {
+    Beatmaps,
+    Beatmapsets,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct CrawlRequest {
+    pub kind: CrawlKind,
+    pub start_id: u32,
+    pub end_id: u32,
+    pub created_at: DateTime<Utc>,
+}
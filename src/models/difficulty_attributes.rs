@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use rosu_v2::prelude::{BeatmapDifficultyAttributes, GameMode, GameMods};
+
+use crate::helpers::difficulty::CALCULATOR_VERSION;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct DifficultyAttributes {
+    pub beatmap_id: u32,
+    pub mode: GameMode,
+    pub mods: u32,
+    /// Checksum of the beatmap version the attributes were calculated for
+    pub checksum: Option<String>,
+    pub data: BeatmapDifficultyAttributes,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DifficultyAttributes {
+    pub fn document_id(beatmap_id: u32, mode: GameMode, mods: u32) -> String {
+        format!(
+            "{}_{}_{}_v{}",
+            beatmap_id, mode as u8, mods, CALCULATOR_VERSION
+        )
+    }
+}
+
+/// Keep only the mods which change difficulty attributes, so e.g. HDDT and NC share one entry.
+pub fn difficulty_mods(mods: GameMods) -> GameMods {
+    let mut difficulty_mods = mods
+        & (GameMods::Easy
+            | GameMods::HardRock
+            | GameMods::DoubleTime
+            | GameMods::HalfTime
+            | GameMods::Flashlight);
+
+    // a bare nightcore bit, without the double time one it implies, still speeds the map up
+    if mods.intersects(GameMods::NightCore) {
+        difficulty_mods |= GameMods::DoubleTime;
+    }
+
+    // hidden only changes the flashlight difficulty
+    if mods.contains(GameMods::Flashlight) {
+        difficulty_mods |= mods & GameMods::Hidden;
+    }
+
+    difficulty_mods
+}
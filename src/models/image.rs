@@ -0,0 +1,75 @@
+/// Standard sizes beatmap backgrounds are rendered at.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageSize {
+    /// 150x150, cropped to fill
+    List,
+    /// 400x140, cropped to fill
+    Card,
+    /// The original image, scaled down to fit in 1920x1080
+    Full,
+}
+
+impl ImageSize {
+    pub const ALL: [ImageSize; 3] = [ImageSize::List, ImageSize::Card, ImageSize::Full];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Card => "card",
+            Self::Full => "full",
+        }
+    }
+
+    /// Width and height to render at, and whether the image is cropped to fill them exactly.
+    pub fn dimensions(self) -> (u32, u32, bool) {
+        match self {
+            Self::List => (150, 150, true),
+            Self::Card => (400, 140, true),
+            Self::Full => (1920, 1080, false),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Jpeg,
+    Webp,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 2] = [ImageFormat::Jpeg, ImageFormat::Webp];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+/// What a rendered image shows, which decides the id it's stored under.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ImageKind {
+    /// A beatmapset's thumbnail, by beatmapset id
+    Thumbnail,
+    /// A difficulty's background, by beatmap id
+    Background,
+}
+
+impl ImageKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Thumbnail => "thumb",
+            Self::Background => "bg",
+        }
+    }
+}
@@ -1,5 +1,17 @@
+pub mod api_key;
+pub mod archive_verification;
 pub mod beatmap;
 pub mod beatmapset;
 pub mod cheesegull;
+pub mod crawl_request;
+pub mod difficulty_attributes;
+pub mod image;
 pub mod mode;
+pub mod parsed_beatmap;
 pub mod ranked_status;
+pub mod search_cursor;
+pub mod search_facets;
+pub mod search_filters;
+pub mod search_sort;
+pub mod stored_archive;
+pub mod suggestions;
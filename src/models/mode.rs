@@ -1,6 +1,10 @@
 use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use rosu_v2::prelude::GameMode;
 
-#[derive(Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize, FromPrimitive)]
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize, FromPrimitive,
+)]
 #[repr(i8)]
 pub enum Mode {
     All = -1,
@@ -9,3 +13,35 @@ pub enum Mode {
     Catch,
     Mania,
 }
+
+impl Mode {
+    /// Names accepted by [`Mode::parse`], alongside the numeric codes.
+    pub const NAMES: [&'static str; 5] = ["all", "osu", "taiko", "fruits", "mania"];
+
+    /// Parse a mode from either its numeric code or its name.
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Ok(code) = value.parse::<i8>() {
+            return FromPrimitive::from_i8(code);
+        }
+
+        match value.to_lowercase().as_str() {
+            "all" => Some(Self::All),
+            "osu" | "standard" | "std" => Some(Self::Standard),
+            "taiko" => Some(Self::Taiko),
+            "fruits" | "catch" | "ctb" => Some(Self::Catch),
+            "mania" => Some(Self::Mania),
+            _ => None,
+        }
+    }
+
+    /// The osu! mode, or `None` for all modes.
+    pub fn game_mode(self) -> Option<GameMode> {
+        match self {
+            Self::All => None,
+            Self::Standard => Some(GameMode::Osu),
+            Self::Taiko => Some(GameMode::Taiko),
+            Self::Catch => Some(GameMode::Catch),
+            Self::Mania => Some(GameMode::Mania),
+        }
+    }
+}
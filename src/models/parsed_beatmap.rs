@@ -0,0 +1,41 @@
+/// Metadata read from a beatmap's `.osu` file which the osu! api doesn't provide.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ParsedBeatmap {
+    pub audio_filename: Option<String>,
+    pub background_filename: Option<String>,
+    /// Milliseconds into the audio where the song preview starts
+    pub preview_time: Option<i32>,
+    /// 0 for no countdown, then 1 for normal, 2 for half and 3 for double speed
+    pub countdown: u8,
+    pub epilepsy_warning: bool,
+    pub letterbox_in_breaks: bool,
+    pub storyboard: bool,
+    pub timing_points: u32,
+    pub bpm_changes: Vec<BpmChange>,
+    pub min_bpm: Option<f32>,
+    pub max_bpm: Option<f32>,
+    pub circles: u32,
+    pub sliders: u32,
+    pub spinners: u32,
+    /// Mania hold notes
+    pub holds: u32,
+    pub object_sections: Vec<ObjectSection>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BpmChange {
+    /// Milliseconds into the audio
+    pub time: i32,
+    pub bpm: f32,
+}
+
+/// Objects starting within a fixed length section of the beatmap.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ObjectSection {
+    /// Milliseconds into the audio
+    pub start_time: i32,
+    pub circles: u32,
+    pub sliders: u32,
+    pub spinners: u32,
+    pub holds: u32,
+}
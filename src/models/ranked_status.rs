@@ -1,6 +1,9 @@
 use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
 
-#[derive(Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize, FromPrimitive)]
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize, FromPrimitive,
+)]
 #[repr(i8)]
 pub enum RankedStatus {
     All = -3,
@@ -12,3 +15,102 @@ pub enum RankedStatus {
     Qualified,
     Loved,
 }
+
+impl RankedStatus {
+    /// Names accepted by [`RankedStatus::parse`], alongside the numeric codes.
+    pub const NAMES: [&'static str; 8] = [
+        "all",
+        "graveyard",
+        "wip",
+        "pending",
+        "ranked",
+        "approved",
+        "qualified",
+        "loved",
+    ];
+
+    /// Statuses which have a leaderboard.
+    pub const HAS_LEADERBOARD: [Self; 3] = [Self::Ranked, Self::Approved, Self::Loved];
+
+    /// Statuses which are still being worked on.
+    pub const IN_PROGRESS: [Self; 2] = [Self::Pending, Self::WorkInProgress];
+
+    /// Names of the groups accepted by [`RankedStatus::parse_group`].
+    pub const GROUP_NAMES: [&'static str; 2] = ["has_leaderboard", "in_progress"];
+
+    /// Parse a named group of statuses.
+    pub fn parse_group(value: &str) -> Option<&'static [Self]> {
+        match value.to_lowercase().as_str() {
+            "has_leaderboard" => Some(&Self::HAS_LEADERBOARD),
+            "in_progress" => Some(&Self::IN_PROGRESS),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[(self as i8 - Self::All as i8) as usize]
+    }
+
+    /// Parse a status from either its numeric code or its name.
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Ok(code) = value.parse::<i8>() {
+            return FromPrimitive::from_i8(code);
+        }
+
+        match value.to_lowercase().as_str() {
+            "all" => Some(Self::All),
+            "graveyard" => Some(Self::Graveyard),
+            "wip" | "work_in_progress" => Some(Self::WorkInProgress),
+            "pending" => Some(Self::Pending),
+            "ranked" => Some(Self::Ranked),
+            "approved" => Some(Self::Approved),
+            "qualified" => Some(Self::Qualified),
+            "loved" => Some(Self::Loved),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_codes() {
+        assert_eq!(RankedStatus::parse("ranked"), Some(RankedStatus::Ranked));
+        assert_eq!(RankedStatus::parse("Loved"), Some(RankedStatus::Loved));
+        assert_eq!(
+            RankedStatus::parse("work_in_progress"),
+            Some(RankedStatus::WorkInProgress)
+        );
+        assert_eq!(RankedStatus::parse("-2"), Some(RankedStatus::Graveyard));
+        assert_eq!(RankedStatus::parse("4"), Some(RankedStatus::Loved));
+    }
+
+    #[test]
+    fn rejects_unknown_statuses() {
+        assert_eq!(RankedStatus::parse("5"), None);
+        assert_eq!(RankedStatus::parse("-4"), None);
+        assert_eq!(RankedStatus::parse("unranked"), None);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for name in RankedStatus::NAMES {
+            assert_eq!(RankedStatus::parse(name).unwrap().name(), name);
+        }
+    }
+
+    #[test]
+    fn parses_groups() {
+        assert_eq!(
+            RankedStatus::parse_group("has_leaderboard"),
+            Some(&RankedStatus::HAS_LEADERBOARD[..])
+        );
+        assert_eq!(
+            RankedStatus::parse_group("IN_PROGRESS"),
+            Some(&RankedStatus::IN_PROGRESS[..])
+        );
+        assert_eq!(RankedStatus::parse_group("ranked"), None);
+    }
+}
@@ -0,0 +1,59 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+/// Where the next page of a search starts: the point-in-time being paged through and the
+/// sort values of the last hit returned.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct SearchCursor {
+    pub pit_id: String,
+    pub search_after: Vec<serde_json::Value>,
+}
+
+impl SearchCursor {
+    /// Encode the cursor as an opaque token for clients.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(token).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_its_token() {
+        let cursor = SearchCursor {
+            pit_id: "46ToAwMDaWR5BXV1aWQy".to_string(),
+            search_after: vec![serde_json::json!(1234), serde_json::json!("title")],
+        };
+
+        let decoded = SearchCursor::decode(&cursor.encode()).unwrap();
+
+        assert_eq!(decoded.pit_id, cursor.pit_id);
+        assert_eq!(decoded.search_after, cursor.search_after);
+    }
+
+    #[test]
+    fn encodes_url_safe_tokens() {
+        let cursor = SearchCursor {
+            pit_id: "?>?>?>".to_string(),
+            search_after: vec![],
+        };
+
+        let token = cursor.encode();
+
+        assert!(!token.contains(['+', '/', '=']));
+    }
+
+    #[test]
+    fn rejects_invalid_tokens() {
+        assert!(SearchCursor::decode("not a cursor!").is_none());
+        assert!(SearchCursor::decode(&URL_SAFE_NO_PAD.encode("{}")).is_none());
+        assert!(SearchCursor::decode(&URL_SAFE_NO_PAD.encode("[1, 2]")).is_none());
+        assert!(SearchCursor::decode("").is_none());
+    }
+}
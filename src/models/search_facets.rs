@@ -0,0 +1,132 @@
+#[derive(serde::Serialize)]
+pub struct FacetBucket {
+    pub key: serde_json::Value,
+    pub count: u64,
+}
+
+/// Result counts per filter value, for the same query as the search hits.
+#[derive(serde::Serialize)]
+pub struct SearchFacets {
+    pub statuses: Vec<FacetBucket>,
+    pub modes: Vec<FacetBucket>,
+    pub genres: Vec<FacetBucket>,
+    pub languages: Vec<FacetBucket>,
+    /// Star rating buckets, one star wide, keyed by their lower bound.
+    pub stars: Vec<FacetBucket>,
+}
+
+fn buckets(aggregations: &serde_json::Value, name: &str) -> Vec<FacetBucket> {
+    aggregations
+        .pointer(&format!("/{}/buckets", name))
+        .and_then(|buckets| buckets.as_array())
+        .map(|buckets| {
+            buckets
+                .iter()
+                .map(|bucket| FacetBucket {
+                    key: bucket.get("key").cloned().unwrap_or_default(),
+                    count: bucket
+                        .get("doc_count")
+                        .and_then(|count| count.as_u64())
+                        .unwrap_or_default(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl SearchFacets {
+    /// The aggregations to request alongside a search for its facets.
+    pub fn aggregations() -> serde_json::Value {
+        serde_json::json!({
+            "statuses": { "terms": { "field": "data.status", "size": 10 } },
+            "modes": { "terms": { "field": "data.beatmaps.mode", "size": 4 } },
+            "genres": { "terms": { "field": "data.genre", "size": 20 } },
+            "languages": { "terms": { "field": "data.language", "size": 20 } },
+            "stars": {
+                "histogram": {
+                    "field": "data.beatmaps.difficulty_rating",
+                    "interval": 1,
+                    "min_doc_count": 1
+                }
+            }
+        })
+    }
+
+    pub fn from_aggregations(aggregations: &serde_json::Value) -> Self {
+        Self {
+            statuses: buckets(aggregations, "statuses"),
+            modes: buckets(aggregations, "modes"),
+            genres: buckets(aggregations, "genres"),
+            languages: buckets(aggregations, "languages"),
+            stars: buckets(aggregations, "stars"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys_and_counts(buckets: &[FacetBucket]) -> Vec<(serde_json::Value, u64)> {
+        buckets
+            .iter()
+            .map(|bucket| (bucket.key.clone(), bucket.count))
+            .collect()
+    }
+
+    #[test]
+    fn reads_buckets_from_aggregations() {
+        let aggregations = serde_json::json!({
+            "statuses": {
+                "doc_count_error_upper_bound": 0,
+                "sum_other_doc_count": 0,
+                "buckets": [
+                    { "key": 1, "doc_count": 120 },
+                    { "key": 4, "doc_count": 8 }
+                ]
+            },
+            "modes": {
+                "buckets": [
+                    { "key": 0, "doc_count": 100 },
+                    { "key": 3, "doc_count": 30 }
+                ]
+            },
+            "genres": { "buckets": [{ "key": 2, "doc_count": 40 }] },
+            "languages": { "buckets": [] },
+            "stars": {
+                "buckets": [
+                    { "key": 1.0, "doc_count": 12 },
+                    { "key": 5.0, "doc_count": 3 }
+                ]
+            }
+        });
+
+        let facets = SearchFacets::from_aggregations(&aggregations);
+
+        assert_eq!(
+            keys_and_counts(&facets.statuses),
+            [(1.into(), 120), (4.into(), 8)]
+        );
+        assert_eq!(
+            keys_and_counts(&facets.modes),
+            [(0.into(), 100), (3.into(), 30)]
+        );
+        assert_eq!(keys_and_counts(&facets.genres), [(2.into(), 40)]);
+        assert!(facets.languages.is_empty());
+        assert_eq!(
+            keys_and_counts(&facets.stars),
+            [(1.0.into(), 12), (5.0.into(), 3)]
+        );
+    }
+
+    #[test]
+    fn missing_aggregations_are_empty() {
+        let facets = SearchFacets::from_aggregations(&serde_json::json!({
+            "statuses": { "buckets": [{ "key": 1 }] }
+        }));
+
+        assert_eq!(keys_and_counts(&facets.statuses), [(1.into(), 0)]);
+        assert!(facets.modes.is_empty());
+        assert!(facets.stars.is_empty());
+    }
+}
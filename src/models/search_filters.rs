@@ -0,0 +1,28 @@
+use super::{mode::Mode, ranked_status::RankedStatus};
+
+/// Filters shared by search and the other endpoints picking from search results.
+#[derive(Default)]
+pub struct SearchFilters {
+    pub query: Option<String>,
+    pub statuses: Vec<RankedStatus>,
+    pub modes: Vec<Mode>,
+    /// Key counts of mania difficulties.
+    pub keys: Vec<u8>,
+    /// Whether converted difficulties count when filtering by mode, keys, star rating or length.
+    pub include_converts: bool,
+    pub genres: Vec<u8>,
+    pub languages: Vec<u8>,
+    /// NSFW beatmapsets are left out unless this is set.
+    pub include_nsfw: bool,
+    pub video: Option<bool>,
+    pub storyboard: Option<bool>,
+    /// Bounds on the star rating. A beatmapset matches if a single difficulty meets these, the
+    /// length bounds, the modes and the key counts together.
+    pub min_stars: Option<f32>,
+    pub max_stars: Option<f32>,
+    /// Bounds on the total length, in seconds.
+    pub min_length: Option<u32>,
+    pub max_length: Option<u32>,
+    /// Sets created by this user, or with guest difficulties by them.
+    pub mapper_id: Option<u32>,
+}
@@ -0,0 +1,156 @@
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortField {
+    Relevance,
+    Ranked,
+    Updated,
+    Plays,
+    Favourites,
+    Title,
+    Artist,
+    Stars,
+    Id,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SearchSort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Default for SearchSort {
+    fn default() -> Self {
+        Self {
+            field: SortField::Relevance,
+            descending: true,
+        }
+    }
+}
+
+impl SearchSort {
+    /// Names accepted by [`SearchSort::parse`], each optionally followed by `_asc` or `_desc`.
+    pub const NAMES: [&'static str; 9] = [
+        "relevance",
+        "ranked",
+        "updated",
+        "plays",
+        "favourites",
+        "title",
+        "artist",
+        "stars",
+        "id",
+    ];
+
+    /// Parse a sort such as `plays_desc` or `title_asc`, descending if no order is given.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.to_lowercase();
+        let (field, descending) = match value.rsplit_once('_') {
+            Some((field, "asc")) => (field, false),
+            Some((field, "desc")) => (field, true),
+            _ => (value.as_str(), true),
+        };
+
+        let field = match field {
+            "relevance" => SortField::Relevance,
+            "ranked" => SortField::Ranked,
+            "updated" => SortField::Updated,
+            "plays" => SortField::Plays,
+            "favourites" => SortField::Favourites,
+            "title" => SortField::Title,
+            "artist" => SortField::Artist,
+            "stars" => SortField::Stars,
+            "id" => SortField::Id,
+            _ => return None,
+        };
+
+        Some(Self { field, descending })
+    }
+
+    /// The elasticsearch sort clause for beatmapset documents.
+    pub fn clause(&self) -> serde_json::Value {
+        let order = match self.descending {
+            true => "desc",
+            false => "asc",
+        };
+
+        match self.field {
+            SortField::Relevance => serde_json::json!({ "_score": order }),
+            SortField::Stars => serde_json::json!({
+                "data.beatmaps.difficulty_rating": {
+                    "order": order,
+                    // sort by the hardest difficulty going down, and the easiest going up
+                    "mode": if self.descending { "max" } else { "min" }
+                }
+            }),
+            field => {
+                let field = match field {
+                    SortField::Ranked => "data.ranked_date",
+                    SortField::Updated => "data.last_updated",
+                    SortField::Plays => "data.play_count",
+                    SortField::Favourites => "data.favourite_count",
+                    SortField::Title => "data.title.keyword",
+                    SortField::Artist => "data.artist.keyword",
+                    _ => "data.id",
+                };
+
+                serde_json::json!({ field: { "order": order, "missing": "_last" } })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fields_and_orders() {
+        assert_eq!(
+            SearchSort::parse("plays_asc"),
+            Some(SearchSort {
+                field: SortField::Plays,
+                descending: false
+            })
+        );
+        assert_eq!(
+            SearchSort::parse("Title_DESC"),
+            Some(SearchSort {
+                field: SortField::Title,
+                descending: true
+            })
+        );
+    }
+
+    #[test]
+    fn defaults_to_descending() {
+        assert_eq!(
+            SearchSort::parse("favourites"),
+            Some(SearchSort {
+                field: SortField::Favourites,
+                descending: true
+            })
+        );
+    }
+
+    #[test]
+    fn parses_every_listed_name() {
+        for name in SearchSort::NAMES {
+            assert!(SearchSort::parse(name).is_some(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_sorts() {
+        assert_eq!(SearchSort::parse("length"), None);
+        assert_eq!(SearchSort::parse("plays_sideways"), None);
+        assert_eq!(SearchSort::parse("_asc"), None);
+    }
+
+    #[test]
+    fn sorts_stars_by_the_hardest_difficulty_going_down() {
+        let descending = SearchSort::parse("stars_desc").unwrap().clause();
+        let ascending = SearchSort::parse("stars_asc").unwrap().clause();
+
+        assert_eq!(descending["data.beatmaps.difficulty_rating"]["mode"], "max");
+        assert_eq!(ascending["data.beatmaps.difficulty_rating"]["mode"], "min");
+    }
+}
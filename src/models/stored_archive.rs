@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+
+/// Bookkeeping for a beatmapset whose archive is stored, used to decide what to evict.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredArchive {
+    pub beatmapset_id: u32,
+    /// The beatmapset's ranked status code when it was last checked, or `None` if it isn't
+    /// indexed
+    pub status: Option<i8>,
+    /// Bytes taken up by every stored variant of the archive, and the renders and preview made
+    /// from it
+    pub size: u64,
+    /// Whether the archive didn't match the beatmapset's checksums on osu! when last checked
+    #[serde(default)]
+    pub stale: bool,
+    pub downloads: u64,
+    pub stored_at: DateTime<Utc>,
+    /// When the archive was last downloaded from the mirror, or stored if it never was
+    pub last_downloaded_at: DateTime<Utc>,
+}
+
+/// Size and activity of the archive store. The size covers the whole store, while the counts
+/// only cover requests served by this api process since it started.
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ArchiveStoreStats {
+    pub archives: u64,
+    /// Bytes taken up by stored archives and the renders and previews made from them
+    pub size: u64,
+    /// Bytes stored archives may take up, if limited
+    pub budget: Option<u64>,
+    pub hits: u64,
+    pub misses: u64,
+    /// Share of archive requests served from the store
+    pub hit_rate: f64,
+    pub evictions: u64,
+    pub evicted_bytes: u64,
+    /// When this process started counting hits, misses and evictions
+    pub counted_since: DateTime<Utc>,
+}
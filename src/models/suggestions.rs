@@ -0,0 +1,6 @@
+#[derive(serde::Serialize)]
+pub struct Suggestions {
+    pub artists: Vec<String>,
+    pub titles: Vec<String>,
+    pub creators: Vec<String>,
+}
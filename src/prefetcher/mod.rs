@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use crate::{
+    helpers::elastic,
+    models::{ranked_status::RankedStatus, search_filters::SearchFilters, search_sort::SearchSort},
+    repositories::{self, beatmapsets::Pagination},
+    usecases, Context,
+};
+
+/// How long to wait after every ranked, approved and loved archive has been looked at, or the
+/// store has filled up, before looking for newly ranked ones or room freed by evictions.
+const CYCLE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const PAGE_SIZE: u64 = 100;
+
+/// Ids of every ranked, approved and loved beatmapset that osu! allows downloading, most popular
+/// first.
+async fn prefetch_ids(ctx: &Context, sort: SearchSort) -> anyhow::Result<Vec<u32>> {
+    let filters = SearchFilters {
+        statuses: vec![
+            RankedStatus::Ranked,
+            RankedStatus::Approved,
+            RankedStatus::Loved,
+        ],
+        include_nsfw: true,
+        ..Default::default()
+    };
+
+    let mut beatmapset_ids = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let page = repositories::beatmapsets::search(
+            ctx,
+            &filters,
+            sort,
+            PAGE_SIZE,
+            Pagination::Cursor(cursor),
+            false,
+        )
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("point-in-time expired while listing beatmapsets"))?;
+
+        // downloads disabled by osu! are refused by the mirror too, so they're never needed
+        beatmapset_ids.extend(
+            page.beatmapsets
+                .iter()
+                .filter(|beatmapset| !beatmapset.data.availability.download_disabled)
+                .map(|beatmapset| beatmapset.data.mapset_id),
+        );
+
+        cursor = match page.cursor {
+            Some(cursor) => Some(cursor),
+            None => break,
+        };
+    }
+
+    Ok(beatmapset_ids)
+}
+
+async fn prefetch_archives(ctx: &Context, sort: SearchSort) -> anyhow::Result<()> {
+    let download_interval = Duration::from_secs(60) / ctx.config.prefetch_per_minute.max(1);
+    let mut backoff_time = ctx.config.backoff_start;
+
+    loop {
+        // collect every id up front, since downloading a page can outlive its point-in-time
+        let beatmapset_ids = match prefetch_ids(ctx, sort).await {
+            Ok(beatmapset_ids) => {
+                backoff_time = ctx.config.backoff_start;
+                beatmapset_ids
+            }
+            Err(e) => {
+                log::error!(
+                    "error while listing beatmapsets to prefetch, retrying in {} seconds: {}",
+                    backoff_time,
+                    e
+                );
+
+                tokio::time::sleep(Duration::from_secs(backoff_time as u64)).await;
+                if backoff_time < ctx.config.max_backoff {
+                    backoff_time = backoff_time.powf(2_f64).min(ctx.config.max_backoff);
+                }
+
+                continue;
+            }
+        };
+
+        log::info!(
+            "starting archive prefetch cycle over {} beatmapsets",
+            beatmapset_ids.len()
+        );
+
+        let mut downloaded = 0;
+
+        for beatmapset_id in beatmapset_ids {
+            // archives that couldn't be looked at are retried next cycle
+            match repositories::archives::exists(ctx, beatmapset_id, false).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    log::error!(
+                        "error while checking for archive of beatmapset {}: {}",
+                        beatmapset_id,
+                        e
+                    );
+                    continue;
+                }
+            }
+
+            // prefetched archives would only evict ones that were actually downloaded
+            match usecases::archives::has_room(ctx).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    log::info!("archive store is full, pausing prefetch until the next cycle");
+                    break;
+                }
+                Err(e) => {
+                    log::error!("error while checking the archive store's size: {}", e);
+                    tokio::time::sleep(download_interval).await;
+                    continue;
+                }
+            }
+
+            match usecases::archives::fetch(ctx, beatmapset_id, false).await {
+                Ok(Some(_)) => downloaded += 1,
+                Ok(None) => log::warn!("beatmapset {} has no archive on osu!", beatmapset_id),
+                Err(e) => log::error!(
+                    "error while prefetching archive for beatmapset {}: {}",
+                    beatmapset_id,
+                    e
+                ),
+            }
+
+            tokio::time::sleep(download_interval).await;
+        }
+
+        log::info!(
+            "finished archive prefetch cycle, downloaded {} archives",
+            downloaded
+        );
+
+        tokio::time::sleep(CYCLE_INTERVAL).await;
+    }
+}
+
+pub async fn serve(context: Context) -> anyhow::Result<()> {
+    if context.config.archive_path.is_none() {
+        anyhow::bail!("archive storage is not configured");
+    }
+
+    elastic::create_index_if_not_exists(&context.database, &context.config.elastic_archives_index)
+        .await?;
+
+    let sort = SearchSort::parse(&context.config.prefetch_order).ok_or_else(|| {
+        anyhow::anyhow!("unknown prefetch order: {}", context.config.prefetch_order)
+    })?;
+
+    prefetch_archives(&context, sort).await
+}
@@ -0,0 +1,27 @@
+use crate::{
+    helpers::elastic::{self, ElasticDocument},
+    models::api_key::ApiKey,
+    Context,
+};
+
+pub async fn fetch(ctx: &Context, key: &str) -> anyhow::Result<Option<ApiKey>> {
+    elastic::get(&ctx.database, &ctx.config.elastic_api_keys_index, key)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to fetch api key: {}", e))
+}
+
+pub async fn create(ctx: &Context, api_key: ApiKey) -> anyhow::Result<()> {
+    let elastic_document = ElasticDocument {
+        id: api_key.key.clone(),
+        data: api_key,
+    };
+
+    elastic::create(
+        &ctx.database,
+        &ctx.config.elastic_api_keys_index,
+        elastic_document,
+    )
+    .await?;
+
+    Ok(())
+}
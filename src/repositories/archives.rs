@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use axum::body::Bytes;
+
+use crate::{helpers::files, Context};
+
+fn archive_path(ctx: &Context, beatmapset_id: u32, no_video: bool) -> Option<PathBuf> {
+    let file_name = match no_video {
+        true => format!("{}_novideo.osz", beatmapset_id),
+        false => format!("{}.osz", beatmapset_id),
+    };
+
+    ctx.config
+        .archive_path
+        .as_ref()
+        .map(|archive_path| PathBuf::from(archive_path).join(file_name))
+}
+
+/// List the ids of the beatmapsets with either variant of their archive stored.
+pub async fn stored_ids(ctx: &Context) -> anyhow::Result<Vec<u32>> {
+    let archive_path = match &ctx.config.archive_path {
+        Some(archive_path) => archive_path,
+        None => return Ok(vec![]),
+    };
+
+    let mut beatmapset_ids = Vec::new();
+    let mut entries = tokio::fs::read_dir(archive_path).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name();
+        let beatmapset_id = file_name
+            .to_str()
+            .and_then(|file_name| file_name.strip_suffix(".osz"))
+            .map(|file_name| file_name.trim_end_matches("_novideo"))
+            .and_then(|beatmapset_id| beatmapset_id.parse::<u32>().ok());
+
+        if let Some(beatmapset_id) = beatmapset_id {
+            beatmapset_ids.push(beatmapset_id);
+        }
+    }
+
+    beatmapset_ids.sort_unstable();
+    beatmapset_ids.dedup();
+
+    Ok(beatmapset_ids)
+}
+
+pub async fn get(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+) -> anyhow::Result<Option<Bytes>> {
+    let path = match archive_path(ctx, beatmapset_id, no_video) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    match tokio::fs::read(&path).await {
+        Ok(archive) => Ok(Some(Bytes::from(archive))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(
+            "failed to read archive {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+pub async fn exists(ctx: &Context, beatmapset_id: u32, no_video: bool) -> anyhow::Result<bool> {
+    Ok(size(ctx, beatmapset_id, no_video).await?.is_some())
+}
+
+/// Bytes taken up by a stored archive, if it's stored.
+pub async fn size(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+) -> anyhow::Result<Option<u64>> {
+    let path = match archive_path(ctx, beatmapset_id, no_video) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    match tokio::fs::metadata(&path).await {
+        Ok(metadata) => Ok(Some(metadata.len())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(
+            "failed to check archive {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+pub async fn store(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+    archive: &[u8],
+) -> anyhow::Result<()> {
+    let path = match archive_path(ctx, beatmapset_id, no_video) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    files::write_atomically(&path, archive).await
+}
+
+pub async fn delete(ctx: &Context, beatmapset_id: u32, no_video: bool) -> anyhow::Result<()> {
+    let path = match archive_path(ctx, beatmapset_id, no_video) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    match tokio::fs::remove_file(&path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(anyhow::anyhow!(
+            "failed to delete archive {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
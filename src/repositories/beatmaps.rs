@@ -1,8 +1,54 @@
 use crate::{
     helpers::elastic::{self, ElasticDocument},
-    models::beatmap::Beatmap,
+    models::{
+        beatmap::Beatmap, mode::Mode, parsed_beatmap::ParsedBeatmap, ranked_status::RankedStatus,
+        search_filters::SearchFilters,
+    },
     Context,
 };
+use elasticsearch::SearchParts;
+use elasticsearch_dsl::{Query, Search};
+use std::collections::HashMap;
+
+pub async fn fetch(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beatmap>> {
+    let query = Search::new()
+        .query(Query::term("data.id", beatmap_id))
+        .size(1);
+
+    let elastic_response = ctx
+        .database
+        .search(SearchParts::Index(&[&ctx.config.elastic_beatmaps_index]))
+        .body(query)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to search for beatmap: {}", e))?;
+
+    let beatmap: Option<Beatmap> = elastic_response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to search for beatmap: {}", e))?
+        .pointer("/hits/hits/0/_source")
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+    Ok(beatmap)
+}
+
+pub async fn fetch_many(
+    ctx: &Context,
+    beatmap_ids: &[u32],
+) -> anyhow::Result<HashMap<u32, Beatmap>> {
+    let ids: Vec<String> = beatmap_ids.iter().map(|id| id.to_string()).collect();
+
+    let beatmaps: Vec<Beatmap> =
+        elastic::mget(&ctx.database, &ctx.config.elastic_beatmaps_index, &ids)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to fetch beatmaps: {}", e))?;
+
+    Ok(beatmaps
+        .into_iter()
+        .map(|beatmap| (beatmap.data.map_id, beatmap))
+        .collect())
+}
 
 pub async fn create(ctx: &Context, beatmap: Beatmap) -> anyhow::Result<()> {
     let elastic_document = ElasticDocument {
@@ -56,3 +102,187 @@ pub async fn update(ctx: &Context, beatmap: Beatmap) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+pub async fn set_parsed(
+    ctx: &Context,
+    beatmap_id: u32,
+    parsed: ParsedBeatmap,
+) -> anyhow::Result<()> {
+    let elastic_document = ElasticDocument {
+        id: beatmap_id.to_string(),
+        data: serde_json::json!({ "parsed": parsed }),
+    };
+
+    elastic::update(
+        &ctx.database,
+        &ctx.config.elastic_beatmaps_index,
+        elastic_document,
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete(ctx: &Context, beatmap_id: u32) -> anyhow::Result<()> {
+    elastic::delete(
+        &ctx.database,
+        &ctx.config.elastic_beatmaps_index,
+        &beatmap_id.to_string(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_by_beatmapset(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    let json_query = serde_json::json!({
+        "query": { "term": { "data.beatmapset_id": beatmapset_id } }
+    });
+
+    elastic::delete_by_query(
+        &ctx.database,
+        &ctx.config.elastic_beatmaps_index,
+        json_query,
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_hidden_by_beatmapset(
+    ctx: &Context,
+    beatmapset_id: u32,
+    hidden: bool,
+) -> anyhow::Result<()> {
+    let json_query = serde_json::json!({
+        "query": { "term": { "data.beatmapset_id": beatmapset_id } },
+        "script": {
+            "source": "ctx._source.hidden = params.hidden",
+            "params": { "hidden": hidden }
+        }
+    });
+
+    elastic::update_by_query(
+        &ctx.database,
+        &ctx.config.elastic_beatmaps_index,
+        json_query,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Build a query over single difficulties from the filters which apply to them.
+fn filter_query(filters: &SearchFilters) -> serde_json::Value {
+    let mut query_conditions: Vec<serde_json::Value> = Vec::new();
+
+    if !filters.modes.is_empty() && !filters.modes.contains(&Mode::All) {
+        let modes: Vec<i8> = filters.modes.iter().map(|mode| *mode as i8).collect();
+
+        query_conditions.push(serde_json::json!({ "terms": { "data.mode": modes } }));
+    }
+
+    if !filters.statuses.is_empty() && !filters.statuses.contains(&RankedStatus::All) {
+        let statuses: Vec<i8> = filters
+            .statuses
+            .iter()
+            .map(|status| *status as i8)
+            .collect();
+
+        query_conditions.push(serde_json::json!({ "terms": { "data.status": statuses } }));
+    }
+
+    if !filters.keys.is_empty() {
+        query_conditions.push(serde_json::json!({ "term": { "data.mode": Mode::Mania as i8 } }));
+        query_conditions.push(serde_json::json!({ "terms": { "data.cs": filters.keys } }));
+    }
+
+    if filters.min_stars.is_some() || filters.max_stars.is_some() {
+        query_conditions.push(serde_json::json!({
+            "range": {
+                "data.difficulty_rating": {
+                    "gte": filters.min_stars,
+                    "lte": filters.max_stars
+                }
+            }
+        }));
+    }
+
+    if filters.min_length.is_some() || filters.max_length.is_some() {
+        query_conditions.push(serde_json::json!({
+            "range": {
+                "data.total_length": {
+                    "gte": filters.min_length,
+                    "lte": filters.max_length
+                }
+            }
+        }));
+    }
+
+    let mut excluded_conditions = vec![serde_json::json!({ "term": { "hidden": true } })];
+
+    // beatmaps don't always embed their beatmapset, so this only narrows down the candidates
+    if !filters.include_nsfw {
+        excluded_conditions.push(serde_json::json!({ "term": { "data.beatmapset.nsfw": true } }));
+    }
+
+    serde_json::json!({
+        "bool": {
+            "must": query_conditions,
+            "must_not": excluded_conditions
+        }
+    })
+}
+
+/// Pick random beatmaps matching the filters, leaving out the given beatmapsets.
+///
+/// With `per_beatmapset`, difficulties of the same beatmapset are scored alike, so every
+/// matching beatmapset is as likely to come first however many of its difficulties match.
+pub async fn random(
+    ctx: &Context,
+    filters: &SearchFilters,
+    seed: u32,
+    weighted: bool,
+    per_beatmapset: bool,
+    excluded_beatmapset_ids: &[u32],
+    amount: u64,
+) -> anyhow::Result<Vec<Beatmap>> {
+    let mut query = filter_query(filters);
+    if !excluded_beatmapset_ids.is_empty() {
+        query["bool"]["must_not"].as_array_mut().unwrap().push(
+            serde_json::json!({ "terms": { "data.beatmapset_id": excluded_beatmapset_ids } }),
+        );
+    }
+
+    let (seed_field, weight_field) = match per_beatmapset {
+        true => ("data.beatmapset_id", "data.beatmapset.play_count"),
+        false => ("data.id", "data.playcount"),
+    };
+
+    let json_query = serde_json::json!({
+        "query": elastic::random_query(query, seed, seed_field, weighted.then_some(weight_field)),
+        "size": amount
+    });
+
+    let beatmaps = ctx
+        .database
+        .search(SearchParts::Index(&[&ctx.config.elastic_beatmaps_index]))
+        .body(json_query)
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to pick a random beatmap: {}", e))?
+        .pointer("/hits/hits")
+        .and_then(|hits| hits.as_array())
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| hit.get("_source"))
+                .filter_map(|source| serde_json::from_value(source.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(beatmaps)
+}
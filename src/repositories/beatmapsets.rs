@@ -1,15 +1,144 @@
 use crate::{
     helpers::elastic::{self, ElasticDocument},
-    models::{beatmapset::Beatmapset, mode::Mode, ranked_status::RankedStatus},
+    models::{
+        beatmapset::Beatmapset, mode::Mode, ranked_status::RankedStatus,
+        search_cursor::SearchCursor, search_facets::SearchFacets, search_filters::SearchFilters,
+        search_sort::SearchSort, suggestions::Suggestions,
+    },
     Context,
 };
 
-use elasticsearch::SearchParts;
+use elasticsearch::{params::Conflicts, SearchParts, UpdateByQueryParts};
+use elasticsearch_dsl::{Query, Search};
+use rosu_v2::prelude::{Beatmap as OsuBeatmap, GameMode};
+use std::collections::{HashMap, HashSet};
+
+pub async fn fetch(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Beatmapset>> {
+    let query = Search::new()
+        .query(Query::term("data.id", beatmapset_id))
+        .size(1);
+
+    let elastic_response = ctx
+        .database
+        .search(SearchParts::Index(&[&ctx.config.elastic_beatmapsets_index]))
+        .body(query)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to search for beatmapset: {}", e))?;
+
+    let beatmapset: Option<Beatmapset> = elastic_response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to search for beatmapset: {}", e))?
+        .pointer("/hits/hits/0/_source")
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+    Ok(beatmapset)
+}
+
+pub async fn fetch_many(
+    ctx: &Context,
+    beatmapset_ids: &[u32],
+) -> anyhow::Result<HashMap<u32, Beatmapset>> {
+    let ids: Vec<String> = beatmapset_ids.iter().map(|id| id.to_string()).collect();
+
+    let beatmapsets: Vec<Beatmapset> =
+        elastic::mget(&ctx.database, &ctx.config.elastic_beatmapsets_index, &ids)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to fetch beatmapsets: {}", e))?;
+
+    Ok(beatmapsets
+        .into_iter()
+        .map(|beatmapset| (beatmapset.data.mapset_id, beatmapset))
+        .collect())
+}
+
+/// A beatmapset as it's indexed, with the fields search filters on worked out up front.
+#[derive(serde::Serialize)]
+struct IndexedBeatmapset {
+    #[serde(flatten)]
+    beatmapset: Beatmapset,
+    /// The difficulties and converts, mapped as nested so a search matches them one at a time
+    difficulties: Vec<IndexedDifficulty>,
+}
+
+/// What search filters a single difficulty or convert on.
+#[derive(serde::Serialize)]
+struct IndexedDifficulty {
+    mode: GameMode,
+    difficulty_rating: f32,
+    total_length: u32,
+    /// Key count, only for mania difficulties and mania converts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keys: Option<u8>,
+    convert: bool,
+}
+
+impl IndexedDifficulty {
+    fn new(beatmap: &OsuBeatmap, convert: bool) -> Self {
+        Self {
+            mode: beatmap.mode,
+            difficulty_rating: beatmap.stars,
+            total_length: beatmap.seconds_total,
+            // mania difficulties store their key count as cs, and the same holds for converts
+            keys: (beatmap.mode == GameMode::Mania).then(|| beatmap.cs.round() as u8),
+            convert,
+        }
+    }
+}
+
+impl From<Beatmapset> for IndexedBeatmapset {
+    fn from(beatmapset: Beatmapset) -> Self {
+        let difficulties = beatmapset
+            .data
+            .maps
+            .iter()
+            .flatten()
+            .map(|beatmap| IndexedDifficulty::new(beatmap, false))
+            .chain(
+                beatmapset
+                    .data
+                    .converts
+                    .iter()
+                    .flatten()
+                    .map(|beatmap| IndexedDifficulty::new(beatmap, true)),
+            )
+            .collect();
+
+        Self {
+            beatmapset,
+            difficulties,
+        }
+    }
+}
+
+/// Works out the fields of [`IndexedBeatmapset`] for documents indexed before they existed.
+const DIFFICULTIES_SCRIPT: &str = "
+    void addDifficulties(List difficulties, def beatmaps, boolean convert) {
+        for (def beatmap : beatmaps ?: []) {
+            Map difficulty = new HashMap();
+            difficulty.put('mode', beatmap.mode);
+            difficulty.put('difficulty_rating', beatmap.difficulty_rating);
+            difficulty.put('total_length', beatmap.total_length);
+            if (beatmap.mode == 3) {
+                difficulty.put('keys', Math.round(((Number) beatmap.cs).doubleValue()));
+            }
+            difficulty.put('convert', convert);
+            difficulties.add(difficulty);
+        }
+    }
+    List difficulties = new ArrayList();
+    addDifficulties(difficulties, ctx._source.data.beatmaps, false);
+    addDifficulties(difficulties, ctx._source.data.converts, true);
+    ctx._source.difficulties = difficulties;
+    ctx._source.remove('mania_keys');
+    ctx._source.remove('mania_keys_with_converts');
+";
 
 pub async fn create(ctx: &Context, beatmapset: Beatmapset) -> anyhow::Result<()> {
     let elastic_document = ElasticDocument {
         id: beatmapset.data.mapset_id.to_string(),
-        data: beatmapset,
+        data: IndexedBeatmapset::from(beatmapset),
     };
 
     elastic::create(
@@ -22,72 +151,484 @@ pub async fn create(ctx: &Context, beatmapset: Beatmapset) -> anyhow::Result<()>
     Ok(())
 }
 
-pub async fn search(
-    ctx: &Context,
-    query: Option<String>,
-    amount: u64,
-    offset: u64,
-    status: RankedStatus,
-    mode: Mode,
-) -> anyhow::Result<Vec<Beatmapset>> {
+/// A field search relies on, on top of elasticsearch's dynamic mapping.
+enum SearchField {
+    /// A sub-field of one of the beatmapset's text fields, e.g. `data.title.suggest`.
+    SubField(&'static str, &'static str, serde_json::Value),
+    /// A field worked out when indexing, next to `data`.
+    Field(&'static str, serde_json::Value),
+}
+
+impl SearchField {
+    fn pointer(&self) -> String {
+        match self {
+            Self::SubField(field, sub_field, _) => {
+                format!("/properties/data/properties/{}/fields/{}", field, sub_field)
+            }
+            Self::Field(field, _) => format!("/properties/{}", field),
+        }
+    }
+
+    /// Add the field to a mapping update.
+    fn add_to(self, mapping: &mut serde_json::Value) {
+        match self {
+            Self::SubField(field, sub_field, sub_field_mapping) => {
+                let field_mapping = &mut mapping["properties"]["data"]["properties"][field];
+                if field_mapping.is_null() {
+                    *field_mapping = serde_json::json!({
+                        "type": "text",
+                        "fields": { "keyword": { "type": "keyword", "ignore_above": 256 } }
+                    });
+                }
+
+                field_mapping["fields"][sub_field] = sub_field_mapping;
+            }
+            Self::Field(field, field_mapping) => mapping["properties"][field] = field_mapping,
+        }
+    }
+}
+
+fn search_fields() -> Vec<SearchField> {
+    // the cjk analyzer splits cjk text into bigrams, which the standard analyzer can't segment.
+    // icu analysis would segment by dictionary instead, but needs the analysis-icu plugin,
+    // which the stock elasticsearch image doesn't ship, while the cjk analyzer is built in
+    let cjk = serde_json::json!({ "type": "text", "analyzer": "cjk" });
+    // completion fields back the suggest endpoint
+    let completion = serde_json::json!({ "type": "completion" });
+    // types as dynamic mapping gives the same fields under data.beatmaps
+    let difficulties = serde_json::json!({
+        "type": "nested",
+        "properties": {
+            "mode": { "type": "long" },
+            "difficulty_rating": { "type": "float" },
+            "total_length": { "type": "long" },
+            "keys": { "type": "long" },
+            "convert": { "type": "boolean" }
+        }
+    });
+
+    vec![
+        SearchField::SubField("title_unicode", "cjk", cjk.clone()),
+        SearchField::SubField("artist_unicode", "cjk", cjk),
+        SearchField::SubField("artist", "suggest", completion.clone()),
+        SearchField::SubField("title", "suggest", completion.clone()),
+        SearchField::SubField("creator", "suggest", completion),
+        SearchField::Field("difficulties", difficulties),
+    ]
+}
+
+/// Add the fields search relies on to the beatmapsets index if they're missing, and start a
+/// throttled reindex so existing documents get them.
+pub async fn put_search_mappings(ctx: &Context) -> anyhow::Result<()> {
+    let index = &ctx.config.elastic_beatmapsets_index;
+
+    let mapping = elastic::get_mapping(&ctx.database, index).await?;
+    let missing_fields: Vec<SearchField> = search_fields()
+        .into_iter()
+        .filter(|field| mapping.pointer(&field.pointer()).is_none())
+        .collect();
+
+    if missing_fields.is_empty() {
+        return Ok(());
+    }
+
+    let mut new_mapping = serde_json::json!({});
+    for field in missing_fields {
+        field.add_to(&mut new_mapping);
+    }
+
+    elastic::put_mapping(&ctx.database, index, new_mapping).await?;
+
+    // reindex existing documents in place so they get the new fields
+    let task = ctx
+        .database
+        .update_by_query(UpdateByQueryParts::Index(&[index]))
+        .body(serde_json::json!({
+            "script": { "source": DIFFICULTIES_SCRIPT, "lang": "painless" }
+        }))
+        .conflicts(Conflicts::Proceed)
+        .requests_per_second(ctx.config.reindex_requests_per_second as i64)
+        .wait_for_completion(false)
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json::<serde_json::Value>()
+        .await?;
+
+    log::info!(
+        "added search mappings to {}, reindexing in task {}",
+        index,
+        task.get("task")
+            .and_then(|task| task.as_str())
+            .unwrap_or("unknown")
+    );
+
+    Ok(())
+}
+
+fn suggestion_options(suggest: &serde_json::Value, name: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    suggest
+        .pointer(&format!("/{}/0/options", name))
+        .and_then(|options| options.as_array())
+        .map(|options| options.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|option| {
+            let flag = |pointer: &str| {
+                option
+                    .pointer(pointer)
+                    .and_then(|flag| flag.as_bool())
+                    .unwrap_or(false)
+            };
+
+            !flag("/_source/hidden") && !flag("/_source/data/nsfw")
+        })
+        .filter_map(|option| option.get("text").and_then(|text| text.as_str()))
+        // completions are only deduplicated exactly, so also catch differently cased ones
+        .filter(|text| seen.insert(text.to_lowercase()))
+        .map(|text| text.to_string())
+        .collect()
+}
+
+/// Complete artists, titles and mappers starting with the given text, leaving out hidden and
+/// NSFW beatmapsets.
+///
+/// Those are filtered out after elasticsearch has skipped duplicate completions, so a text
+/// shared by a hidden or NSFW beatmapset and a visible one may be left out too.
+pub async fn suggest(ctx: &Context, prefix: &str, amount: u64) -> anyhow::Result<Suggestions> {
+    let completion = |field: &str| {
+        serde_json::json!({
+            "prefix": prefix,
+            "completion": {
+                "field": field,
+                // leave room for the hidden, NSFW and differently cased ones filtered out after
+                "size": amount * 2,
+                "skip_duplicates": true
+            }
+        })
+    };
+
+    let json_query = serde_json::json!({
+        "_source": ["hidden", "data.nsfw"],
+        "suggest": {
+            "artists": completion("data.artist.suggest"),
+            "titles": completion("data.title.suggest"),
+            "creators": completion("data.creator.suggest")
+        }
+    });
+
+    let suggest = ctx
+        .database
+        .search(SearchParts::Index(&[&ctx.config.elastic_beatmapsets_index]))
+        .body(json_query)
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to get suggestions: {}", e))?
+        .get("suggest")
+        .cloned()
+        .unwrap_or_default();
+
+    let limit = |mut options: Vec<String>| {
+        options.truncate(amount as usize);
+        options
+    };
+
+    Ok(Suggestions {
+        artists: limit(suggestion_options(&suggest, "artists")),
+        titles: limit(suggestion_options(&suggest, "titles")),
+        creators: limit(suggestion_options(&suggest, "creators")),
+    })
+}
+
+/// How long a point-in-time stays open between search pages.
+const SEARCH_KEEP_ALIVE: &str = "5m";
+
+pub enum Pagination {
+    /// Skip a number of results, only usable within the first 10,000.
+    Offset(u64),
+    /// Continue from a cursor, or open a new point-in-time for the first page.
+    Cursor(Option<SearchCursor>),
+}
+
+pub struct SearchPage {
+    pub beatmapsets: Vec<Beatmapset>,
+    pub total: u64,
+    /// Where the next page starts, if there might be one.
+    pub cursor: Option<SearchCursor>,
+    pub facets: Option<SearchFacets>,
+}
+
+/// Build a query matching beatmapsets with a difficulty that meets every per-difficulty filter,
+/// or `None` if there are none.
+fn difficulty_query(filters: &SearchFilters) -> Option<serde_json::Value> {
+    let mut conditions: Vec<serde_json::Value> = Vec::new();
+
+    if !filters.modes.is_empty() && !filters.modes.contains(&Mode::All) {
+        let modes: Vec<i8> = filters.modes.iter().map(|mode| *mode as i8).collect();
+        conditions.push(serde_json::json!({ "terms": { "difficulties.mode": modes } }));
+    }
+
+    if !filters.keys.is_empty() {
+        conditions.push(serde_json::json!({ "terms": { "difficulties.keys": filters.keys } }));
+    }
+
+    if filters.min_stars.is_some() || filters.max_stars.is_some() {
+        conditions.push(serde_json::json!({
+            "range": {
+                "difficulties.difficulty_rating": {
+                    "gte": filters.min_stars,
+                    "lte": filters.max_stars
+                }
+            }
+        }));
+    }
+
+    if filters.min_length.is_some() || filters.max_length.is_some() {
+        conditions.push(serde_json::json!({
+            "range": {
+                "difficulties.total_length": {
+                    "gte": filters.min_length,
+                    "lte": filters.max_length
+                }
+            }
+        }));
+    }
+
+    if conditions.is_empty() {
+        return None;
+    }
+
+    if !filters.include_converts {
+        conditions.push(serde_json::json!({ "term": { "difficulties.convert": false } }));
+    }
+
+    Some(serde_json::json!({
+        "nested": {
+            "path": "difficulties",
+            "query": { "bool": { "filter": conditions } }
+        }
+    }))
+}
+
+/// Build the search request body for the given filters, without any paging.
+fn search_body(filters: &SearchFilters) -> serde_json::Value {
     let mut query_conditions: Vec<serde_json::Value> = Vec::new();
 
-    if let Some(query) = query {
+    if let Some(query) = &filters.query {
+        let fields = [
+            "data.title^3",
+            "data.title_unicode^3",
+            "data.title_unicode.cjk^3",
+            "data.artist^2",
+            "data.artist_unicode^2",
+            "data.artist_unicode.cjk^2",
+            "data.creator^1.5",
+            "data.source",
+            "data.beatmaps.version",
+            "data.tags^0.5",
+        ];
+
+        // romanised and unicode fields are searched together, so either form of a title
+        // matches, and the phrase prefix clause matches partially typed input
         let field_query = serde_json::json!({
-            "simple_query_string": {
-                "query": query,
-                "fields": vec![
-                    "data.artist",
-                    "data.creator",
-                    "data.title",
-                    "data.title_unicode",
-                    "data.tags",
-                    "data.beatmaps.version"
-                ]
+            "bool": {
+                "should": [
+                    {
+                        "multi_match": {
+                            "query": query,
+                            "fields": fields,
+                            "type": "best_fields",
+                            "fuzziness": "AUTO",
+                            "prefix_length": 1
+                        }
+                    },
+                    {
+                        "multi_match": {
+                            "query": query,
+                            "fields": fields,
+                            "type": "phrase_prefix",
+                            "boost": 2
+                        }
+                    }
+                ],
+                "minimum_should_match": 1
             }
         });
 
         query_conditions.push(field_query);
     }
 
-    if mode != Mode::All {
+    if !filters.statuses.is_empty() && !filters.statuses.contains(&RankedStatus::All) {
+        let statuses: Vec<i8> = filters
+            .statuses
+            .iter()
+            .map(|status| *status as i8)
+            .collect();
+
+        query_conditions.push(serde_json::json!({
+            "terms": {
+                "data.status": statuses
+            }
+        }));
+    }
+
+    if !filters.genres.is_empty() {
+        query_conditions.push(serde_json::json!({
+            "terms": {
+                "data.genre": filters.genres
+            }
+        }));
+    }
+
+    if !filters.languages.is_empty() {
+        query_conditions.push(serde_json::json!({
+            "terms": {
+                "data.language": filters.languages
+            }
+        }));
+    }
+
+    if let Some(video) = filters.video {
         query_conditions.push(serde_json::json!({
             "term": {
-                "data.beatmaps.mode": mode as i8
+                "data.video": video
             }
         }));
     }
 
-    if status != RankedStatus::All {
+    if let Some(storyboard) = filters.storyboard {
         query_conditions.push(serde_json::json!({
             "term": {
-                "data.status": status as i8
+                "data.storyboard": storyboard
+            }
+        }));
+    }
+
+    if let Some(difficulty_query) = difficulty_query(filters) {
+        query_conditions.push(difficulty_query);
+    }
+
+    if let Some(mapper_id) = filters.mapper_id {
+        query_conditions.push(serde_json::json!({
+            "bool": {
+                "should": [
+                    { "term": { "data.user_id": mapper_id } },
+                    { "term": { "data.beatmaps.user_id": mapper_id } }
+                ],
+                "minimum_should_match": 1
             }
         }));
     }
 
-    let json_query =
-        serde_json::json!({"query": {"bool": {"must": serde_json::json!(query_conditions)}}});
+    let mut excluded_conditions = vec![serde_json::json!({ "term": { "hidden": true } })];
 
-    let elastic_response = ctx
-        .database
-        .search(SearchParts::Index(&[&ctx.config.elastic_beatmapsets_index]))
-        .body(json_query)
-        .size(amount as i64)
-        .from(offset as i64)
-        .send()
-        .await?;
+    if !filters.include_nsfw {
+        excluded_conditions.push(serde_json::json!({ "term": { "data.nsfw": true } }));
+    }
+
+    let mut body = serde_json::json!({});
+
+    body["query"] = serde_json::json!({
+        "bool": {
+            "must": query_conditions,
+            "must_not": excluded_conditions
+        }
+    });
+
+    body
+}
+
+/// Returns `None` if the cursor's point-in-time has expired.
+pub async fn search(
+    ctx: &Context,
+    filters: &SearchFilters,
+    sort: SearchSort,
+    amount: u64,
+    pagination: Pagination,
+    with_facets: bool,
+) -> anyhow::Result<Option<SearchPage>> {
+    let mut json_query = search_body(filters);
+    json_query["size"] = amount.into();
+    json_query["track_total_hits"] = true.into();
+
+    let continues_cursor = matches!(pagination, Pagination::Cursor(Some(_)));
+
+    // facets are the same for every page, so they're only worked out for the first one
+    let with_facets = with_facets
+        && match pagination {
+            Pagination::Offset(offset) => offset == 0,
+            Pagination::Cursor(_) => !continues_cursor,
+        };
+
+    if with_facets {
+        json_query["aggs"] = SearchFacets::aggregations();
+    }
+
+    let elastic_response = match pagination {
+        Pagination::Offset(offset) => {
+            json_query["from"] = offset.into();
+            json_query["sort"] = serde_json::json!([sort.clause()]);
+
+            ctx.database
+                .search(SearchParts::Index(&[&ctx.config.elastic_beatmapsets_index]))
+                .body(json_query)
+                .send()
+                .await?
+        }
+        Pagination::Cursor(cursor) => {
+            let cursor = match cursor {
+                Some(cursor) => cursor,
+                None => SearchCursor {
+                    pit_id: elastic::open_point_in_time(
+                        &ctx.database,
+                        &ctx.config.elastic_beatmapsets_index,
+                        SEARCH_KEEP_ALIVE,
+                    )
+                    .await?,
+                    search_after: vec![],
+                },
+            };
+
+            json_query["pit"] = serde_json::json!({
+                "id": cursor.pit_id,
+                "keep_alive": SEARCH_KEEP_ALIVE
+            });
+            // _shard_doc breaks ties between equally sorted hits within the point-in-time
+            json_query["sort"] = serde_json::json!([sort.clause(), { "_shard_doc": "asc" }]);
+            if !cursor.search_after.is_empty() {
+                json_query["search_after"] = cursor.search_after.into();
+            }
+
+            ctx.database
+                .search(SearchParts::None)
+                .body(json_query)
+                .send()
+                .await?
+        }
+    };
+
+    if continues_cursor && elastic_response.status_code() == 404 {
+        return Ok(None);
+    }
 
     let beatmapsets_json = elastic_response
+        .error_for_status_code()?
         .json::<serde_json::Value>()
         .await
         .map_err(|e| anyhow::anyhow!("failed to search for beatmap: {}", e))?;
 
-    let beatmaps: Vec<Beatmapset> = beatmapsets_json
+    let hits = beatmapsets_json
         .pointer("/hits/hits")
-        .unwrap_or(&serde_json::Value::Array(vec![]))
-        .as_array()
-        .unwrap_or(&vec![])
+        .and_then(|hits| hits.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let beatmapsets: Vec<Beatmapset> = hits
         .iter()
         .filter_map(|v| {
             v.pointer("/_source")
@@ -95,13 +636,50 @@ pub async fn search(
         })
         .collect();
 
-    Ok(beatmaps)
+    let total = beatmapsets_json
+        .pointer("/hits/total/value")
+        .and_then(|total| total.as_u64())
+        .unwrap_or_default();
+
+    // the point-in-time id can change between pages, so always continue from the latest one
+    let pit_id = beatmapsets_json
+        .get("pit_id")
+        .and_then(|pit_id| pit_id.as_str());
+
+    let cursor = match (pit_id, hits.last()) {
+        (Some(pit_id), Some(last_hit)) if hits.len() as u64 == amount => Some(SearchCursor {
+            pit_id: pit_id.to_string(),
+            search_after: last_hit
+                .get("sort")
+                .and_then(|sort| sort.as_array())
+                .cloned()
+                .unwrap_or_default(),
+        }),
+        (Some(pit_id), _) => {
+            // this was the last page, so the point-in-time isn't needed anymore
+            let _ = elastic::close_point_in_time(&ctx.database, pit_id).await;
+            None
+        }
+        _ => None,
+    };
+
+    let facets = beatmapsets_json
+        .get("aggregations")
+        .filter(|_| with_facets)
+        .map(SearchFacets::from_aggregations);
+
+    Ok(Some(SearchPage {
+        beatmapsets,
+        total,
+        cursor,
+        facets,
+    }))
 }
 
 pub async fn update(ctx: &Context, beatmapset: Beatmapset) -> anyhow::Result<()> {
     let elastic_document = ElasticDocument {
         id: beatmapset.data.mapset_id.to_string(),
-        data: beatmapset,
+        data: IndexedBeatmapset::from(beatmapset),
     };
 
     elastic::update(
@@ -113,3 +691,143 @@ pub async fn update(ctx: &Context, beatmapset: Beatmapset) -> anyhow::Result<()>
 
     Ok(())
 }
+
+pub async fn delete(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    elastic::delete(
+        &ctx.database,
+        &ctx.config.elastic_beatmapsets_index,
+        &beatmapset_id.to_string(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_hidden(ctx: &Context, beatmapset_id: u32, hidden: bool) -> anyhow::Result<()> {
+    let json_query = serde_json::json!({
+        "query": { "term": { "data.id": beatmapset_id } },
+        "script": {
+            "source": "ctx._source.hidden = params.hidden",
+            "params": { "hidden": hidden }
+        }
+    });
+
+    elastic::update_by_query(
+        &ctx.database,
+        &ctx.config.elastic_beatmapsets_index,
+        json_query,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_fields_are_found_where_they_are_added() {
+        let mut mapping = serde_json::json!({});
+        let pointers: Vec<String> = search_fields().iter().map(SearchField::pointer).collect();
+
+        for field in search_fields() {
+            field.add_to(&mut mapping);
+        }
+
+        for pointer in pointers {
+            assert!(mapping.pointer(&pointer).is_some(), "{}", pointer);
+        }
+        assert_eq!(
+            mapping.pointer("/properties/data/properties/title/fields/keyword/type"),
+            Some(&serde_json::json!("keyword"))
+        );
+    }
+
+    #[test]
+    fn suggestions_leave_out_hidden_nsfw_and_repeated_texts() {
+        let option = |text: &str, hidden: bool, nsfw: bool| {
+            serde_json::json!({
+                "text": text,
+                "_source": { "hidden": hidden, "data": { "nsfw": nsfw } }
+            })
+        };
+        let suggest = serde_json::json!({
+            "titles": [{
+                "options": [
+                    option("Bad Apple!!", false, false),
+                    option("Hidden", true, false),
+                    option("Explicit", false, true),
+                    option("bad apple!!", false, false),
+                    { "text": "Blue Zenith", "_source": {} }
+                ]
+            }]
+        });
+
+        assert_eq!(
+            suggestion_options(&suggest, "titles"),
+            ["Bad Apple!!", "Blue Zenith"]
+        );
+        assert!(suggestion_options(&suggest, "artists").is_empty());
+    }
+
+    #[test]
+    fn difficulty_filters_are_matched_together() {
+        let filters = SearchFilters {
+            modes: vec![Mode::Mania],
+            keys: vec![4, 7],
+            min_stars: Some(3.0),
+            max_length: Some(120),
+            ..Default::default()
+        };
+
+        let body = search_body(&filters);
+        let conditions = body
+            .pointer("/query/bool/must")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        let nested: Vec<&serde_json::Value> = conditions
+            .iter()
+            .filter_map(|condition| condition.get("nested"))
+            .collect();
+
+        assert_eq!(nested.len(), 1);
+        assert_eq!(nested[0]["path"], "difficulties");
+        assert_eq!(
+            nested[0]["query"]["bool"]["filter"],
+            serde_json::json!([
+                { "terms": { "difficulties.mode": [3] } },
+                { "terms": { "difficulties.keys": [4, 7] } },
+                { "range": { "difficulties.difficulty_rating": { "gte": 3.0, "lte": null } } },
+                { "range": { "difficulties.total_length": { "gte": null, "lte": 120 } } },
+                { "term": { "difficulties.convert": false } }
+            ])
+        );
+    }
+
+    #[test]
+    fn converts_count_when_included() {
+        let filters = SearchFilters {
+            modes: vec![Mode::Taiko],
+            include_converts: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            difficulty_query(&filters).unwrap()["nested"]["query"]["bool"]["filter"],
+            serde_json::json!([{ "terms": { "difficulties.mode": [1] } }])
+        );
+    }
+
+    #[test]
+    fn no_difficulty_query_without_difficulty_filters() {
+        let filters = SearchFilters {
+            modes: vec![Mode::All],
+            genres: vec![2],
+            ..Default::default()
+        };
+
+        assert!(difficulty_query(&filters).is_none());
+    }
+}
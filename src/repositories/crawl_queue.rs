@@ -0,0 +1,68 @@
+use crate::{
+    helpers::elastic::{self, ElasticDocument},
+    models::crawl_request::CrawlRequest,
+    Context,
+};
+use elasticsearch::{params::Refresh, DeleteParts, SearchParts};
+
+pub async fn push(ctx: &Context, crawl_request: CrawlRequest) -> anyhow::Result<()> {
+    let elastic_document = ElasticDocument {
+        id: format!(
+            "{:?}:{}-{}",
+            crawl_request.kind, crawl_request.start_id, crawl_request.end_id
+        ),
+        data: crawl_request,
+    };
+
+    elastic::create(
+        &ctx.database,
+        &ctx.config.elastic_crawl_queue_index,
+        elastic_document,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Returns the oldest queued request along with its document id.
+pub async fn next(ctx: &Context) -> anyhow::Result<Option<(String, CrawlRequest)>> {
+    let json_query = serde_json::json!({
+        "sort": [{ "created_at": "asc" }],
+        "size": 1
+    });
+
+    let elastic_response = ctx
+        .database
+        .search(SearchParts::Index(&[&ctx.config.elastic_crawl_queue_index]))
+        .body(json_query)
+        .send()
+        .await?;
+
+    let hit = elastic_response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to search crawl queue: {}", e))?
+        .pointer("/hits/hits/0")
+        .cloned();
+
+    Ok(hit.and_then(|hit| {
+        let id = hit.pointer("/_id")?.as_str()?.to_string();
+        let crawl_request = serde_json::from_value(hit.pointer("/_source")?.clone()).ok()?;
+
+        Some((id, crawl_request))
+    }))
+}
+
+pub async fn remove(ctx: &Context, id: &str) -> anyhow::Result<()> {
+    // wait for the removal to be visible so the request isn't picked up again
+    ctx.database
+        .delete(DeleteParts::IndexId(
+            &ctx.config.elastic_crawl_queue_index,
+            id,
+        ))
+        .refresh(Refresh::WaitFor)
+        .send()
+        .await?;
+
+    Ok(())
+}
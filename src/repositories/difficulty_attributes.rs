@@ -0,0 +1,35 @@
+use crate::{
+    helpers::elastic::{self, ElasticDocument},
+    models::difficulty_attributes::DifficultyAttributes,
+    Context,
+};
+
+pub async fn fetch(ctx: &Context, id: &str) -> anyhow::Result<Option<DifficultyAttributes>> {
+    elastic::get(
+        &ctx.database,
+        &ctx.config.elastic_difficulty_attributes_index,
+        id,
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("failed to fetch difficulty attributes: {}", e))
+}
+
+pub async fn store(ctx: &Context, attributes: DifficultyAttributes) -> anyhow::Result<()> {
+    let elastic_document = ElasticDocument {
+        id: DifficultyAttributes::document_id(
+            attributes.beatmap_id,
+            attributes.mode,
+            attributes.mods,
+        ),
+        data: attributes,
+    };
+
+    elastic::index(
+        &ctx.database,
+        &ctx.config.elastic_difficulty_attributes_index,
+        elastic_document,
+    )
+    .await?;
+
+    Ok(())
+}
@@ -1,3 +1,10 @@
+pub mod api_keys;
+pub mod archives;
 pub mod beatmaps;
 pub mod beatmapsets;
+pub mod crawl_queue;
+pub mod difficulty_attributes;
 pub mod osu;
+pub mod previews;
+pub mod renders;
+pub mod stored_archives;
@@ -1,5 +1,3 @@
-use futures::future;
-
 use rosu_v2::prelude::{Beatmap, OsuError};
 
 use crate::Context;
@@ -14,28 +12,27 @@ pub async fn fetch(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beat
     }
 }
 
-pub async fn bulk_fetch(ctx: &Context, beatmap_ids: Vec<u32>) -> anyhow::Result<Vec<Beatmap>> {
-    let beatmap_count = beatmap_ids.len();
-    let mut beatmap_futures = Vec::with_capacity(beatmap_count);
+/// Most beatmaps osu! returns from a single bulk lookup.
+const BULK_FETCH_LIMIT: usize = 50;
 
-    for beatmap_id in beatmap_ids {
-        let beatmap_future = fetch(ctx, beatmap_id);
-        beatmap_futures.push(beatmap_future);
-    }
+#[derive(serde::Deserialize)]
+struct BulkBeatmaps {
+    beatmaps: Vec<Beatmap>,
+}
 
-    let beatmap_future_results = future::join_all(beatmap_futures).await;
-    let mut beatmaps: Vec<Beatmap> = Vec::with_capacity(beatmap_count);
+/// Fetch beatmaps through osu!'s bulk lookup, 50 at a time. Ids osu! doesn't know are left
+/// out.
+pub async fn bulk_fetch(ctx: &Context, beatmap_ids: Vec<u32>) -> anyhow::Result<Vec<Beatmap>> {
+    let mut beatmaps = Vec::with_capacity(beatmap_ids.len());
 
-    for beatmap_result in beatmap_future_results {
-        let beatmap_option = match beatmap_result {
-            Ok(beatmap) => beatmap,
-            Err(OsuError::NotFound) => None,
-            Err(e) => return Err(e.into()),
-        };
+    for chunk in beatmap_ids.chunks(BULK_FETCH_LIMIT) {
+        let query: Vec<(&str, u32)> = chunk
+            .iter()
+            .map(|beatmap_id| ("ids[]", *beatmap_id))
+            .collect();
 
-        if let Some(beatmap) = beatmap_option {
-            beatmaps.push(beatmap);
-        }
+        let response: BulkBeatmaps = ctx.osu_raw_api.get("/beatmaps", &query).await?;
+        beatmaps.extend(response.beatmaps);
     }
 
     Ok(beatmaps)
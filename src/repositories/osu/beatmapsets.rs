@@ -1,3 +1,4 @@
+use axum::body::Bytes;
 use rosu_v2::prelude::{Beatmapset, OsuError};
 use std::collections::HashMap;
 
@@ -17,8 +18,11 @@ pub async fn fetch(
 }
 
 // TODO: authenticating twice is stupid
-// TODO: store?
-pub async fn download(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<String>> {
+pub async fn download(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+) -> anyhow::Result<Option<String>> {
     let client = reqwest::Client::new();
 
     let mut body_params = HashMap::new();
@@ -46,8 +50,8 @@ pub async fn download(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Optio
 
     let redirect_request = hyper::Request::builder()
         .uri(format!(
-            "https://osu.ppy.sh/api/v2/beatmapsets/{}/download",
-            beatmapset_id
+            "https://osu.ppy.sh/api/v2/beatmapsets/{}/download?noVideo={}",
+            beatmapset_id, no_video as u8
         ))
         .header("Content-Type", "application/x-osu-beatmap-archive")
         .header("Accept", "application/x-osu-beatmap-archive")
@@ -66,3 +70,27 @@ pub async fn download(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Optio
 
     Ok(redirect_url)
 }
+
+pub async fn download_archive(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+) -> anyhow::Result<Option<Bytes>> {
+    let osz_url = match download(ctx, beatmapset_id, no_video).await? {
+        Some(url) => url,
+        None => return Ok(None),
+    };
+
+    let osz_response = reqwest::Client::new()
+        .get(osz_url)
+        .header("User-Agent", "osu!")
+        .send()
+        .await?;
+
+    if osz_response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let archive = osz_response.error_for_status()?.bytes().await?;
+    Ok(Some(archive))
+}
@@ -1,2 +1,146 @@
 pub mod beatmaps;
 pub mod beatmapsets;
+
+use std::{collections::HashMap, time::Duration};
+
+use leaky_bucket_lite::LeakyBucket;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::config::Config;
+
+/// Root of the osu! api v2, for the endpoints called without the api client.
+pub const API_URL: &str = "https://osu.ppy.sh/api/v2";
+
+/// How long before it expires an access token is replaced.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+struct AccessToken {
+    token: String,
+    expires_at: Instant,
+}
+
+/// Client for the osu! api endpoints rosu-v2 doesn't cover. It keeps its access token between
+/// requests, and is held to `max_requests_per_second` like rosu-v2.
+pub struct RawClient {
+    http: reqwest::Client,
+    ratelimiter: LeakyBucket,
+    access_token: Mutex<Option<AccessToken>>,
+    client_id: u64,
+    client_secret: String,
+}
+
+impl RawClient {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.max_timeout))
+            .build()?;
+
+        let per_second = config.max_requests_per_second.max(1);
+        let ratelimiter = LeakyBucket::builder()
+            .max(per_second)
+            .tokens(per_second)
+            .refill_interval(Duration::from_millis(1000 / per_second as u64))
+            .refill_amount(1)
+            .build();
+
+        Ok(Self {
+            http,
+            ratelimiter,
+            access_token: Mutex::new(None),
+            client_id: config.osu_api_client_id,
+            client_secret: config.osu_api_client_secret.clone(),
+        })
+    }
+
+    /// Send a GET request to an endpoint of the osu! api, getting a new access token if osu!
+    /// rejects the current one.
+    pub async fn get<T>(&self, path: &str, query: &[(&str, u32)]) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let mut retried = false;
+
+        loop {
+            let access_token = self.access_token().await?;
+
+            self.ratelimiter.acquire_one().await;
+            let response = self
+                .http
+                .get(format!("{}{}", API_URL, path))
+                .query(query)
+                .bearer_auth(&access_token)
+                .header("Accept", "application/json")
+                .send()
+                .await?;
+
+            if response.status() == StatusCode::UNAUTHORIZED && !retried {
+                self.forget_access_token(&access_token).await;
+                retried = true;
+                continue;
+            }
+
+            return Ok(response.error_for_status()?.json().await?);
+        }
+    }
+
+    async fn access_token(&self) -> anyhow::Result<String> {
+        let mut access_token = self.access_token.lock().await;
+
+        if let Some(current) = access_token.as_ref() {
+            if Instant::now() + TOKEN_EXPIRY_MARGIN < current.expires_at {
+                return Ok(current.token.clone());
+            }
+        }
+
+        let fresh = self.request_access_token().await?;
+        let token = fresh.token.clone();
+        *access_token = Some(fresh);
+
+        Ok(token)
+    }
+
+    async fn forget_access_token(&self, rejected: &str) {
+        let mut access_token = self.access_token.lock().await;
+
+        // another request may have replaced it already
+        if access_token
+            .as_ref()
+            .is_some_and(|current| current.token == rejected)
+        {
+            *access_token = None;
+        }
+    }
+
+    async fn request_access_token(&self) -> anyhow::Result<AccessToken> {
+        let mut body_params = HashMap::new();
+        body_params.insert("client_id", self.client_id.to_string());
+        body_params.insert("client_secret", self.client_secret.clone());
+        body_params.insert("grant_type", "client_credentials".to_string());
+        body_params.insert("scope", "public".to_string());
+
+        self.ratelimiter.acquire_one().await;
+        let api_response: serde_json::Value = self
+            .http
+            .post("https://osu.ppy.sh/oauth/token")
+            .json(&body_params)
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let token = api_response["access_token"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("osu! didn't return an access token"))?
+            .to_string();
+        let expires_in = api_response["expires_in"].as_u64().unwrap_or(0);
+
+        Ok(AccessToken {
+            token,
+            expires_at: Instant::now() + Duration::from_secs(expires_in),
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use axum::body::Bytes;
+
+use crate::{helpers::files, Context};
+
+fn preview_path(ctx: &Context, beatmapset_id: u32) -> Option<PathBuf> {
+    ctx.config.archive_path.as_ref().map(|archive_path| {
+        PathBuf::from(archive_path)
+            .join("previews")
+            .join(format!("{}.mp3", beatmapset_id))
+    })
+}
+
+/// Marks that a beatmapset has no audio a preview can be made of.
+fn missing_path(ctx: &Context, beatmapset_id: u32) -> Option<PathBuf> {
+    ctx.config.archive_path.as_ref().map(|archive_path| {
+        PathBuf::from(archive_path)
+            .join("previews")
+            .join(format!("{}_missing", beatmapset_id))
+    })
+}
+
+pub async fn get(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Bytes>> {
+    let path = match preview_path(ctx, beatmapset_id) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    match tokio::fs::read(&path).await {
+        Ok(preview) => Ok(Some(Bytes::from(preview))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(
+            "failed to read preview {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+pub async fn store(ctx: &Context, beatmapset_id: u32, preview: &[u8]) -> anyhow::Result<()> {
+    let path = match preview_path(ctx, beatmapset_id) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    files::write_atomically(&path, preview).await
+}
+
+/// Whether a beatmapset was found to have no audio to make a preview of.
+pub async fn is_missing(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<bool> {
+    let path = match missing_path(ctx, beatmapset_id) {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+
+    tokio::fs::try_exists(&path)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to check preview {}: {}", path.display(), e))
+}
+
+/// Remember that a beatmapset has no audio to make a preview of, until its preview is deleted.
+pub async fn store_missing(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    let path = match missing_path(ctx, beatmapset_id) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    files::write_atomically(&path, &[]).await
+}
+
+/// Bytes taken up by a beatmapset's preview, or by the mark that it has none.
+pub async fn size(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<u64> {
+    let paths = [
+        preview_path(ctx, beatmapset_id),
+        missing_path(ctx, beatmapset_id),
+    ];
+
+    let mut size = 0;
+    for path in paths.into_iter().flatten() {
+        size += files::size(&path).await?;
+    }
+
+    Ok(size)
+}
+
+/// Delete a beatmapset's preview, and whether it has no audio to make one of.
+pub async fn delete(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    let paths = [
+        preview_path(ctx, beatmapset_id),
+        missing_path(ctx, beatmapset_id),
+    ];
+
+    for path in paths.into_iter().flatten() {
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "failed to delete preview {}: {}",
+                    path.display(),
+                    e
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
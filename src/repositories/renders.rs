@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use axum::body::Bytes;
+
+use crate::{
+    helpers::files,
+    models::image::{ImageFormat, ImageKind, ImageSize},
+    Context,
+};
+
+fn render_path(
+    ctx: &Context,
+    kind: ImageKind,
+    id: u32,
+    size: ImageSize,
+    format: ImageFormat,
+) -> Option<PathBuf> {
+    let file_name = format!(
+        "{}_{}_{}.{}",
+        kind.name(),
+        id,
+        size.name(),
+        format.extension()
+    );
+
+    ctx.config
+        .archive_path
+        .as_ref()
+        .map(|archive_path| PathBuf::from(archive_path).join("renders").join(file_name))
+}
+
+/// Marks that a beatmapset or difficulty has no background to render.
+fn missing_path(ctx: &Context, kind: ImageKind, id: u32) -> Option<PathBuf> {
+    let file_name = format!("{}_{}_missing", kind.name(), id);
+
+    ctx.config
+        .archive_path
+        .as_ref()
+        .map(|archive_path| PathBuf::from(archive_path).join("renders").join(file_name))
+}
+
+pub async fn get(
+    ctx: &Context,
+    kind: ImageKind,
+    id: u32,
+    size: ImageSize,
+    format: ImageFormat,
+) -> anyhow::Result<Option<Bytes>> {
+    let path = match render_path(ctx, kind, id, size, format) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    match tokio::fs::read(&path).await {
+        Ok(render) => Ok(Some(Bytes::from(render))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(
+            "failed to read render {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+pub async fn store(
+    ctx: &Context,
+    kind: ImageKind,
+    id: u32,
+    size: ImageSize,
+    format: ImageFormat,
+    render: &[u8],
+) -> anyhow::Result<()> {
+    let path = match render_path(ctx, kind, id, size, format) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    files::write_atomically(&path, render).await
+}
+
+/// Whether an id was found to have no background, so there's nothing to render.
+pub async fn is_missing(ctx: &Context, kind: ImageKind, id: u32) -> anyhow::Result<bool> {
+    let path = match missing_path(ctx, kind, id) {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+
+    tokio::fs::try_exists(&path)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to check render {}: {}", path.display(), e))
+}
+
+/// Remember that an id has no background, until its renders are deleted.
+pub async fn store_missing(ctx: &Context, kind: ImageKind, id: u32) -> anyhow::Result<()> {
+    let path = match missing_path(ctx, kind, id) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    files::write_atomically(&path, &[]).await
+}
+
+/// Every file rendered for an id, including the mark that it has no background.
+fn all_paths(ctx: &Context, kind: ImageKind, id: u32) -> Vec<Option<PathBuf>> {
+    let mut paths = vec![missing_path(ctx, kind, id)];
+    for size in ImageSize::ALL {
+        for format in ImageFormat::ALL {
+            paths.push(render_path(ctx, kind, id, size, format));
+        }
+    }
+
+    paths
+}
+
+/// Bytes taken up by every size and format rendered for an id.
+pub async fn size(ctx: &Context, kind: ImageKind, id: u32) -> anyhow::Result<u64> {
+    let mut size = 0;
+    for path in all_paths(ctx, kind, id).into_iter().flatten() {
+        size += files::size(&path).await?;
+    }
+
+    Ok(size)
+}
+
+/// Delete every size and format rendered for an id, and whether it has no background.
+pub async fn delete(ctx: &Context, kind: ImageKind, id: u32) -> anyhow::Result<()> {
+    for path in all_paths(ctx, kind, id) {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "failed to delete render {}: {}",
+                    path.display(),
+                    e
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use elasticsearch::{SearchParts, UpdateParts};
+
+use crate::{
+    helpers::elastic::{self, ElasticDocument},
+    models::stored_archive::StoredArchive,
+    Context,
+};
+
+pub async fn fetch(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<StoredArchive>> {
+    elastic::get(
+        &ctx.database,
+        &ctx.config.elastic_archives_index,
+        &beatmapset_id.to_string(),
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("failed to fetch stored archive: {}", e))
+}
+
+pub async fn store(ctx: &Context, stored_archive: StoredArchive) -> anyhow::Result<()> {
+    let elastic_document = ElasticDocument {
+        id: stored_archive.beatmapset_id.to_string(),
+        data: stored_archive,
+    };
+
+    elastic::index(
+        &ctx.database,
+        &ctx.config.elastic_archives_index,
+        elastic_document,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Count a download of a stored archive, doing nothing if it isn't tracked.
+pub async fn record_download(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    let elastic_response = ctx
+        .database
+        .update(UpdateParts::IndexId(
+            &ctx.config.elastic_archives_index,
+            &beatmapset_id.to_string(),
+        ))
+        .body(serde_json::json!({
+            "script": {
+                "source": "ctx._source.downloads += 1; ctx._source.last_downloaded_at = params.now",
+                "params": { "now": chrono::Utc::now() }
+            }
+        }))
+        .send()
+        .await?;
+
+    if elastic_response.status_code() == 404 {
+        return Ok(());
+    }
+
+    elastic_response.error_for_status_code()?;
+
+    Ok(())
+}
+
+/// Count the bytes of a render or preview toward the size of the stored archive it was made
+/// from, doing nothing if it isn't tracked.
+pub async fn add_size(ctx: &Context, beatmapset_id: u32, size: u64) -> anyhow::Result<()> {
+    let elastic_response = ctx
+        .database
+        .update(UpdateParts::IndexId(
+            &ctx.config.elastic_archives_index,
+            &beatmapset_id.to_string(),
+        ))
+        .body(serde_json::json!({
+            "script": {
+                "source": "ctx._source.size += params.size",
+                "params": { "size": size }
+            }
+        }))
+        .send()
+        .await?;
+
+    if elastic_response.status_code() == 404 {
+        return Ok(());
+    }
+
+    elastic_response.error_for_status_code()?;
+
+    Ok(())
+}
+
+/// Record the current status of a stored archive's beatmapset, doing nothing if it isn't
+/// tracked.
+pub async fn set_status(
+    ctx: &Context,
+    beatmapset_id: u32,
+    status: Option<i8>,
+) -> anyhow::Result<()> {
+    let elastic_response = ctx
+        .database
+        .update(UpdateParts::IndexId(
+            &ctx.config.elastic_archives_index,
+            &beatmapset_id.to_string(),
+        ))
+        .body(serde_json::json!({ "doc": { "status": status } }))
+        .send()
+        .await?;
+
+    if elastic_response.status_code() == 404 {
+        return Ok(());
+    }
+
+    elastic_response.error_for_status_code()?;
+
+    Ok(())
+}
+
+/// Record whether a stored archive is stale, doing nothing if it isn't tracked.
+pub async fn set_stale(ctx: &Context, beatmapset_id: u32, stale: bool) -> anyhow::Result<()> {
+    let elastic_response = ctx
+        .database
+        .update(UpdateParts::IndexId(
+            &ctx.config.elastic_archives_index,
+            &beatmapset_id.to_string(),
+        ))
+        .body(serde_json::json!({ "doc": { "stale": stale } }))
+        .send()
+        .await?;
+
+    if elastic_response.status_code() == 404 {
+        return Ok(());
+    }
+
+    elastic_response.error_for_status_code()?;
+
+    Ok(())
+}
+
+pub async fn delete(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    elastic::delete(
+        &ctx.database,
+        &ctx.config.elastic_archives_index,
+        &beatmapset_id.to_string(),
+    )
+    .await
+}
+
+/// Make every change to stored archives so far visible to `totals` and searches.
+pub async fn refresh(ctx: &Context) -> anyhow::Result<()> {
+    elastic::refresh_index(&ctx.database, &ctx.config.elastic_archives_index).await
+}
+
+/// The number of stored archives and the bytes they take up.
+pub async fn totals(ctx: &Context) -> anyhow::Result<(u64, u64)> {
+    let totals = ctx
+        .database
+        .search(SearchParts::Index(&[&ctx.config.elastic_archives_index]))
+        .body(serde_json::json!({
+            "size": 0,
+            "track_total_hits": true,
+            "aggs": { "size": { "sum": { "field": "size" } } }
+        }))
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to total stored archives: {}", e))?;
+
+    let archives = totals
+        .pointer("/hits/total/value")
+        .and_then(|archives| archives.as_u64())
+        .unwrap_or_default();
+    let size = totals
+        .pointer("/aggregations/size/value")
+        .and_then(|size| size.as_f64())
+        .unwrap_or_default() as u64;
+
+    Ok((archives, size))
+}
+
+/// Stored archives of the given statuses, or of beatmapsets that aren't indexed if none are
+/// given, least recently downloaded first. Pages continue after the last archive of the previous
+/// one, so archives deleted in between don't show up again.
+pub async fn least_recently_downloaded(
+    ctx: &Context,
+    statuses: Option<&[i8]>,
+    after: Option<&StoredArchive>,
+    amount: u64,
+) -> anyhow::Result<Vec<StoredArchive>> {
+    let query = match statuses {
+        Some(statuses) => serde_json::json!({ "terms": { "status": statuses } }),
+        None => serde_json::json!({ "bool": { "must_not": { "exists": { "field": "status" } } } }),
+    };
+
+    let mut body = serde_json::json!({
+        "size": amount,
+        "query": query,
+        "sort": [{ "last_downloaded_at": "asc" }, { "beatmapset_id": "asc" }]
+    });
+    if let Some(after) = after {
+        body["search_after"] = serde_json::json!([
+            after.last_downloaded_at.timestamp_millis(),
+            after.beatmapset_id
+        ]);
+    }
+
+    let stored_archives = ctx
+        .database
+        .search(SearchParts::Index(&[&ctx.config.elastic_archives_index]))
+        .body(body)
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to search stored archives: {}", e))?
+        .pointer("/hits/hits")
+        .and_then(|hits| hits.as_array())
+        .cloned()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| {
+            v.pointer("/_source")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+        })
+        .collect();
+
+    Ok(stored_archives)
+}
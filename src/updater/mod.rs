@@ -2,7 +2,7 @@ use elasticsearch::SearchParts;
 
 use crate::{
     models::{beatmap::Beatmap, beatmapset::Beatmapset, ranked_status::RankedStatus},
-    repositories, Context,
+    repositories, usecases, Context,
 };
 
 async fn update_beatmaps(ctx: &Context) -> anyhow::Result<()> {
@@ -50,12 +50,10 @@ async fn update_beatmaps(ctx: &Context) -> anyhow::Result<()> {
             .as_array()
             .unwrap()
             .iter()
-            .map(|v| {
+            .filter_map(|v| {
                 v.pointer("/_source")
                     .and_then(|v| serde_json::from_value(v.clone()).ok())
             })
-            .filter(|v| v.is_some())
-            .map(|v| v.unwrap())
             .collect();
 
         for mut beatmap in beatmaps {
@@ -76,14 +74,21 @@ async fn update_beatmaps(ctx: &Context) -> anyhow::Result<()> {
                 let now = chrono::Utc::now();
                 beatmap.last_checked = now;
 
-                if osu_beatmap != beatmap.data {
+                let beatmap_id = beatmap.data.map_id;
+                let changed = osu_beatmap != beatmap.data;
+
+                if changed {
                     beatmap.data = osu_beatmap;
                     beatmap.updated_at = now;
 
-                    log::info!("updated beatmap id {}", beatmap.data.map_id);
+                    log::info!("updated beatmap id {}", beatmap_id);
                 }
 
                 repositories::beatmaps::update(ctx, beatmap).await?;
+
+                if changed {
+                    ctx.beatmap_cache.invalidate(beatmap_id);
+                }
             }
         }
     }
@@ -134,12 +139,10 @@ async fn update_beatmapsets(ctx: &Context) -> anyhow::Result<()> {
             .as_array()
             .unwrap()
             .iter()
-            .map(|v| {
+            .filter_map(|v| {
                 v.pointer("/_source")
                     .and_then(|v| serde_json::from_value(v.clone()).ok())
             })
-            .filter(|v| v.is_some())
-            .map(|v| v.unwrap())
             .collect();
 
         for mut beatmapset in beatmapsets {
@@ -160,14 +163,33 @@ async fn update_beatmapsets(ctx: &Context) -> anyhow::Result<()> {
                 let now = chrono::Utc::now();
                 beatmapset.last_checked = now;
 
-                if osu_beatmapset != beatmapset.data {
+                let beatmapset_id = beatmapset.data.mapset_id;
+                let changed = osu_beatmapset != beatmapset.data;
+                let files_changed = osu_beatmapset.last_updated != beatmapset.data.last_updated;
+
+                if changed {
                     beatmapset.data = osu_beatmapset;
                     beatmapset.updated_at = now;
 
-                    log::info!("updated beatmapset id {}", beatmapset.data.mapset_id);
+                    log::info!("updated beatmapset id {}", beatmapset_id);
                 }
 
                 repositories::beatmapsets::update(ctx, beatmapset).await?;
+
+                if changed {
+                    ctx.beatmapset_cache.invalidate(beatmapset_id);
+                }
+
+                if files_changed {
+                    if let Err(e) = usecases::archives::refresh_if_stored(ctx, beatmapset_id).await
+                    {
+                        log::error!(
+                            "error while refreshing archive for beatmapset {}: {}",
+                            beatmapset_id,
+                            e
+                        );
+                    }
+                }
             }
         }
     }
@@ -175,7 +197,7 @@ async fn update_beatmapsets(ctx: &Context) -> anyhow::Result<()> {
 
 pub async fn serve(context: Context) -> anyhow::Result<()> {
     let res = tokio::try_join!(update_beatmaps(&context), update_beatmapsets(&context));
-    if !res.is_ok() {
+    if res.is_err() {
         anyhow::bail!("updater failed: {:?}", res);
     }
 
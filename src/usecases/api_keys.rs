@@ -0,0 +1,27 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::{models::api_key::ApiKey, repositories, Context};
+
+/// Create an api key with a random secret.
+pub async fn create(
+    ctx: &Context,
+    name: String,
+    requests_per_minute: u32,
+    admin: bool,
+) -> anyhow::Result<ApiKey> {
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut secret)
+        .map_err(|e| anyhow::anyhow!("failed to generate api key: {}", e))?;
+
+    let api_key = ApiKey {
+        key: URL_SAFE_NO_PAD.encode(secret),
+        name,
+        requests_per_minute,
+        admin,
+        created_at: chrono::Utc::now(),
+    };
+
+    repositories::api_keys::create(ctx, api_key.clone()).await?;
+
+    Ok(api_key)
+}
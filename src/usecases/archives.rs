@@ -0,0 +1,1075 @@
+use std::future::Future;
+
+use axum::body::Bytes;
+
+use crate::{
+    helpers::{archive, osu_file},
+    models::{
+        archive_verification::ArchiveVerification,
+        image::ImageKind,
+        ranked_status::RankedStatus,
+        stored_archive::{ArchiveStoreStats, StoredArchive},
+    },
+    repositories, usecases, Context,
+};
+
+/// Stored archives looked at per search when evicting to stay within the budget.
+const EVICTION_BATCH_SIZE: u64 = 100;
+
+pub async fn fetch(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+) -> anyhow::Result<Option<Bytes>> {
+    let archive = repositories::archives::get(ctx, beatmapset_id, no_video).await?;
+    if archive.is_some() {
+        ctx.archive_stats.hit();
+        return Ok(archive);
+    }
+
+    // concurrent misses for the same archive share a single download
+    ctx.archive_downloads
+        .run((beatmapset_id, no_video), || {
+            download(ctx, beatmapset_id, no_video)
+        })
+        .await
+}
+
+async fn download(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+) -> anyhow::Result<Option<Bytes>> {
+    // a download that finished between the miss and this one starting has stored it already
+    let archive = repositories::archives::get(ctx, beatmapset_id, no_video).await?;
+    if archive.is_some() {
+        ctx.archive_stats.hit();
+        return Ok(archive);
+    }
+
+    ctx.archive_stats.miss();
+
+    let osu_archive =
+        repositories::osu::beatmapsets::download_archive(ctx, beatmapset_id, no_video)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to download beatmapset: {}", e))?;
+
+    if let Some(osu_archive) = &osu_archive {
+        store_download(ctx, beatmapset_id, no_video, osu_archive).await?;
+    }
+
+    Ok(osu_archive)
+}
+
+/// Store a freshly downloaded archive, replacing any stored one in a single step, and keep the
+/// beatmaps parsed from it and the store's bookkeeping up to date.
+async fn store_download(
+    ctx: &Context,
+    beatmapset_id: u32,
+    no_video: bool,
+    osu_archive: &Bytes,
+) -> anyhow::Result<()> {
+    repositories::archives::store(ctx, beatmapset_id, no_video, osu_archive).await?;
+
+    if let Err(e) = store_parsed_beatmaps(ctx, beatmapset_id, osu_archive.clone()).await {
+        log::warn!("failed to parse beatmapset {}: {}", beatmapset_id, e);
+    }
+
+    // a fresh download can only disagree with the index if the index is out of date, or if
+    // osu! serves an outdated archive, which is recorded
+    let stale = match matches_index(ctx, beatmapset_id, std::slice::from_ref(osu_archive)).await {
+        Ok(matches) => matches == Some(false),
+        Err(e) => {
+            log::warn!("failed to verify beatmapset {}: {}", beatmapset_id, e);
+            false
+        }
+    };
+    if stale {
+        log::warn!(
+            "downloaded archive for beatmapset {} doesn't match its checksums on osu!",
+            beatmapset_id
+        );
+    }
+
+    if let Err(e) = track_stored(ctx, beatmapset_id, stale).await {
+        log::warn!(
+            "failed to track stored archive for beatmapset {}: {}",
+            beatmapset_id,
+            e
+        );
+    }
+
+    // evicting can take a while, so it's left to run after the download is served
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        if let Err(e) = enforce_budget(&ctx, beatmapset_id).await {
+            log::warn!("failed to evict archives: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Get whichever variant of a beatmapset's archive is stored, downloading the one without
+/// video if neither is, for when only the beatmap files are needed.
+pub async fn fetch_any(
+    ctx: &Context,
+    beatmapset_id: u32,
+    has_video: bool,
+) -> anyhow::Result<Option<Bytes>> {
+    let archive = repositories::archives::get(ctx, beatmapset_id, false).await?;
+    if archive.is_some() {
+        return Ok(archive);
+    }
+
+    // sets without a video only have one variant, which is stored as the full archive
+    fetch(ctx, beatmapset_id, has_video).await
+}
+
+/// Replace the stored archive with a fresh download from osu!. The stored archive is only
+/// swapped for the new one once it's fully downloaded, and kept if the download fails.
+pub async fn refresh(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Bytes>> {
+    let osu_archive =
+        match repositories::osu::beatmapsets::download_archive(ctx, beatmapset_id, false)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to download beatmapset: {}", e))?
+        {
+            Some(osu_archive) => osu_archive,
+            None => return Ok(None),
+        };
+
+    // the no video variant is downloaded again on its next request, and renders and previews
+    // are made again from the new archive
+    repositories::archives::delete(ctx, beatmapset_id, true).await?;
+    let beatmap_ids = beatmap_ids(ctx, beatmapset_id).await?;
+    usecases::images::delete_renders(ctx, beatmapset_id, &beatmap_ids).await?;
+    repositories::previews::delete(ctx, beatmapset_id).await?;
+
+    store_download(ctx, beatmapset_id, false, &osu_archive).await?;
+
+    Ok(Some(osu_archive))
+}
+
+/// Count a download of a beatmapset's stored archive from the mirror.
+pub async fn record_download(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    repositories::stored_archives::record_download(ctx, beatmapset_id).await
+}
+
+/// Size and activity of the archive store.
+pub async fn store_stats(ctx: &Context) -> anyhow::Result<ArchiveStoreStats> {
+    let (archives, size) = repositories::stored_archives::totals(ctx).await?;
+    let counts = ctx.archive_stats.counts();
+
+    let requests = counts.hits + counts.misses;
+    let hit_rate = match requests {
+        0 => 0.0,
+        requests => counts.hits as f64 / requests as f64,
+    };
+
+    Ok(ArchiveStoreStats {
+        archives,
+        size,
+        budget: budget(ctx),
+        hits: counts.hits,
+        misses: counts.misses,
+        hit_rate,
+        evictions: counts.evictions,
+        evicted_bytes: counts.evicted_bytes,
+        counted_since: counts.since,
+    })
+}
+
+/// Bytes stored archives may take up, if limited. Budgets too large to count in bytes are left
+/// unlimited.
+fn budget(ctx: &Context) -> Option<u64> {
+    ctx.config
+        .archive_budget
+        .and_then(|budget| budget.checked_mul(1024 * 1024))
+}
+
+/// Whether the archive store has room left in its budget, which it always has without one.
+pub async fn has_room(ctx: &Context) -> anyhow::Result<bool> {
+    let budget = match budget(ctx) {
+        Some(budget) => budget,
+        None => return Ok(true),
+    };
+
+    // the archive stored just before may not be searchable yet
+    repositories::stored_archives::refresh(ctx).await?;
+    let (_, size) = repositories::stored_archives::totals(ctx).await?;
+
+    Ok(size < budget)
+}
+
+/// Count a render or preview made from a beatmapset's stored archive toward its size, so the
+/// budget covers it too.
+pub async fn count_made(ctx: &Context, beatmapset_id: u32, size: u64) {
+    if let Err(e) = repositories::stored_archives::add_size(ctx, beatmapset_id, size).await {
+        log::warn!(
+            "failed to count files made from archive for beatmapset {}: {}",
+            beatmapset_id,
+            e
+        );
+    }
+}
+
+/// Ranked status code of a beatmapset, or `None` if it isn't indexed.
+async fn current_status(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<i8>> {
+    Ok(usecases::beatmapsets::fetch(ctx, beatmapset_id)
+        .await?
+        .map(|beatmapset| beatmapset.data.status as i8))
+}
+
+/// Ids of a beatmapset's difficulties, which renders of their backgrounds are stored by.
+async fn beatmap_ids(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Vec<u32>> {
+    Ok(usecases::beatmapsets::fetch(ctx, beatmapset_id)
+        .await?
+        .and_then(|beatmapset| beatmapset.data.maps)
+        .unwrap_or_default()
+        .iter()
+        .map(|beatmap| beatmap.map_id)
+        .collect())
+}
+
+/// Bytes taken up by both variants of a beatmapset's archive and everything made from them.
+async fn stored_size(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<u64> {
+    let mut size = 0;
+    for no_video in [false, true] {
+        size += repositories::archives::size(ctx, beatmapset_id, no_video)
+            .await?
+            .unwrap_or_default();
+    }
+
+    size += repositories::renders::size(ctx, ImageKind::Thumbnail, beatmapset_id).await?;
+    for beatmap_id in beatmap_ids(ctx, beatmapset_id).await? {
+        size += repositories::renders::size(ctx, ImageKind::Background, beatmap_id).await?;
+    }
+    size += repositories::previews::size(ctx, beatmapset_id).await?;
+
+    Ok(size)
+}
+
+/// Record the size and status of a freshly stored archive and whether it's stale, keeping its
+/// download history.
+async fn track_stored(ctx: &Context, beatmapset_id: u32, stale: bool) -> anyhow::Result<()> {
+    let size = stored_size(ctx, beatmapset_id).await?;
+    let status = current_status(ctx, beatmapset_id).await?;
+
+    let now = chrono::Utc::now();
+    let stored_archive = match repositories::stored_archives::fetch(ctx, beatmapset_id).await? {
+        Some(stored_archive) => StoredArchive {
+            status,
+            size,
+            stale,
+            stored_at: now,
+            ..stored_archive
+        },
+        None => StoredArchive {
+            beatmapset_id,
+            status,
+            size,
+            stale,
+            downloads: 0,
+            stored_at: now,
+            last_downloaded_at: now,
+        },
+    };
+
+    repositories::stored_archives::store(ctx, stored_archive).await
+}
+
+/// Track archives stored before the store kept track of them, so they count toward the budget
+/// and can be evicted. Returns how many were tracked.
+pub async fn track_untracked(ctx: &Context) -> anyhow::Result<usize> {
+    let mut tracked = 0;
+
+    for beatmapset_id in repositories::archives::stored_ids(ctx).await? {
+        if repositories::stored_archives::fetch(ctx, beatmapset_id)
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+
+        track_stored(ctx, beatmapset_id, false).await?;
+        tracked += 1;
+    }
+
+    Ok(tracked)
+}
+
+/// Evict the least recently downloaded archives until the store fits in its budget, one run at
+/// a time so that runs started by concurrent stores don't each evict for the same overflow.
+async fn enforce_budget(ctx: &Context, stored_beatmapset_id: u32) -> anyhow::Result<()> {
+    let budget = match budget(ctx) {
+        Some(budget) => budget,
+        None => return Ok(()),
+    };
+
+    let _evicting = ctx.archive_evictions.lock().await;
+
+    evict_to_budget(
+        ctx,
+        budget,
+        ctx.config.evict_ranked_archives,
+        stored_beatmapset_id,
+    )
+    .await
+}
+
+/// What eviction needs from the store and the index, so the order archives are evicted in can
+/// be checked without either.
+trait EvictionStore {
+    /// Make every change to the store's bookkeeping so far visible to `size` and searches.
+    fn refresh(&self) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Bytes taken up by the store.
+    fn size(&self) -> impl Future<Output = anyhow::Result<u64>> + Send;
+
+    fn least_recently_downloaded(
+        &self,
+        statuses: Option<&[i8]>,
+        after: Option<&StoredArchive>,
+        amount: u64,
+    ) -> impl Future<Output = anyhow::Result<Vec<StoredArchive>>> + Send;
+
+    fn current_status(
+        &self,
+        beatmapset_id: u32,
+    ) -> impl Future<Output = anyhow::Result<Option<i8>>> + Send;
+
+    fn set_status(
+        &self,
+        beatmapset_id: u32,
+        status: Option<i8>,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn evict(
+        &self,
+        stored_archive: &StoredArchive,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+impl EvictionStore for Context {
+    async fn refresh(&self) -> anyhow::Result<()> {
+        repositories::stored_archives::refresh(self).await
+    }
+
+    async fn size(&self) -> anyhow::Result<u64> {
+        Ok(repositories::stored_archives::totals(self).await?.1)
+    }
+
+    async fn least_recently_downloaded(
+        &self,
+        statuses: Option<&[i8]>,
+        after: Option<&StoredArchive>,
+        amount: u64,
+    ) -> anyhow::Result<Vec<StoredArchive>> {
+        repositories::stored_archives::least_recently_downloaded(self, statuses, after, amount)
+            .await
+    }
+
+    async fn current_status(&self, beatmapset_id: u32) -> anyhow::Result<Option<i8>> {
+        current_status(self, beatmapset_id).await
+    }
+
+    async fn set_status(&self, beatmapset_id: u32, status: Option<i8>) -> anyhow::Result<()> {
+        repositories::stored_archives::set_status(self, beatmapset_id, status).await
+    }
+
+    async fn evict(&self, stored_archive: &StoredArchive) -> anyhow::Result<()> {
+        evict(self, stored_archive).await
+    }
+}
+
+/// Evict the least recently downloaded archives other than the one just stored until the store
+/// fits in the budget, going through sets that aren't indexed anymore and then graveyard sets
+/// first, and only touching ranked, approved and loved sets if allowed.
+async fn evict_to_budget(
+    store: &impl EvictionStore,
+    budget: u64,
+    evict_ranked: bool,
+    stored_beatmapset_id: u32,
+) -> anyhow::Result<()> {
+    // sizes recorded by the stores that started this run may not be searchable yet
+    store.refresh().await?;
+
+    let mut size = store.size().await?;
+    if size <= budget {
+        return Ok(());
+    }
+
+    let mut tiers = vec![
+        None,
+        Some(vec![RankedStatus::Graveyard]),
+        Some(vec![
+            RankedStatus::WorkInProgress,
+            RankedStatus::Pending,
+            RankedStatus::Qualified,
+        ]),
+    ];
+    if evict_ranked {
+        tiers.push(Some(RankedStatus::HAS_LEADERBOARD.to_vec()));
+    }
+
+    for statuses in tiers {
+        let statuses: Option<Vec<i8>> =
+            statuses.map(|statuses| statuses.into_iter().map(|status| status as i8).collect());
+        let mut after = None;
+
+        while size > budget {
+            let candidates = store
+                .least_recently_downloaded(statuses.as_deref(), after.as_ref(), EVICTION_BATCH_SIZE)
+                .await?;
+            let exhausted = (candidates.len() as u64) < EVICTION_BATCH_SIZE;
+
+            for stored_archive in candidates {
+                if size <= budget {
+                    break;
+                }
+
+                after = Some(stored_archive.clone());
+                if stored_archive.beatmapset_id == stored_beatmapset_id {
+                    continue;
+                }
+
+                // statuses change after archives are stored, so they're checked again before
+                // evicting, leaving archives that moved to another tier for that tier
+                let status = store.current_status(stored_archive.beatmapset_id).await?;
+                if status != stored_archive.status {
+                    store
+                        .set_status(stored_archive.beatmapset_id, status)
+                        .await?;
+
+                    let in_tier = match (&statuses, status) {
+                        (None, None) => true,
+                        (Some(statuses), Some(status)) => statuses.contains(&status),
+                        _ => false,
+                    };
+                    if !in_tier {
+                        continue;
+                    }
+                }
+
+                store.evict(&stored_archive).await?;
+                size = size.saturating_sub(stored_archive.size);
+            }
+
+            if exhausted {
+                break;
+            }
+        }
+    }
+
+    if size > budget {
+        log::warn!("archive store is over its budget with nothing left to evict");
+    }
+
+    Ok(())
+}
+
+/// Delete both variants of a beatmapset's archive, along with everything made from them.
+async fn evict(ctx: &Context, stored_archive: &StoredArchive) -> anyhow::Result<()> {
+    let beatmapset_id = stored_archive.beatmapset_id;
+
+    repositories::archives::delete(ctx, beatmapset_id, false).await?;
+    repositories::archives::delete(ctx, beatmapset_id, true).await?;
+    let beatmap_ids = beatmap_ids(ctx, beatmapset_id).await?;
+    usecases::images::delete_renders(ctx, beatmapset_id, &beatmap_ids).await?;
+    repositories::previews::delete(ctx, beatmapset_id).await?;
+    repositories::stored_archives::delete(ctx, beatmapset_id).await?;
+
+    ctx.archive_stats.evicted(stored_archive.size);
+    log::info!("evicted archive for beatmapset {}", beatmapset_id);
+
+    Ok(())
+}
+
+/// Download a beatmapset's archive again if either variant is stored, for when its files
+/// changed on osu!.
+pub async fn refresh_if_stored(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    if repositories::archives::exists(ctx, beatmapset_id, false).await?
+        || repositories::archives::exists(ctx, beatmapset_id, true).await?
+    {
+        refresh(ctx, beatmapset_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Check the stored archives of a beatmapset against the checksums of its beatmaps, downloading
+/// them again if they're stale.
+pub async fn verify(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<ArchiveVerification> {
+    verify_with(ctx, beatmapset_id).await
+}
+
+/// What verifying a beatmapset's archives needs from the store and the index, so the outcome
+/// can be checked without either.
+trait VerifyStore {
+    /// Both variants of the beatmapset's archive that are stored, the full one first.
+    fn stored(&self, beatmapset_id: u32) -> impl Future<Output = anyhow::Result<Vec<Bytes>>>;
+
+    fn matches_index(
+        &self,
+        beatmapset_id: u32,
+        osu_archives: &[Bytes],
+    ) -> impl Future<Output = anyhow::Result<Option<bool>>>;
+
+    fn set_stale(
+        &self,
+        beatmapset_id: u32,
+        stale: bool,
+    ) -> impl Future<Output = anyhow::Result<()>>;
+
+    fn refresh(&self, beatmapset_id: u32) -> impl Future<Output = anyhow::Result<()>>;
+
+    fn backfill(
+        &self,
+        beatmapset_id: u32,
+        osu_archive: Bytes,
+    ) -> impl Future<Output = anyhow::Result<()>>;
+}
+
+impl VerifyStore for Context {
+    async fn stored(&self, beatmapset_id: u32) -> anyhow::Result<Vec<Bytes>> {
+        let mut stored = Vec::new();
+        for no_video in [false, true] {
+            if let Some(osu_archive) =
+                repositories::archives::get(self, beatmapset_id, no_video).await?
+            {
+                stored.push(osu_archive);
+            }
+        }
+
+        Ok(stored)
+    }
+
+    async fn matches_index(
+        &self,
+        beatmapset_id: u32,
+        osu_archives: &[Bytes],
+    ) -> anyhow::Result<Option<bool>> {
+        matches_index(self, beatmapset_id, osu_archives).await
+    }
+
+    async fn set_stale(&self, beatmapset_id: u32, stale: bool) -> anyhow::Result<()> {
+        repositories::stored_archives::set_stale(self, beatmapset_id, stale).await
+    }
+
+    async fn refresh(&self, beatmapset_id: u32) -> anyhow::Result<()> {
+        refresh(self, beatmapset_id).await.map(|_| ())
+    }
+
+    async fn backfill(&self, beatmapset_id: u32, osu_archive: Bytes) -> anyhow::Result<()> {
+        backfill_parsed_beatmaps(self, beatmapset_id, osu_archive).await
+    }
+}
+
+async fn verify_with(
+    store: &impl VerifyStore,
+    beatmapset_id: u32,
+) -> anyhow::Result<ArchiveVerification> {
+    let stored = store.stored(beatmapset_id).await?;
+
+    let osu_archive = match stored.first() {
+        Some(osu_archive) => osu_archive.clone(),
+        None => return Ok(ArchiveVerification::NotStored),
+    };
+
+    match store.matches_index(beatmapset_id, &stored).await? {
+        Some(true) => {}
+        Some(false) => {
+            log::warn!("archive for beatmapset {} is stale", beatmapset_id);
+
+            // recorded first, so it stays recorded if downloading it again fails
+            store.set_stale(beatmapset_id, true).await?;
+            store.refresh(beatmapset_id).await?;
+
+            return Ok(ArchiveVerification::Stale);
+        }
+        None => return Ok(ArchiveVerification::Unverifiable),
+    }
+
+    store.set_stale(beatmapset_id, false).await?;
+
+    if let Err(e) = store.backfill(beatmapset_id, osu_archive).await {
+        log::warn!("failed to parse beatmapset {}: {}", beatmapset_id, e);
+    }
+
+    Ok(ArchiveVerification::Valid)
+}
+
+/// Whether every archive matches the checksums of the beatmapset's indexed beatmaps, or `None`
+/// if there are none to compare against. When they don't match, the beatmapset is fetched from
+/// osu! again before the archives are compared once more, in case it's the index that's out of
+/// date.
+async fn matches_index(
+    ctx: &Context,
+    beatmapset_id: u32,
+    osu_archives: &[Bytes],
+) -> anyhow::Result<Option<bool>> {
+    let checksums = match indexed_checksums(ctx, beatmapset_id).await? {
+        Some(checksums) => checksums,
+        None => return Ok(None),
+    };
+
+    if all_match_checksums(osu_archives, checksums).await? {
+        return Ok(Some(true));
+    }
+
+    if usecases::beatmapsets::refresh(ctx, beatmapset_id)
+        .await?
+        .is_none()
+    {
+        return Ok(Some(false));
+    }
+
+    match indexed_checksums(ctx, beatmapset_id).await? {
+        Some(checksums) => Ok(Some(all_match_checksums(osu_archives, checksums).await?)),
+        None => Ok(None),
+    }
+}
+
+async fn all_match_checksums(
+    osu_archives: &[Bytes],
+    checksums: Vec<String>,
+) -> anyhow::Result<bool> {
+    let osu_archives = osu_archives.to_vec();
+
+    Ok(tokio::task::spawn_blocking(move || {
+        osu_archives
+            .iter()
+            .all(|osu_archive| matches_checksums(osu_archive, &checksums))
+    })
+    .await?)
+}
+
+/// Parse the beatmaps of an archive stored before beatmaps were parsed on download.
+async fn backfill_parsed_beatmaps(
+    ctx: &Context,
+    beatmapset_id: u32,
+    osu_archive: Bytes,
+) -> anyhow::Result<()> {
+    let beatmap_ids: Vec<u32> = match usecases::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset
+            .data
+            .maps
+            .unwrap_or_default()
+            .iter()
+            .map(|beatmap| beatmap.map_id)
+            .collect(),
+        None => return Ok(()),
+    };
+
+    let beatmaps = repositories::beatmaps::fetch_many(ctx, &beatmap_ids).await?;
+    if beatmaps.values().all(|beatmap| beatmap.parsed.is_some()) {
+        return Ok(());
+    }
+
+    store_parsed_beatmaps(ctx, beatmapset_id, osu_archive).await
+}
+
+/// Checksums of a beatmapset's indexed beatmaps, if it's indexed and has any.
+async fn indexed_checksums(
+    ctx: &Context,
+    beatmapset_id: u32,
+) -> anyhow::Result<Option<Vec<String>>> {
+    let beatmaps = match usecases::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset.data.maps.unwrap_or_default(),
+        None => return Ok(None),
+    };
+
+    let checksums: Vec<String> = beatmaps
+        .into_iter()
+        .filter_map(|beatmap| beatmap.checksum)
+        .collect();
+
+    Ok(match checksums.is_empty() {
+        true => None,
+        false => Some(checksums),
+    })
+}
+
+/// Whether every checksum has a matching `.osu` file in the archive. Archives that can't be
+/// read don't match.
+fn matches_checksums(osu_archive: &[u8], checksums: &[String]) -> bool {
+    match archive::osu_checksums(osu_archive) {
+        Ok(osu_checksums) => checksums
+            .iter()
+            .all(|checksum| osu_checksums.contains(checksum)),
+        Err(_) => false,
+    }
+}
+
+/// Parse the `.osu` files of a freshly stored archive onto the beatmaps of its set.
+async fn store_parsed_beatmaps(
+    ctx: &Context,
+    beatmapset_id: u32,
+    osu_archive: Bytes,
+) -> anyhow::Result<()> {
+    let beatmaps = match usecases::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset.data.maps.unwrap_or_default(),
+        None => return Ok(()),
+    };
+
+    let (osu_files, storyboard_file) = tokio::task::spawn_blocking(move || {
+        let osu_files: Vec<_> = archive::read_files_with_extension(&osu_archive, ".osu")?
+            .into_iter()
+            .map(|(_, contents)| osu_file::parse(&String::from_utf8_lossy(&contents)))
+            .collect();
+        let storyboard_file = archive::file_names(&osu_archive)?
+            .iter()
+            .any(|name| name.to_lowercase().ends_with(".osb"));
+
+        anyhow::Ok((osu_files, storyboard_file))
+    })
+    .await??;
+
+    for mut osu_file in osu_files {
+        // older files don't name their beatmap id, so fall back to the difficulty name
+        let beatmap_id = osu_file
+            .beatmap_id
+            .filter(|beatmap_id| beatmaps.iter().any(|beatmap| beatmap.map_id == *beatmap_id))
+            .or_else(|| {
+                let version = osu_file.version.as_deref()?;
+                beatmaps
+                    .iter()
+                    .find(|beatmap| beatmap.version == version)
+                    .map(|beatmap| beatmap.map_id)
+            });
+
+        let beatmap_id = match beatmap_id {
+            Some(beatmap_id) => beatmap_id,
+            None => continue,
+        };
+
+        // a storyboard in the set's .osb file shows on every difficulty
+        osu_file.parsed.storyboard |= storyboard_file;
+
+        repositories::beatmaps::set_parsed(ctx, beatmap_id, osu_file.parsed).await?;
+        ctx.beatmap_cache.invalidate(beatmap_id);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use super::*;
+
+    /// A store with the given archives whose comparison against the index comes out as given,
+    /// recording what's done to it.
+    struct StubStore {
+        stored: Vec<Bytes>,
+        matches: Option<bool>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl StubStore {
+        fn new(stored: usize, matches: Option<bool>) -> Self {
+            Self {
+                stored: vec![Bytes::from_static(b"archive"); stored],
+                matches,
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        fn call(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    impl VerifyStore for StubStore {
+        async fn stored(&self, _: u32) -> anyhow::Result<Vec<Bytes>> {
+            Ok(self.stored.clone())
+        }
+
+        async fn matches_index(
+            &self,
+            _: u32,
+            osu_archives: &[Bytes],
+        ) -> anyhow::Result<Option<bool>> {
+            self.call(format!("matches_index {}", osu_archives.len()));
+            Ok(self.matches)
+        }
+
+        async fn set_stale(&self, beatmapset_id: u32, stale: bool) -> anyhow::Result<()> {
+            self.call(format!("set_stale {} {}", beatmapset_id, stale));
+            Ok(())
+        }
+
+        async fn refresh(&self, beatmapset_id: u32) -> anyhow::Result<()> {
+            self.call(format!("refresh {}", beatmapset_id));
+            Ok(())
+        }
+
+        async fn backfill(&self, beatmapset_id: u32, _: Bytes) -> anyhow::Result<()> {
+            self.call(format!("backfill {}", beatmapset_id));
+            anyhow::bail!("backfill failures are only logged")
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_flags_and_refreshes_stale_archives() {
+        let store = StubStore::new(2, Some(false));
+
+        assert_eq!(
+            verify_with(&store, 1).await.unwrap(),
+            ArchiveVerification::Stale
+        );
+        assert_eq!(
+            store.calls(),
+            ["matches_index 2", "set_stale 1 true", "refresh 1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_clears_the_flag_and_backfills_valid_archives() {
+        let store = StubStore::new(1, Some(true));
+
+        assert_eq!(
+            verify_with(&store, 1).await.unwrap(),
+            ArchiveVerification::Valid
+        );
+        assert_eq!(
+            store.calls(),
+            ["matches_index 1", "set_stale 1 false", "backfill 1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_leaves_unverifiable_archives_alone() {
+        let store = StubStore::new(1, None);
+
+        assert_eq!(
+            verify_with(&store, 1).await.unwrap(),
+            ArchiveVerification::Unverifiable
+        );
+        assert_eq!(store.calls(), ["matches_index 1"]);
+    }
+
+    #[tokio::test]
+    async fn verify_skips_sets_without_archives() {
+        let store = StubStore::new(0, Some(false));
+
+        assert_eq!(
+            verify_with(&store, 1).await.unwrap(),
+            ArchiveVerification::NotStored
+        );
+        assert!(store.calls().is_empty());
+    }
+
+    /// A store of the given archives, with the statuses their sets currently have, recording
+    /// what's done to it.
+    struct StubEvictionStore {
+        archives: Mutex<Vec<StoredArchive>>,
+        statuses: HashMap<u32, Option<i8>>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl StubEvictionStore {
+        fn new(archives: Vec<StoredArchive>) -> Self {
+            let statuses = archives
+                .iter()
+                .map(|stored_archive| (stored_archive.beatmapset_id, stored_archive.status))
+                .collect();
+
+            Self {
+                archives: Mutex::new(archives),
+                statuses,
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        fn call(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn evicted(&self) -> Vec<String> {
+            self.calls()
+                .into_iter()
+                .filter(|call| call.starts_with("evict"))
+                .collect()
+        }
+    }
+
+    impl EvictionStore for StubEvictionStore {
+        async fn refresh(&self) -> anyhow::Result<()> {
+            self.call("refresh".to_string());
+            Ok(())
+        }
+
+        async fn size(&self) -> anyhow::Result<u64> {
+            self.call("size".to_string());
+            Ok(self.archives.lock().unwrap().iter().map(|a| a.size).sum())
+        }
+
+        async fn least_recently_downloaded(
+            &self,
+            statuses: Option<&[i8]>,
+            after: Option<&StoredArchive>,
+            amount: u64,
+        ) -> anyhow::Result<Vec<StoredArchive>> {
+            let key = |a: &StoredArchive| (a.last_downloaded_at, a.beatmapset_id);
+
+            let mut archives: Vec<StoredArchive> = self
+                .archives
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|a| match (statuses, a.status) {
+                    (None, status) => status.is_none(),
+                    (Some(statuses), Some(status)) => statuses.contains(&status),
+                    (Some(_), None) => false,
+                })
+                .filter(|a| after.is_none_or(|after| key(a) > key(after)))
+                .cloned()
+                .collect();
+            archives.sort_by_key(key);
+            archives.truncate(amount as usize);
+
+            Ok(archives)
+        }
+
+        async fn current_status(&self, beatmapset_id: u32) -> anyhow::Result<Option<i8>> {
+            Ok(self.statuses[&beatmapset_id])
+        }
+
+        async fn set_status(&self, beatmapset_id: u32, status: Option<i8>) -> anyhow::Result<()> {
+            self.call(format!("set_status {}", beatmapset_id));
+            for stored_archive in self.archives.lock().unwrap().iter_mut() {
+                if stored_archive.beatmapset_id == beatmapset_id {
+                    stored_archive.status = status;
+                }
+            }
+
+            Ok(())
+        }
+
+        async fn evict(&self, stored_archive: &StoredArchive) -> anyhow::Result<()> {
+            self.call(format!("evict {}", stored_archive.beatmapset_id));
+            self.archives
+                .lock()
+                .unwrap()
+                .retain(|a| a.beatmapset_id != stored_archive.beatmapset_id);
+
+            Ok(())
+        }
+    }
+
+    /// A stored archive of a byte, last downloaded the given number of minutes into the day.
+    fn stored_archive(
+        beatmapset_id: u32,
+        status: Option<RankedStatus>,
+        minutes: i64,
+    ) -> StoredArchive {
+        let at = chrono::DateTime::<chrono::Utc>::UNIX_EPOCH + chrono::Duration::minutes(minutes);
+
+        StoredArchive {
+            beatmapset_id,
+            status: status.map(|status| status as i8),
+            size: 1,
+            stale: false,
+            downloads: 0,
+            stored_at: at,
+            last_downloaded_at: at,
+        }
+    }
+
+    #[tokio::test]
+    async fn eviction_goes_through_the_tiers_in_order() {
+        let store = StubEvictionStore::new(vec![
+            stored_archive(1, Some(RankedStatus::Ranked), 0),
+            stored_archive(2, Some(RankedStatus::Pending), 1),
+            stored_archive(3, Some(RankedStatus::Graveyard), 2),
+            stored_archive(4, None, 3),
+            stored_archive(5, Some(RankedStatus::Graveyard), 4),
+            stored_archive(6, Some(RankedStatus::Loved), 5),
+        ]);
+
+        evict_to_budget(&store, 2, false, 0).await.unwrap();
+
+        assert_eq!(&store.calls()[..2], ["refresh", "size"]);
+        assert_eq!(
+            store.evicted(),
+            ["evict 4", "evict 3", "evict 5", "evict 2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn eviction_only_touches_ranked_sets_if_allowed() {
+        let archives = || {
+            vec![
+                stored_archive(1, Some(RankedStatus::Ranked), 0),
+                stored_archive(2, Some(RankedStatus::Loved), 1),
+                stored_archive(3, Some(RankedStatus::Approved), 2),
+            ]
+        };
+
+        let store = StubEvictionStore::new(archives());
+        evict_to_budget(&store, 1, false, 0).await.unwrap();
+        assert!(store.evicted().is_empty());
+
+        let store = StubEvictionStore::new(archives());
+        evict_to_budget(&store, 1, true, 0).await.unwrap();
+        assert_eq!(store.evicted(), ["evict 1", "evict 2"]);
+    }
+
+    #[tokio::test]
+    async fn eviction_leaves_sets_that_changed_tier_for_their_new_tier() {
+        let mut store = StubEvictionStore::new(vec![
+            stored_archive(1, Some(RankedStatus::Graveyard), 0),
+            stored_archive(2, Some(RankedStatus::Graveyard), 1),
+            stored_archive(3, Some(RankedStatus::Graveyard), 2),
+        ]);
+        // ranked since it was stored
+        store.statuses.insert(1, Some(RankedStatus::Ranked as i8));
+        // revived since it was stored, so it goes with the pending tier
+        store.statuses.insert(3, Some(RankedStatus::Pending as i8));
+
+        evict_to_budget(&store, 1, false, 0).await.unwrap();
+
+        assert_eq!(
+            store.calls()[2..],
+            ["set_status 1", "evict 2", "set_status 3", "evict 3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn eviction_pages_past_the_first_batch_and_keeps_the_stored_archive() {
+        let archives = (0..EVICTION_BATCH_SIZE as u32 + 50)
+            .map(|beatmapset_id| stored_archive(beatmapset_id, None, beatmapset_id as i64))
+            .collect();
+        let store = StubEvictionStore::new(archives);
+
+        evict_to_budget(&store, 20, false, 0).await.unwrap();
+
+        let evicted: Vec<String> = (1..=EVICTION_BATCH_SIZE + 30)
+            .map(|beatmapset_id| format!("evict {}", beatmapset_id))
+            .collect();
+        assert_eq!(store.evicted(), evicted);
+    }
+
+    #[test]
+    fn matches_checksums_needs_every_checksum() {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("easy.osu", Default::default()).unwrap();
+        std::io::Write::write_all(&mut zip, b"easy").unwrap();
+        let archive = zip.finish().unwrap().into_inner();
+        let easy = format!("{:x}", <md5::Md5 as md5::Digest>::digest(b"easy"));
+
+        assert!(matches_checksums(&archive, std::slice::from_ref(&easy)));
+        assert!(!matches_checksums(&archive, &[easy, "missing".to_string()]));
+        assert!(!matches_checksums(b"not a zip", &[]));
+    }
+}
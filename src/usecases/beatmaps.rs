@@ -1,32 +1,45 @@
+use std::collections::{HashMap, HashSet};
+
 use crate::Context;
-use crate::{models::beatmap::Beatmap, repositories};
-use elasticsearch::SearchParts;
-use elasticsearch_dsl::{Query, Search};
+use crate::{
+    models::{beatmap::Beatmap, beatmapset::Beatmapset, search_filters::SearchFilters},
+    repositories, usecases,
+};
 
 pub async fn fetch(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beatmap>> {
-    let query = Search::new()
-        .query(Query::term("data.id", beatmap_id))
-        .size(1);
-
-    let elastic_response = ctx
-        .database
-        .search(SearchParts::Index(&[&ctx.config.elastic_beatmaps_index]))
-        .body(query)
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("failed to search for beatmap: {}", e))?;
+    if let Some(beatmap) = ctx.beatmap_cache.get(beatmap_id) {
+        return Ok(Some(beatmap));
+    }
 
-    let beatmap: Option<Beatmap> = elastic_response
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("failed to search for beatmap: {}", e))?
-        .pointer("/hits/hits/0/_source")
-        .and_then(|v| serde_json::from_value(v.clone()).ok());
+    // concurrent misses for the same id share a single osu! api call
+    let beatmap = ctx
+        .beatmap_lookups
+        .run(beatmap_id, || lookup(ctx, beatmap_id))
+        .await?;
+
+    if let Some(beatmap) = &beatmap {
+        ctx.beatmap_cache
+            .insert(beatmap_id, beatmap.clone(), beatmap.data.status);
+    }
+
+    Ok(beatmap)
+}
+
+async fn lookup(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beatmap>> {
+    let beatmap = repositories::beatmaps::fetch(ctx, beatmap_id).await?;
 
-    if beatmap.is_some() {
-        return Ok(beatmap);
+    if let Some(beatmap) = beatmap {
+        // hidden beatmaps stay indexed so they aren't fetched from osu! again
+        return Ok(match beatmap.hidden {
+            true => None,
+            false => Some(beatmap),
+        });
     }
 
+    fetch_from_osu(ctx, beatmap_id).await
+}
+
+async fn fetch_from_osu(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beatmap>> {
     let osu_beatmap = repositories::osu::beatmaps::fetch(ctx, beatmap_id)
         .await
         .map_err(|e| anyhow::anyhow!("failed to search for beatmap: {}", e))?;
@@ -40,6 +53,8 @@ pub async fn fetch(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beat
                 updated_at: now,
                 last_checked: now,
                 crawled: false,
+                hidden: false,
+                parsed: None,
             };
 
             repositories::beatmaps::create(ctx, beatmap.clone()).await?;
@@ -48,3 +63,208 @@ pub async fn fetch(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beat
         _ => None,
     })
 }
+
+/// Fetch many beatmaps at once, keyed by id. Ids which can't be found are left out.
+///
+/// Indexed beatmaps are read in a single request, and the rest with osu!'s bulk lookup.
+pub async fn fetch_many(
+    ctx: &Context,
+    beatmap_ids: &[u32],
+) -> anyhow::Result<HashMap<u32, Beatmap>> {
+    let mut beatmaps = HashMap::with_capacity(beatmap_ids.len());
+    let mut uncached = Vec::new();
+
+    for &beatmap_id in beatmap_ids.iter().collect::<HashSet<_>>() {
+        match ctx.beatmap_cache.get(beatmap_id) {
+            Some(beatmap) => {
+                beatmaps.insert(beatmap_id, beatmap);
+            }
+            None => uncached.push(beatmap_id),
+        }
+    }
+
+    let mut indexed = repositories::beatmaps::fetch_many(ctx, &uncached).await?;
+    let mut misses = Vec::new();
+
+    for beatmap_id in uncached {
+        match indexed.remove(&beatmap_id) {
+            Some(beatmap) if beatmap.hidden => {}
+            Some(beatmap) => {
+                ctx.beatmap_cache
+                    .insert(beatmap_id, beatmap.clone(), beatmap.data.status);
+                beatmaps.insert(beatmap_id, beatmap);
+            }
+            None => misses.push(beatmap_id),
+        }
+    }
+
+    for (beatmap_id, beatmap) in fetch_many_from_osu(ctx, misses).await? {
+        ctx.beatmap_cache
+            .insert(beatmap_id, beatmap.clone(), beatmap.data.status);
+        beatmaps.insert(beatmap_id, beatmap);
+    }
+
+    Ok(beatmaps)
+}
+
+/// Fetch beatmaps through osu!'s bulk lookup and store them.
+async fn fetch_many_from_osu(
+    ctx: &Context,
+    beatmap_ids: Vec<u32>,
+) -> anyhow::Result<HashMap<u32, Beatmap>> {
+    let beatmap_ids: Vec<u32> = beatmap_ids
+        .into_iter()
+        .filter(|beatmap_id| !ctx.beatmap_lookups.is_recently_not_found(beatmap_id))
+        .collect();
+
+    if beatmap_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let osu_beatmaps = repositories::osu::beatmaps::bulk_fetch(ctx, beatmap_ids.clone())
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to look up beatmaps: {}", e))?;
+
+    let now = chrono::Utc::now();
+    let beatmaps: HashMap<u32, Beatmap> = osu_beatmaps
+        .into_iter()
+        .map(|osu_beatmap| {
+            let beatmap = Beatmap {
+                data: osu_beatmap,
+                created_at: now,
+                updated_at: now,
+                last_checked: now,
+                crawled: false,
+                hidden: false,
+                parsed: None,
+            };
+
+            (beatmap.data.map_id, beatmap)
+        })
+        .collect();
+
+    for beatmap_id in beatmap_ids {
+        if !beatmaps.contains_key(&beatmap_id) {
+            ctx.beatmap_lookups.remember_not_found(beatmap_id);
+        }
+    }
+
+    if !beatmaps.is_empty() {
+        repositories::beatmaps::bulk_create(ctx, beatmaps.values().cloned().collect()).await?;
+    }
+
+    Ok(beatmaps)
+}
+
+/// Beatmaps picked at a time when picking at random, so NSFW beatmapsets can be skipped.
+const RANDOM_CANDIDATES: u64 = 10;
+
+/// Rounds of candidates to go through before giving up on finding one that isn't NSFW.
+const RANDOM_ROUNDS: usize = 3;
+
+/// Pick a random beatmap matching the filters, along with its beatmapset.
+///
+/// With `per_beatmapset`, every matching beatmapset is as likely as any other, however many
+/// of its difficulties match.
+pub async fn random(
+    ctx: &Context,
+    filters: &SearchFilters,
+    seed: Option<u32>,
+    weighted: bool,
+    per_beatmapset: bool,
+) -> anyhow::Result<Option<(Beatmap, Beatmapset)>> {
+    let seed = seed.unwrap_or_else(|| fastrand::u32(..));
+    let mut excluded_beatmapset_ids = Vec::new();
+
+    for _ in 0..RANDOM_ROUNDS {
+        let candidates = repositories::beatmaps::random(
+            ctx,
+            filters,
+            seed,
+            weighted,
+            per_beatmapset,
+            &excluded_beatmapset_ids,
+            RANDOM_CANDIDATES,
+        )
+        .await?;
+
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        // beatmaps don't always embed their beatmapset, so check nsfw on the beatmapset itself
+        let beatmapset_ids: Vec<u32> = candidates
+            .iter()
+            .map(|beatmap| beatmap.data.mapset_id)
+            .collect();
+        let mut beatmapsets = usecases::beatmapsets::fetch_many(ctx, &beatmapset_ids).await?;
+
+        for beatmap in candidates {
+            let beatmapset_id = beatmap.data.mapset_id;
+
+            match beatmapsets.remove(&beatmapset_id) {
+                Some(beatmapset) if filters.include_nsfw || !beatmapset.data.nsfw => {
+                    return Ok(Some((beatmap, beatmapset)));
+                }
+                _ => {
+                    if !excluded_beatmapset_ids.contains(&beatmapset_id) {
+                        excluded_beatmapset_ids.push(beatmapset_id);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Fetch a beatmap from osu! and store it, regardless of when it was last checked.
+pub async fn refresh(ctx: &Context, beatmap_id: u32) -> anyhow::Result<Option<Beatmap>> {
+    let osu_beatmap = match repositories::osu::beatmaps::fetch(ctx, beatmap_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to refresh beatmap: {}", e))?
+    {
+        Some(osu_beatmap) => osu_beatmap,
+        None => return Ok(None),
+    };
+
+    let now = chrono::Utc::now();
+    let beatmap = match repositories::beatmaps::fetch(ctx, beatmap_id).await? {
+        Some(mut beatmap) => {
+            beatmap.last_checked = now;
+            if osu_beatmap != beatmap.data {
+                beatmap.updated_at = now;
+            }
+            beatmap.data = osu_beatmap;
+
+            repositories::beatmaps::update(ctx, beatmap.clone()).await?;
+            beatmap
+        }
+        None => {
+            let beatmap = Beatmap {
+                data: osu_beatmap,
+                created_at: now,
+                updated_at: now,
+                last_checked: now,
+                crawled: false,
+                hidden: false,
+                parsed: None,
+            };
+
+            repositories::beatmaps::create(ctx, beatmap.clone()).await?;
+            beatmap
+        }
+    };
+
+    ctx.beatmap_lookups.forget(&beatmap_id);
+    ctx.beatmap_cache.invalidate(beatmap_id);
+
+    Ok(Some(beatmap))
+}
+
+pub async fn delete(ctx: &Context, beatmap_id: u32) -> anyhow::Result<()> {
+    repositories::beatmaps::delete(ctx, beatmap_id).await?;
+    ctx.beatmap_cache.invalidate(beatmap_id);
+
+    Ok(())
+}
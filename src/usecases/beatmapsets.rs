@@ -1,33 +1,46 @@
-use crate::{models::beatmapset::Beatmapset, repositories, Context};
+use crate::{
+    models::{beatmapset::Beatmapset, ranked_status::RankedStatus},
+    repositories, usecases, Context,
+};
 use chrono::{TimeZone, Utc};
-use elasticsearch::SearchParts;
-use elasticsearch_dsl::{Query, Search};
+use futures::{stream, StreamExt};
 use rosu_v2::prelude::Beatmapset as OsuBeatmapset;
+use std::collections::{HashMap, HashSet};
 
 pub async fn fetch(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Beatmapset>> {
-    let query = Search::new()
-        .query(Query::term("data.id", beatmapset_id))
-        .size(1);
-
-    let elastic_response = ctx
-        .database
-        .search(SearchParts::Index(&[&ctx.config.elastic_beatmapsets_index]))
-        .body(query)
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("failed to search for beatmapset: {}", e))?;
+    if let Some(beatmapset) = ctx.beatmapset_cache.get(beatmapset_id) {
+        return Ok(Some(beatmapset));
+    }
 
-    let beatmapset: Option<Beatmapset> = elastic_response
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| anyhow::anyhow!("failed to search for beatmapset: {}", e))?
-        .pointer("/hits/hits/0/_source")
-        .and_then(|v| serde_json::from_value(v.clone()).ok());
+    // concurrent misses for the same id share a single osu! api call
+    let beatmapset = ctx
+        .beatmapset_lookups
+        .run(beatmapset_id, || lookup(ctx, beatmapset_id))
+        .await?;
+
+    if let Some(beatmapset) = &beatmapset {
+        ctx.beatmapset_cache
+            .insert(beatmapset_id, beatmapset.clone(), beatmapset.data.status);
+    }
+
+    Ok(beatmapset)
+}
 
-    if beatmapset.is_some() {
-        return Ok(beatmapset);
+async fn lookup(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Beatmapset>> {
+    let beatmapset = repositories::beatmapsets::fetch(ctx, beatmapset_id).await?;
+
+    if let Some(beatmapset) = beatmapset {
+        // hidden beatmapsets stay indexed so they aren't fetched from osu! again
+        return Ok(match beatmapset.hidden {
+            true => None,
+            false => Some(beatmapset),
+        });
     }
 
+    fetch_from_osu(ctx, beatmapset_id).await
+}
+
+async fn fetch_from_osu(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Beatmapset>> {
     let osu_beatmapset = repositories::osu::beatmapsets::fetch(ctx, beatmapset_id)
         .await
         .map_err(|e| anyhow::anyhow!("failed to search for beatmapset: {}", e))?;
@@ -41,6 +54,7 @@ pub async fn fetch(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<B
                 updated_at: now,
                 last_checked: now,
                 crawled: false,
+                hidden: false,
             };
 
             repositories::beatmapsets::create(ctx, beatmapset.clone()).await?;
@@ -50,6 +64,191 @@ pub async fn fetch(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<B
     })
 }
 
+/// Beatmapsets fetched from osu! at once when looking up many.
+const CONCURRENT_OSU_LOOKUPS: usize = 8;
+
+/// Fetch many beatmapsets at once, keyed by id. Ids which can't be found are left out.
+///
+/// Indexed beatmapsets are read in a single request, and only the rest are fetched from osu!.
+pub async fn fetch_many(
+    ctx: &Context,
+    beatmapset_ids: &[u32],
+) -> anyhow::Result<HashMap<u32, Beatmapset>> {
+    let mut beatmapsets = HashMap::with_capacity(beatmapset_ids.len());
+    let mut uncached = Vec::new();
+
+    for &beatmapset_id in beatmapset_ids.iter().collect::<HashSet<_>>() {
+        match ctx.beatmapset_cache.get(beatmapset_id) {
+            Some(beatmapset) => {
+                beatmapsets.insert(beatmapset_id, beatmapset);
+            }
+            None => uncached.push(beatmapset_id),
+        }
+    }
+
+    let mut indexed = repositories::beatmapsets::fetch_many(ctx, &uncached).await?;
+    let mut misses = Vec::new();
+
+    for beatmapset_id in uncached {
+        match indexed.remove(&beatmapset_id) {
+            Some(beatmapset) if beatmapset.hidden => {}
+            Some(beatmapset) => {
+                ctx.beatmapset_cache.insert(
+                    beatmapset_id,
+                    beatmapset.clone(),
+                    beatmapset.data.status,
+                );
+                beatmapsets.insert(beatmapset_id, beatmapset);
+            }
+            None => misses.push(beatmapset_id),
+        }
+    }
+
+    // osu! has no bulk beatmapset lookup, so misses are fetched a few at a time
+    let fetched: Vec<(u32, anyhow::Result<Option<Beatmapset>>)> = stream::iter(misses)
+        .map(|beatmapset_id| async move {
+            let beatmapset = ctx
+                .beatmapset_lookups
+                .run(beatmapset_id, || fetch_from_osu(ctx, beatmapset_id))
+                .await;
+
+            (beatmapset_id, beatmapset)
+        })
+        .buffer_unordered(CONCURRENT_OSU_LOOKUPS)
+        .collect()
+        .await;
+
+    for (beatmapset_id, beatmapset) in fetched {
+        match beatmapset {
+            Ok(Some(beatmapset)) => {
+                ctx.beatmapset_cache.insert(
+                    beatmapset_id,
+                    beatmapset.clone(),
+                    beatmapset.data.status,
+                );
+                beatmapsets.insert(beatmapset_id, beatmapset);
+            }
+            Ok(None) => {}
+            // not found and unreachable mean different things to the client, so don't mix them
+            Err(e) => anyhow::bail!("failed to look up beatmapset {}: {}", beatmapset_id, e),
+        }
+    }
+
+    Ok(beatmapsets)
+}
+
+/// Fetch a beatmapset from osu! and store it, regardless of when it was last checked.
+pub async fn refresh(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Beatmapset>> {
+    let osu_beatmapset = match repositories::osu::beatmapsets::fetch(ctx, beatmapset_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to refresh beatmapset: {}", e))?
+    {
+        Some(osu_beatmapset) => osu_beatmapset,
+        None => return Ok(None),
+    };
+
+    let now = chrono::Utc::now();
+    let beatmapset = match repositories::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(mut beatmapset) => {
+            beatmapset.last_checked = now;
+            if osu_beatmapset != beatmapset.data {
+                beatmapset.updated_at = now;
+            }
+            beatmapset.data = osu_beatmapset;
+
+            repositories::beatmapsets::update(ctx, beatmapset.clone()).await?;
+            beatmapset
+        }
+        None => {
+            let beatmapset = Beatmapset {
+                data: osu_beatmapset,
+                created_at: now,
+                updated_at: now,
+                last_checked: now,
+                crawled: false,
+                hidden: false,
+            };
+
+            repositories::beatmapsets::create(ctx, beatmapset.clone()).await?;
+            beatmapset
+        }
+    };
+
+    ctx.beatmapset_lookups.forget(&beatmapset_id);
+    ctx.beatmapset_cache.invalidate(beatmapset_id);
+
+    Ok(Some(beatmapset))
+}
+
+/// Returns false if the beatmapset isn't indexed.
+pub async fn set_hidden(ctx: &Context, beatmapset_id: u32, hidden: bool) -> anyhow::Result<bool> {
+    let beatmapset = match repositories::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset,
+        None => return Ok(false),
+    };
+
+    repositories::beatmapsets::set_hidden(ctx, beatmapset_id, hidden).await?;
+    repositories::beatmaps::set_hidden_by_beatmapset(ctx, beatmapset_id, hidden).await?;
+    ctx.beatmapset_lookups.forget(&beatmapset_id);
+    ctx.beatmapset_cache.invalidate(beatmapset_id);
+    forget_beatmaps(ctx, &beatmap_ids(&beatmapset));
+
+    Ok(true)
+}
+
+pub async fn delete(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<()> {
+    let beatmap_ids = repositories::beatmapsets::fetch(ctx, beatmapset_id)
+        .await?
+        .map(|beatmapset| beatmap_ids(&beatmapset))
+        .unwrap_or_default();
+
+    repositories::beatmapsets::delete(ctx, beatmapset_id).await?;
+    repositories::beatmaps::delete_by_beatmapset(ctx, beatmapset_id).await?;
+    ctx.beatmapset_lookups.forget(&beatmapset_id);
+    ctx.beatmapset_cache.invalidate(beatmapset_id);
+    forget_beatmaps(ctx, &beatmap_ids);
+
+    repositories::archives::delete(ctx, beatmapset_id, false).await?;
+    repositories::archives::delete(ctx, beatmapset_id, true).await?;
+    repositories::stored_archives::delete(ctx, beatmapset_id).await?;
+    usecases::images::delete_renders(ctx, beatmapset_id, &beatmap_ids).await?;
+    repositories::previews::delete(ctx, beatmapset_id).await?;
+
+    Ok(())
+}
+
+fn beatmap_ids(beatmapset: &Beatmapset) -> Vec<u32> {
+    beatmapset
+        .data
+        .maps
+        .iter()
+        .flatten()
+        .map(|beatmap| beatmap.map_id)
+        .collect()
+}
+
+/// Drop a beatmapset's difficulties from memory, so their new state is read from the index.
+fn forget_beatmaps(ctx: &Context, beatmap_ids: &[u32]) {
+    for beatmap_id in beatmap_ids {
+        ctx.beatmap_lookups.forget(beatmap_id);
+        ctx.beatmap_cache.invalidate(*beatmap_id);
+    }
+}
+
+/// Statuses matching one of osu!direct's ranked filters (`r`), as sent by the osu! client.
+pub fn direct_statuses(direct_status: i8) -> Option<Vec<RankedStatus>> {
+    match direct_status {
+        // "Ranked" and "Ranked (Played)" cover every status with a leaderboard
+        0 | 7 => Some(RankedStatus::HAS_LEADERBOARD.to_vec()),
+        2 => Some(RankedStatus::IN_PROGRESS.to_vec()),
+        3 => Some(vec![RankedStatus::Qualified]),
+        4 => Some(vec![RankedStatus::All]),
+        5 => Some(vec![RankedStatus::Graveyard]),
+        8 => Some(vec![RankedStatus::Loved]),
+        _ => None,
+    }
+}
+
 pub fn format_to_direct(beatmapsets: Vec<OsuBeatmapset>) -> String {
     let mut response_lines: Vec<String> = Vec::with_capacity(beatmapsets.len());
 
@@ -73,7 +272,9 @@ pub fn format_to_direct(beatmapsets: Vec<OsuBeatmapset>) -> String {
             .collect::<Vec<String>>()
             .join(",");
 
-        let last_updated = Utc.timestamp(beatmapset.last_updated.unix_timestamp(), 0);
+        let last_updated = Utc
+            .timestamp_opt(beatmapset.last_updated.unix_timestamp(), 0)
+            .unwrap();
         let last_updated_str = last_updated.to_rfc3339();
         let beatmapset_string = format!(
             "{}.osz|{}|{}|{}|{}|10.0|{}|{}|0|{}|0|0|0|{}",
@@ -81,7 +282,7 @@ pub fn format_to_direct(beatmapsets: Vec<OsuBeatmapset>) -> String {
             beatmapset.artist,
             beatmapset.title,
             beatmapset.creator_name,
-            beatmapset.status as u8,
+            beatmapset.status as i8,
             last_updated_str,
             beatmapset.mapset_id,
             beatmapset.video as u8,
@@ -0,0 +1,112 @@
+use md5::{Digest, Md5};
+use rosu_v2::prelude::{BeatmapDifficultyAttributes, GameMode, GameMods};
+
+use crate::{
+    helpers::{archive, difficulty, osu_file},
+    models::{
+        beatmap::Beatmap,
+        difficulty_attributes::{self, DifficultyAttributes},
+    },
+    repositories, usecases, Context,
+};
+
+/// Get the difficulty attributes of a beatmap for a mode and mods, from the index if they were
+/// calculated for the current version of the beatmap, otherwise calculated from its `.osu` file
+/// in the stored archive.
+pub async fn fetch(
+    ctx: &Context,
+    beatmap: &Beatmap,
+    mode: GameMode,
+    mods: GameMods,
+) -> anyhow::Result<Option<BeatmapDifficultyAttributes>> {
+    let beatmap_id = beatmap.data.map_id;
+    let mods = difficulty_attributes::difficulty_mods(mods);
+    let document_id = DifficultyAttributes::document_id(beatmap_id, mode, mods.bits());
+
+    if let Some(cached) = repositories::difficulty_attributes::fetch(ctx, &document_id).await? {
+        if cached.checksum == beatmap.data.checksum {
+            return Ok(Some(cached.data));
+        }
+    }
+
+    let beatmapset_id = beatmap.data.mapset_id;
+    let beatmapset = match usecases::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset,
+        None => return Ok(None),
+    };
+
+    let osu_archive =
+        match usecases::archives::fetch_any(ctx, beatmapset_id, beatmapset.data.video).await? {
+            Some(osu_archive) => osu_archive,
+            None => return Ok(None),
+        };
+
+    let checksum = beatmap.data.checksum.clone();
+    let version = beatmap.data.version.clone();
+    let calculated = tokio::task::spawn_blocking(move || {
+        let (content, current) = match osu_file_of(&osu_archive, beatmap_id, checksum, &version)? {
+            Some(osu_file) => osu_file,
+            None => return anyhow::Ok(None),
+        };
+
+        let attributes = difficulty::calculate(&difficulty::parse(&content), mode, mods);
+        Ok(attributes.map(|attributes| (attributes, current)))
+    })
+    .await??;
+
+    let (attributes, current) = match calculated {
+        Some(calculated) => calculated,
+        None => return Ok(None),
+    };
+
+    // attributes of an outdated file are only good until the archive is refreshed
+    if current {
+        let document = DifficultyAttributes {
+            beatmap_id,
+            mode,
+            mods: mods.bits(),
+            checksum: beatmap.data.checksum.clone(),
+            data: attributes.clone(),
+            created_at: chrono::Utc::now(),
+        };
+
+        repositories::difficulty_attributes::store(ctx, document).await?;
+    }
+
+    Ok(Some(attributes))
+}
+
+/// Find a beatmap's `.osu` file in its beatmapset's archive, by its checksum or otherwise by the
+/// id or version it names, along with whether it is the current version of the beatmap.
+fn osu_file_of(
+    osu_archive: &[u8],
+    beatmap_id: u32,
+    checksum: Option<String>,
+    version: &str,
+) -> anyhow::Result<Option<(String, bool)>> {
+    let osu_files = archive::read_files_with_extension(osu_archive, ".osu")?;
+
+    if let Some(checksum) = checksum {
+        let matching = osu_files
+            .iter()
+            .find(|(_, contents)| format!("{:x}", Md5::digest(contents)) == checksum);
+        if let Some((_, contents)) = matching {
+            return Ok(Some((String::from_utf8_lossy(contents).into_owned(), true)));
+        }
+    }
+
+    let mut by_version = None;
+    for (_, contents) in osu_files {
+        let content = String::from_utf8_lossy(&contents).into_owned();
+        let osu_file = osu_file::parse(&content);
+
+        if osu_file.beatmap_id == Some(beatmap_id) {
+            return Ok(Some((content, false)));
+        }
+        if by_version.is_none() && osu_file.version.as_deref() == Some(version) {
+            by_version = Some((content, false));
+        }
+    }
+
+    Ok(by_version)
+}
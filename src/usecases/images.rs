@@ -0,0 +1,209 @@
+use axum::body::Bytes;
+use rosu_v2::prelude::Beatmap as OsuBeatmap;
+
+use crate::{
+    helpers::{archive, osu_file, render},
+    models::image::{ImageFormat, ImageKind, ImageSize},
+    repositories, usecases, Context,
+};
+
+/// Get a beatmapset's thumbnail, the background of its first difficulty which has one.
+pub async fn thumbnail(
+    ctx: &Context,
+    beatmapset_id: u32,
+    size: ImageSize,
+    format: ImageFormat,
+) -> anyhow::Result<Option<Bytes>> {
+    let kind = ImageKind::Thumbnail;
+    if let Some(render) = repositories::renders::get(ctx, kind, beatmapset_id, size, format).await?
+    {
+        return Ok(Some(render));
+    }
+    if repositories::renders::is_missing(ctx, kind, beatmapset_id).await? {
+        return Ok(None);
+    }
+
+    // concurrent requests for the same image share a single render
+    ctx.image_renders
+        .run((kind, beatmapset_id, size, format), || {
+            render_thumbnail(ctx, beatmapset_id, size, format)
+        })
+        .await
+}
+
+async fn render_thumbnail(
+    ctx: &Context,
+    beatmapset_id: u32,
+    size: ImageSize,
+    format: ImageFormat,
+) -> anyhow::Result<Option<Bytes>> {
+    let beatmapset = match usecases::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset,
+        None => return Ok(None),
+    };
+
+    let osu_archive =
+        match usecases::archives::fetch_any(ctx, beatmapset_id, beatmapset.data.video).await? {
+            Some(osu_archive) => osu_archive,
+            None => return Ok(None),
+        };
+
+    render_background(ctx, beatmapset_id, osu_archive, None, size, format).await
+}
+
+/// Get the background of a difficulty.
+pub async fn background(
+    ctx: &Context,
+    beatmap_id: u32,
+    size: ImageSize,
+    format: ImageFormat,
+) -> anyhow::Result<Option<Bytes>> {
+    let kind = ImageKind::Background;
+    if let Some(render) = repositories::renders::get(ctx, kind, beatmap_id, size, format).await? {
+        return Ok(Some(render));
+    }
+    if repositories::renders::is_missing(ctx, kind, beatmap_id).await? {
+        return Ok(None);
+    }
+
+    ctx.image_renders
+        .run((kind, beatmap_id, size, format), || {
+            render_difficulty_background(ctx, beatmap_id, size, format)
+        })
+        .await
+}
+
+async fn render_difficulty_background(
+    ctx: &Context,
+    beatmap_id: u32,
+    size: ImageSize,
+    format: ImageFormat,
+) -> anyhow::Result<Option<Bytes>> {
+    let beatmap = match usecases::beatmaps::fetch(ctx, beatmap_id).await? {
+        Some(beatmap) => beatmap,
+        None => return Ok(None),
+    };
+
+    let beatmapset_id = beatmap.data.mapset_id;
+    let beatmapset = match usecases::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset,
+        None => return Ok(None),
+    };
+
+    let osu_archive =
+        match usecases::archives::fetch_any(ctx, beatmapset_id, beatmapset.data.video).await? {
+            Some(osu_archive) => osu_archive,
+            None => return Ok(None),
+        };
+
+    render_background(
+        ctx,
+        beatmapset_id,
+        osu_archive,
+        Some(beatmap.data),
+        size,
+        format,
+    )
+    .await
+}
+
+/// Render the background of a difficulty, or the thumbnail of its beatmapset when none is given,
+/// counting the render toward the size of the beatmapset's stored archive.
+async fn render_background(
+    ctx: &Context,
+    beatmapset_id: u32,
+    osu_archive: Bytes,
+    beatmap: Option<OsuBeatmap>,
+    size: ImageSize,
+    format: ImageFormat,
+) -> anyhow::Result<Option<Bytes>> {
+    let (kind, id) = match &beatmap {
+        Some(beatmap) => (ImageKind::Background, beatmap.map_id),
+        None => (ImageKind::Thumbnail, beatmapset_id),
+    };
+
+    let rendered = tokio::task::spawn_blocking(move || {
+        let background = match read_background(&osu_archive, beatmap.as_ref())? {
+            Some(background) => background,
+            None => return anyhow::Ok(None),
+        };
+
+        // an image that can't be decoded won't render any better next time
+        match render::render(&background, size, format) {
+            Ok(rendered) => Ok(Some(rendered)),
+            Err(e) => {
+                log::warn!(
+                    "failed to render the background of {:?} {}: {}",
+                    kind,
+                    id,
+                    e
+                );
+                Ok(None)
+            }
+        }
+    })
+    .await??;
+
+    let rendered = match rendered {
+        Some(rendered) => rendered,
+        None => {
+            // the archive only changes when it is refreshed, which deletes this again
+            repositories::renders::store_missing(ctx, kind, id).await?;
+            return Ok(None);
+        }
+    };
+
+    repositories::renders::store(ctx, kind, id, size, format, &rendered).await?;
+    usecases::archives::count_made(ctx, beatmapset_id, rendered.len() as u64).await;
+
+    Ok(Some(Bytes::from(rendered)))
+}
+
+/// Read the background image named in the events of a difficulty's `.osu` file, or of the first
+/// difficulty that has one when none is given.
+fn read_background(
+    osu_archive: &[u8],
+    beatmap: Option<&OsuBeatmap>,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    for (_, contents) in archive::read_files_with_extension(osu_archive, ".osu")? {
+        let osu_file = osu_file::parse(&String::from_utf8_lossy(&contents));
+
+        if let Some(beatmap) = beatmap {
+            // older files don't name their beatmap id, so fall back to the difficulty name
+            let is_beatmap = match osu_file.beatmap_id {
+                Some(beatmap_id) => beatmap_id == beatmap.map_id,
+                None => osu_file.version.as_deref() == Some(beatmap.version.as_str()),
+            };
+
+            if !is_beatmap {
+                continue;
+            }
+        }
+
+        let background_filename = match &osu_file.parsed.background_filename {
+            Some(background_filename) => background_filename,
+            None => continue,
+        };
+
+        if let Some(background) = archive::read_file(osu_archive, background_filename)? {
+            return Ok(Some(background));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Delete the renders of a beatmapset and its difficulties, e.g. after its archive changed.
+pub async fn delete_renders(
+    ctx: &Context,
+    beatmapset_id: u32,
+    beatmap_ids: &[u32],
+) -> anyhow::Result<()> {
+    repositories::renders::delete(ctx, ImageKind::Thumbnail, beatmapset_id).await?;
+
+    for &beatmap_id in beatmap_ids {
+        repositories::renders::delete(ctx, ImageKind::Background, beatmap_id).await?;
+    }
+
+    Ok(())
+}
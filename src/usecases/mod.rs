@@ -1,2 +1,7 @@
+pub mod api_keys;
+pub mod archives;
 pub mod beatmaps;
 pub mod beatmapsets;
+pub mod difficulty_attributes;
+pub mod images;
+pub mod previews;
@@ -0,0 +1,120 @@
+use axum::body::Bytes;
+
+use crate::{
+    helpers::{archive, audio, mp3, osu_file},
+    repositories, usecases, Context,
+};
+
+/// Length of a preview in milliseconds, matching osu!'s own previews.
+const PREVIEW_LENGTH: u32 = 10_000;
+
+/// Get a beatmapset's audio preview, cut from its song at the preview time of its `.osu` files
+/// and encoded as a low bitrate mono MP3.
+pub async fn fetch(ctx: &Context, beatmapset_id: u32) -> anyhow::Result<Option<Bytes>> {
+    if let Some(preview) = repositories::previews::get(ctx, beatmapset_id).await? {
+        return Ok(Some(preview));
+    }
+    if repositories::previews::is_missing(ctx, beatmapset_id).await? {
+        return Ok(None);
+    }
+
+    let beatmapset = match usecases::beatmapsets::fetch(ctx, beatmapset_id).await? {
+        Some(beatmapset) => beatmapset,
+        None => return Ok(None),
+    };
+
+    let osu_archive =
+        match usecases::archives::fetch_any(ctx, beatmapset_id, beatmapset.data.video).await? {
+            Some(osu_archive) => osu_archive,
+            None => return Ok(None),
+        };
+
+    let preview = tokio::task::spawn_blocking(move || make_preview(&osu_archive)).await??;
+
+    let preview = match preview {
+        Some(preview) => preview,
+        None => {
+            // the archive only changes when it is refreshed, which deletes this again
+            repositories::previews::store_missing(ctx, beatmapset_id).await?;
+            return Ok(None);
+        }
+    };
+
+    repositories::previews::store(ctx, beatmapset_id, &preview).await?;
+    usecases::archives::count_made(ctx, beatmapset_id, preview.len() as u64).await;
+
+    Ok(Some(Bytes::from(preview)))
+}
+
+/// Make the preview out of the audio named by the first `.osu` file whose audio can be decoded.
+fn make_preview(osu_archive: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    for (_, contents) in archive::read_files_with_extension(osu_archive, ".osu")? {
+        let osu_file = osu_file::parse(&String::from_utf8_lossy(&contents));
+
+        let audio_filename = match &osu_file.parsed.audio_filename {
+            Some(audio_filename) => audio_filename,
+            None => continue,
+        };
+
+        let audio = match archive::read_file(osu_archive, audio_filename)? {
+            Some(audio) => audio,
+            None => continue,
+        };
+
+        // a negative preview time means the map doesn't set one
+        let preview_time = osu_file
+            .parsed
+            .preview_time
+            .and_then(|preview_time| u32::try_from(preview_time).ok());
+
+        let extension = audio_filename
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase());
+
+        if let Some(samples) = preview_samples(audio, extension.as_deref(), preview_time) {
+            return Ok(Some(mp3::encode(&samples)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Decode the section of a song a preview plays, resampled for the encoder.
+fn preview_samples(
+    audio: Vec<u8>,
+    extension: Option<&str>,
+    start_ms: Option<u32>,
+) -> Option<Vec<f32>> {
+    let length = PREVIEW_LENGTH as u64 * 1000;
+
+    // MP3s are cut along their frames first, so only the preview has to be decoded
+    let section = match extension {
+        Some("mp3") => mp3::cut(&audio, start_ms, PREVIEW_LENGTH),
+        _ => None,
+    };
+
+    let (decoded, start) = match section {
+        Some(section) => (
+            audio::decode(section.audio, Some("mp3"), None)?,
+            section.lead,
+        ),
+        None => {
+            let limit = start_ms.map(|start_ms| start_ms as u64 * 1000 + length);
+            let decoded = audio::decode(audio, extension, limit)?;
+            let start = audio::section_start(decoded.duration(), start_ms, PREVIEW_LENGTH);
+
+            (decoded, start)
+        }
+    };
+
+    let section = decoded.section(start, length);
+    if section.is_empty() {
+        return None;
+    }
+
+    Some(audio::resample(
+        section,
+        decoded.sample_rate,
+        mp3::SAMPLE_RATE,
+    ))
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use crate::{
+    helpers::elastic, models::archive_verification::ArchiveVerification, repositories, usecases,
+    Context,
+};
+
+/// Check every stored archive against its indexed checksums, re-downloading stale ones and
+/// parsing the beatmaps of archives stored before they were parsed, and report the results.
+pub async fn serve(context: Context) -> anyhow::Result<()> {
+    if context.config.archive_path.is_none() {
+        anyhow::bail!("archive storage is not configured");
+    }
+
+    elastic::create_index_if_not_exists(&context.database, &context.config.elastic_archives_index)
+        .await?;
+
+    let beatmapset_ids = repositories::archives::stored_ids(&context).await?;
+
+    log::info!("verifying {} stored archives", beatmapset_ids.len());
+
+    // downloads are spread out like the prefetcher's, to stay well within osu!'s limits
+    let download_interval =
+        Duration::from_secs(60) / context.config.verify_downloads_per_minute.max(1);
+
+    let (mut valid, mut stale, mut unverifiable, mut failed) = (0, 0, 0, 0);
+
+    for beatmapset_id in beatmapset_ids {
+        match usecases::archives::verify(&context, beatmapset_id).await {
+            Ok(ArchiveVerification::Valid) => valid += 1,
+            Ok(ArchiveVerification::Stale) => {
+                stale += 1;
+                tokio::time::sleep(download_interval).await;
+            }
+            // removed since it was listed
+            Ok(ArchiveVerification::NotStored) => {}
+            Ok(ArchiveVerification::Unverifiable) => unverifiable += 1,
+            Err(e) => {
+                log::error!("failed to verify beatmapset {}: {}", beatmapset_id, e);
+                failed += 1;
+
+                // the failure may have been downloading it again
+                tokio::time::sleep(download_interval).await;
+            }
+        }
+    }
+
+    log::info!(
+        "verified archives: {} valid, {} stale and re-downloaded, {} unverifiable, {} failed",
+        valid,
+        stale,
+        unverifiable,
+        failed
+    );
+
+    Ok(())
+}